@@ -2,8 +2,15 @@ use std::fs::{self, File};
 use std::io::Write;
 use std::num::ParseIntError;
 
-use benchmarking_lib::generators::{GenOutput, define_dummy_trait, gen, gen_analysis_pass, gen_read_only_func, helper_gen_clarity_list_type, helper_generate_rand_char_string, helper_make_value_for_sized_type_sig, make_sized_contracts_map, make_sized_tuple_sigs_map, make_sized_type_sig_map, make_sized_values_map, make_type_sig_list_of_size};
-use benchmarking_lib::headers_db::{SimHeadersDB, TestHeadersDB};
+use benchmarking_lib::allocations::{self, AllocationCounts, CountingAllocator, Metric};
+use benchmarking_lib::burn_state_db::SimBurnStateDB;
+use benchmarking_lib::corpus;
+use benchmarking_lib::cost_probe::{self, ChargeRecord, Charges, ProbeMode, SizeCheck, PROBE_CRITERION_DIR};
+use benchmarking_lib::generators::{GenOutput, define_dummy_trait, gen, gen_analysis_pass, gen_read_only_func, helper_gen_clarity_list_type, helper_generate_rand_char_string, helper_make_value_for_sized_type_sig, make_sized_contract, make_sized_tuple_sigs_map, make_sized_type_sig_map, make_sized_values_map, make_type_sig_list_of_size};
+use benchmarking_lib::headers_db::{self, InstrumentedHeadersDB, SimHeadersDB, TestHeadersDB, DEFAULT_CHAINSTATE_PATH, MIN_BLOCK_COUNT};
+use benchmarking_lib::registry::{self, BenchSpec, CostFunction, INPUT_SIZES};
+use benchmarking_lib::run_metadata::{self, BenchmarkRecord, Environment, RunMetadata};
+use benchmarking_lib::storage::{self, StorageBackend};
 use blockstack_lib::address::AddressHashMode;
 use blockstack_lib::chainstate::stacks::db::StacksChainState;
 use blockstack_lib::chainstate::stacks::{
@@ -43,7 +50,7 @@ use blockstack_lib::vm::costs::cost_functions::{AnalysisCostFunction, ClarityCos
 use blockstack_lib::vm::costs::{CostTracker, ExecutionCost, LimitedCostTracker};
 use blockstack_lib::vm::database::clarity_store::NullBackingStore;
 use blockstack_lib::vm::database::{
-    ClarityDatabase, HeadersDB, NULL_BURN_STATE_DB, NULL_HEADER_DB, ClaritySerializable
+    ClarityBackingStore, ClarityDatabase, HeadersDB, NULL_BURN_STATE_DB, NULL_HEADER_DB, ClaritySerializable
 };
 use blockstack_lib::vm::functions::crypto::special_principal_of;
 use blockstack_lib::vm::representations::depth_traverse;
@@ -53,49 +60,339 @@ use blockstack_lib::vm::types::signatures::TypeSignature::{
 use blockstack_lib::vm::types::signatures::{TupleTypeSignature, TypeSignature};
 use blockstack_lib::vm::types::{FunctionSignature, FunctionType, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData, TraitIdentifier, SequenceSubtype, BufferLength};
 use blockstack_lib::vm::{CallStack, ClarityName, Environment, LocalContext, SymbolicExpression, Value, apply, ast, bench_create_ft_in_context, bench_create_map_in_context, bench_create_nft_in_context, bench_create_var_in_context, eval_all, lookup_function, lookup_variable};
-use criterion::measurement::WallTime;
+use criterion::measurement::{Measurement, ValueFormatter, WallTime};
 use criterion::{
-    criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+    criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
 };
 use lazy_static::lazy_static;
 use rand::prelude::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{env, fs};
 // use secp256k1::serde::Serialize;
 
-// for when input size is the number of elements
-const INPUT_SIZES: [u64; 8] = [1, 2, 8, 16, 32, 64, 128, 256];
-const MORE_INPUT_SIZES: [u64; 12] = [1, 2, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
-
-// for when input size is the size of the data
-const INPUT_SIZES_DATA: [u64; 8] = [22, 1000, 40000, 160000, 360000, 640000, 1000000, 1100000];
-
-// for when input size is the size of the data, but with a smaller max value
-const INPUT_SIZES_DATA_SMALL: [u64; 8] = [17, 100, 500, 1000, 5000, 10000, 50000, 500000];
-
-// input sizes for arithmetic functions
-const INPUT_SIZES_ARITHMETIC: [u64; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
-
-const INPUT_SIZES_ANALYSIS_PASS: [u64; 6] = [1, 2, 8, 16, 32, 64];
-
-// scaling factor for code generators
-const SCALE: u16 = 75;
+// counts nothing unless `COST_BENCH_MEASUREMENT` asks for allocations, see `benches`
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 lazy_static! {
+    // seed for every code generator in this run, see `generator_seed`
+    pub static ref SEED: u64 = generator_seed();
+    // store every storage-touching benchmark runs against, see `storage_backend`
+    pub static ref STORAGE: StorageBackend = storage_backend();
+    // what every benchmark measures, see `Metered`
+    pub static ref METRIC: Metric = Metric::from_env();
+    // delay added to every header lookup in `run_bench`, see `headers_latency`
+    pub static ref HEADERS_LATENCY: Duration = headers_latency();
+    // burn state `run_bench` reads, see `build_burn_state`
+    pub static ref BURN_STATE_PATH: PathBuf = build_burn_state();
+    // benchmarks whose input size the VM disagreed with, when checking sizes
+    pub static ref SIZE_CHECK_FAILURES: Mutex<Vec<String>> = Mutex::new(vec![]);
+    // every benchmark's charges, when recording charges
+    pub static ref CHARGE_RECORDS: Mutex<Vec<ChargeRecord>> = Mutex::new(vec![]);
+    // where generated code is written, if anywhere, see `bench_gen`
+    pub static ref CORPUS_EXPORT_DIR: Option<PathBuf> = corpus::export_dir_from_env();
+    // where code is read from instead of generating it, if anywhere, see `bench_gen`
+    pub static ref CORPUS_REPLAY_DIR: Option<PathBuf> = corpus::replay_dir_from_env();
     pub static ref SIZED_VALUES: HashMap<u64, Value> = make_sized_values_map(INPUT_SIZES.to_vec());
-    pub static ref SIZED_CONTRACTS: HashMap<u64, String> =
-        make_sized_contracts_map(INPUT_SIZES.to_vec());
+    pub static ref SIZED_CONTRACTS: HashMap<u64, String> = make_sized_contracts();
     // The size of the TupleTypeSignature is measured by the length of its type map
     pub static ref SIZED_TUPLE_SIG: HashMap<u64, TupleTypeSignature> =
-        make_sized_tuple_sigs_map(INPUT_SIZES.to_vec());
+        make_sized_tuple_sigs_map(INPUT_SIZES.to_vec(), &mut bench_rng());
     pub static ref SIZED_TYPE_SIG: HashMap<u64, TypeSignature> =
-        make_sized_type_sig_map(INPUT_SIZES.to_vec());
+        make_sized_type_sig_map(INPUT_SIZES.to_vec(), &mut bench_rng());
     pub static ref TYPE_SIG_LIST: HashMap<u64, Vec<TypeSignature>> =
         make_type_sig_list_of_size(INPUT_SIZES.to_vec());
 }
 
+/// Criterion measurement of `METRIC`: wall time as `WallTime` measures it, or the
+/// allocations `CountingAllocator` counts. Every benchmark group uses it, so any
+/// benchmark can measure allocations.
+struct Metered {
+    metric: Metric,
+    wall_time: WallTime,
+    formatter: CountFormatter,
+}
+
+impl Metered {
+    fn new(metric: Metric) -> Metered {
+        let (unit, per_input) = metric.units().unwrap_or(("", ""));
+        Metered {
+            metric,
+            wall_time: WallTime,
+            formatter: CountFormatter { unit, per_input },
+        }
+    }
+}
+
+impl Measurement for Metered {
+    type Intermediate = (AllocationCounts, Instant);
+    type Value = f64;
+
+    fn start(&self) -> Self::Intermediate {
+        (AllocationCounts::now(), Instant::now())
+    }
+
+    fn end(&self, (before, start): Self::Intermediate) -> f64 {
+        let elapsed = start.elapsed();
+        let counts = AllocationCounts::now().since(&before);
+        match self.metric {
+            Metric::WallTime => elapsed.as_nanos() as f64,
+            Metric::Allocations => counts.allocations as f64,
+            Metric::AllocatedBytes => counts.bytes as f64,
+        }
+    }
+
+    fn add(&self, v1: &f64, v2: &f64) -> f64 {
+        v1 + v2
+    }
+
+    fn zero(&self) -> f64 {
+        0.0
+    }
+
+    fn to_f64(&self, value: &f64) -> f64 {
+        *value
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        match self.metric {
+            Metric::WallTime => self.wall_time.formatter(),
+            _ => &self.formatter,
+        }
+    }
+}
+
+/// Formats counts unscaled, so estimates stay in allocations or bytes for fitting.
+struct CountFormatter {
+    unit: &'static str,
+    per_input: &'static str,
+}
+
+impl ValueFormatter for CountFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        self.unit
+    }
+
+    fn scale_throughputs(&self, _typical_value: f64, throughput: &Throughput, values: &mut [f64]) -> &'static str {
+        let input = match throughput {
+            Throughput::Bytes(n) | Throughput::Elements(n) => *n as f64,
+        };
+        for value in values {
+            *value /= input;
+        }
+        self.per_input
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        self.unit
+    }
+}
+
+/// Directory criterion writes its results to, resolved the same way criterion does.
+/// Probe runs always write to `PROBE_CRITERION_DIR`, see `benches`.
+fn criterion_output_dir() -> PathBuf {
+    if ProbeMode::from_env() != ProbeMode::Off {
+        PathBuf::from(PROBE_CRITERION_DIR)
+    } else if let Some(dir) = env::var_os("CRITERION_HOME") {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("CARGO_TARGET_DIR") {
+        PathBuf::from(dir).join("criterion")
+    } else {
+        PathBuf::from("target/criterion")
+    }
+}
+
+/// Seed used for all generated code in this run. Taken from `COST_BENCH_SEED` if set,
+/// otherwise drawn at random. Either way it is printed and written to
+/// `generator_seed` in the criterion output directory, so any data point can be
+/// regenerated byte-for-byte by re-running with `COST_BENCH_SEED` set to it.
+///
+/// When replaying a corpus, the seed is the one the corpus was generated with, so
+/// setup code seeded from it matches too.
+fn generator_seed() -> u64 {
+    let env_seed = env::var("COST_BENCH_SEED").ok().map(|seed| {
+        seed.parse()
+            .expect("COST_BENCH_SEED must be an unsigned 64-bit integer")
+    });
+    let corpus_seed = CORPUS_REPLAY_DIR.as_ref().and_then(|dir| {
+        corpus::seed(dir).unwrap_or_else(|e| panic!("Invalid corpus: {}", e))
+    });
+    let seed = match (env_seed, corpus_seed) {
+        (Some(env_seed), Some(corpus_seed)) if env_seed != corpus_seed => panic!(
+            "COST_BENCH_SEED is {}, but the replayed corpus was generated with seed {}",
+            env_seed, corpus_seed
+        ),
+        (Some(seed), _) | (None, Some(seed)) => seed,
+        (None, None) => thread_rng().gen(),
+    };
+    println!("generator seed: {}", seed);
+
+    let out_dir = criterion_output_dir();
+    fs::create_dir_all(&out_dir).expect("failed to create criterion output directory");
+    fs::write(out_dir.join("generator_seed"), format!("{}\n", seed))
+        .expect("failed to record generator seed");
+    seed
+}
+
+/// The storage backend selected by `COST_BENCH_STORAGE`, recorded next to the results
+/// since it isn't part of any benchmark's name.
+fn storage_backend() -> StorageBackend {
+    let backend = StorageBackend::from_env();
+    println!("storage backend: {}", backend);
+
+    let out_dir = criterion_output_dir();
+    fs::create_dir_all(&out_dir).expect("failed to create criterion output directory");
+    fs::write(out_dir.join("storage_backend"), format!("{}\n", backend))
+        .expect("failed to record storage backend");
+    backend
+}
+
+/// The header lookup latency selected by `COST_BENCH_HEADERS_LATENCY_US`, recorded next
+/// to the results like the storage backend.
+fn headers_latency() -> Duration {
+    let latency = headers_db::latency_from_env();
+    println!("header lookup latency: {}us", latency.as_micros());
+
+    let out_dir = criterion_output_dir();
+    fs::create_dir_all(&out_dir).expect("failed to create criterion output directory");
+    fs::write(out_dir.join("headers_latency_us"), format!("{}\n", latency.as_micros()))
+        .expect("failed to record header lookup latency");
+    latency
+}
+
+/// Records this run's environment, settings and benchmarks in `run_metadata.json` in
+/// the criterion output directory, see `run_metadata`.
+fn record_run(specs: &[BenchSpec]) {
+    let run = RunMetadata {
+        environment: Environment::current(),
+        generator_seed: *SEED,
+        storage_backend: STORAGE.to_string(),
+        measurement: METRIC.to_string(),
+        headers_latency_us: HEADERS_LATENCY.as_micros() as u64,
+        benchmarks: specs
+            .iter()
+            .map(|spec| {
+                let record = BenchmarkRecord {
+                    scale: spec.scale,
+                    input_sizes: spec.input_sizes(),
+                };
+                (spec.name(), record)
+            })
+            .collect(),
+    };
+    run_metadata::append_run(&criterion_output_dir(), &run)
+        .unwrap_or_else(|e| panic!("Failed to record run metadata: {}", e));
+}
+
+/// Builds sortitions for the chainstate's blocks in `burnchain.sqlite` in the criterion
+/// output directory, replacing any from an earlier run.
+fn build_burn_state() -> PathBuf {
+    let path = criterion_output_dir().join("burnchain.sqlite");
+    if path.exists() {
+        fs::remove_file(&path).expect("failed to remove old burn state");
+    }
+    SimBurnStateDB::build_from_chainstate(&SimHeadersDB::new(), *SEED, &path)
+        .unwrap_or_else(|e| panic!("Failed to build burn state: {}", e));
+    path
+}
+
+/// `gen` with the run seed. When `COST_BENCH_REPLAY_CORPUS` is set, the output is read
+/// from the corpus there instead, and when `COST_BENCH_EXPORT_CORPUS` is set it is
+/// written to the corpus there, see `corpus`.
+fn bench_gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOutput {
+    let output = match replay_from_corpus(CostFunction::Clarity(function), scale, input_size) {
+        Some(output) => output,
+        None => gen(function, scale, input_size, *SEED),
+    };
+    export_to_corpus(CostFunction::Clarity(function), scale, input_size, &output);
+    output
+}
+
+/// `gen_analysis_pass` with the run seed, replayed and exported like `bench_gen`.
+fn bench_gen_analysis_pass(function: AnalysisCostFunction, scale: u16, input_size: u64) -> GenOutput {
+    let output = match replay_from_corpus(CostFunction::Analysis(function), scale, input_size) {
+        Some(output) => output,
+        None => gen_analysis_pass(function, scale, input_size, *SEED),
+    };
+    export_to_corpus(CostFunction::Analysis(function), scale, input_size, &output);
+    output
+}
+
+/// Code a benchmark of `function` builds itself rather than with `gen`, named
+/// `name`. Replayed and exported like `bench_gen`, as a harness entry.
+fn bench_harness_code<F>(function: ClarityCostFunction, name: &str, input_size: u64, generate: F) -> GenOutput
+where
+    F: FnOnce() -> GenOutput,
+{
+    let function = CostFunction::Clarity(function);
+    let output = match &*CORPUS_REPLAY_DIR {
+        Some(dir) => corpus::load_harness(dir, function, name, input_size)
+            .unwrap_or_else(|e| panic!("Failed to replay {} from the corpus: {}", function, e)),
+        None => generate(),
+    };
+    if let Some(dir) = &*CORPUS_EXPORT_DIR {
+        corpus::export_harness(dir, function, name, input_size, *SEED, &output)
+            .unwrap_or_else(|e| panic!("Failed to export {} to the corpus: {}", function, e));
+    }
+    output
+}
+
+/// Contracts `AstParse` parses, by claimed input size.
+fn make_sized_contracts() -> HashMap<u64, String> {
+    let mut rng = bench_rng();
+    INPUT_SIZES
+        .iter()
+        .map(|size| {
+            let output = bench_harness_code(ClarityCostFunction::AstParse, "sized_contract", *size, || {
+                let (body, claimed_input_size) = make_sized_contract(*size, &mut rng);
+                GenOutput::new(None, body, claimed_input_size)
+            });
+            (output.input_size, output.body)
+        })
+        .collect()
+}
+
+/// The corpus entry for a generator call, if replaying. Panics if it is missing,
+/// since generating it instead would silently time different code.
+fn replay_from_corpus(function: CostFunction, scale: u16, input_size: u64) -> Option<GenOutput> {
+    CORPUS_REPLAY_DIR.as_ref().map(|dir| {
+        corpus::load(dir, function, scale, input_size)
+            .unwrap_or_else(|e| panic!("Failed to replay {} from the corpus: {}", function, e))
+    })
+}
+
+fn export_to_corpus(function: CostFunction, scale: u16, input_size: u64, output: &GenOutput) {
+    if let Some(dir) = &*CORPUS_EXPORT_DIR {
+        corpus::export(dir, function, scale, input_size, *SEED, output)
+            .unwrap_or_else(|e| panic!("Failed to export {} to the corpus: {}", function, e));
+    }
+}
+
+/// Fresh RNG for benchmark setup code, seeded from the run seed.
+fn bench_rng() -> StdRng {
+    StdRng::seed_from_u64(*SEED)
+}
+
+/// Reports the outcome of checking the input size recorded for a benchmark against
+/// the charges the VM made, when `COST_BENCH_CHECK_SIZES` is set.
+fn report_size_check(function: ClarityCostFunction, input_size: u64, charges: &Charges) {
+    let check = cost_probe::check_input_size(input_size, charges);
+    println!("{} at input size {}: {}", function, input_size, check);
+    if check != SizeCheck::Agrees {
+        SIZE_CHECK_FAILURES
+            .lock()
+            .unwrap()
+            .push(format!("{} at input size {}: {}", function, input_size, check));
+    }
+}
+
 fn eval(
     contract_ast: &ContractAST,
     global_context: &mut GlobalContext,
@@ -115,14 +412,14 @@ fn eval(
 /// * `scale` - a scaling parameter used by the Clarity function code generator
 /// * `input_sizes` - an optional list of input sizes. a separate benchmark will be run for each size provided. If None, will be benchmarked as constant size.
 /// * `use_headers_db` - if true, use a sim headers db instead of a null one
-/// * `maybe_make_store` - an optional closure returning a `MemoryBackingStore`. useful if you want to run a benchmark with pre-loaded state.
+/// * `maybe_prepare_store` - an optional closure that loads state into the store. useful if you want to run a benchmark with pre-loaded state.
 fn bench_with_input_sizes(
-    c: &mut Criterion,
+    c: &mut Criterion<Metered>,
     function: ClarityCostFunction,
     scale: u16,
     input_sizes: Option<Vec<u64>>,
     use_headers_db: bool,
-    maybe_make_store: Option<Box<dyn Fn() -> MemoryBackingStore>>,
+    maybe_prepare_store: Option<Box<dyn Fn(&mut dyn ClarityBackingStore)>>,
 ) {
     let mut group = c.benchmark_group(function.to_string());
 
@@ -135,7 +432,7 @@ fn bench_with_input_sizes(
                     scale,
                     *input_size,
                     use_headers_db,
-                    &maybe_make_store,
+                    &maybe_prepare_store,
                     eval,
                 )
             }
@@ -146,7 +443,7 @@ fn bench_with_input_sizes(
             scale,
             1,
             use_headers_db,
-            &maybe_make_store,
+            &maybe_prepare_store,
             eval,
         ),
     }
@@ -160,43 +457,89 @@ fn bench_with_input_sizes(
 /// * `function` - the Clarity cost function that is being benchmarked
 /// * `scale` - a scaling parameter used by the Clarity function code generator
 /// * `input_size` - The input size to pass in to the code generator. Pass in 1 if constant.
-/// * `use_headers_db` - if true, use a sim headers db instead of a null one
-/// * `maybe_make_store` - an optional closure returning a `MemoryBackingStore`. useful if you want to run a benchmark with pre-loaded state.
+/// * `use_headers_db` - if true, use a sim headers db and burn state db instead of null ones. header
+///   lookups are counted and printed, and delayed by `COST_BENCH_HEADERS_LATENCY_US`
+/// * `maybe_prepare_store` - an optional closure that loads state into the store. useful if you want to run a benchmark with pre-loaded state.
 /// * `code_to_bench` - a function that will run the generated Clarity code
+///
+/// With `COST_BENCH_CHECK_SIZES` or `COST_BENCH_RECORD_CHARGES` set, `code_to_bench` is run
+/// under probe cost trackers instead of being benchmarked, see `probe_bench`.
 fn run_bench<F>(
-    group: &mut BenchmarkGroup<WallTime>,
+    group: &mut BenchmarkGroup<Metered>,
     function: ClarityCostFunction,
     scale: u16,
     input_size: u64,
     use_headers_db: bool,
-    maybe_make_store: &Option<Box<dyn Fn() -> MemoryBackingStore>>,
+    maybe_prepare_store: &Option<Box<dyn Fn(&mut dyn ClarityBackingStore)>>,
     code_to_bench: F,
 ) where
     F: Fn(&ContractAST, &mut GlobalContext, &mut ContractContext),
 {
-    let mut memory_backing_store = match maybe_make_store {
-        Some(ref make_store) => make_store(),
-        None => MemoryBackingStore::new(),
+    let probe = |probed| {
+        prepare_bench(function, scale, input_size, use_headers_db, maybe_prepare_store, Some(probed),
+            |computed_input_size, contract_ast, global_context, contract_context, _| {
+                let before = global_context.cost_track.get_total();
+                code_to_bench(contract_ast, global_context, contract_context);
+                (computed_input_size, Charges::between(&before, &global_context.cost_track.get_total()))
+            })
     };
+    if probe_bench(function, probe) {
+        return;
+    }
 
-    let headers_db = SimHeadersDB::new();
-    let clarity_db = match use_headers_db {
-        true => ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB),
-        false => memory_backing_store.as_clarity_db(),
-    };
+    prepare_bench(function, scale, input_size, use_headers_db, maybe_prepare_store, None,
+        |computed_input_size, contract_ast, global_context, contract_context, headers_db| {
+            if use_headers_db {
+                // one untimed run, to see how many header lookups an iteration makes
+                headers_db.reset_counts();
+                code_to_bench(contract_ast, global_context, contract_context);
+                for (method, count) in headers_db.counts() {
+                    println!("{} at input size {}: {} calls to {}", function, computed_input_size, count, method);
+                }
+                headers_db.reset_counts();
+            }
 
-    let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-    global_context.begin();
+            group.throughput(Throughput::Bytes(computed_input_size.clone() as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(computed_input_size),
+                &input_size,
+                |b, &_| {
+                    b.iter(|| {
+                        code_to_bench(contract_ast, global_context, contract_context);
+                    })
+                },
+            );
+        });
+}
+
+/// Generates the code for a `run_bench` benchmark, evaluates its setup, and hands the
+/// generated input size, contract and headers db to `then`. If `probed` is given, the global context
+/// uses a probe cost tracker for that cost function, otherwise a free one.
+fn prepare_bench<T, R>(
+    function: ClarityCostFunction,
+    scale: u16,
+    input_size: u64,
+    use_headers_db: bool,
+    maybe_prepare_store: &Option<Box<dyn Fn(&mut dyn ClarityBackingStore)>>,
+    probed: Option<ClarityCostFunction>,
+    then: T,
+) -> R
+where
+    T: FnOnce(u64, &ContractAST, &mut GlobalContext, &mut ContractContext, &InstrumentedHeadersDB<SimHeadersDB>) -> R,
+{
+    let headers_db = InstrumentedHeadersDB::new(SimHeadersDB::new(), *HEADERS_LATENCY);
+    let burn_state_db = SimBurnStateDB::open(&*BURN_STATE_PATH).unwrap_or_else(|e| panic!("{}", e));
 
     let GenOutput {
         setup: pre_contract_opt,
         body: contract,
         input_size: computed_input_size,
-    } = gen(function, scale, input_size);
+    } = bench_gen(function, scale, input_size);
 
     let contract_identifier =
         QualifiedContractIdentifier::local(&*format!("c{}", computed_input_size)).unwrap();
-    let mut contract_context = ContractContext::new(contract_identifier.clone());
+    let pre_contract_identifier =
+        QualifiedContractIdentifier::local(&*format!("pre{}", computed_input_size)).unwrap();
 
     let contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
         Ok(res) => res,
@@ -205,35 +548,113 @@ fn run_bench<F>(
         }
     };
 
-    match pre_contract_opt {
-        Some(pre_contract) => {
-            let pre_contract_identifier =
-                QualifiedContractIdentifier::local(&*format!("pre{}", computed_input_size))
-                    .unwrap();
-            let pre_contract_ast =
-                match ast::build_ast(&pre_contract_identifier, &pre_contract, &mut ()) {
-                    Ok(res) => res,
-                    Err(error) => {
-                        panic!("Parsing error: {}", error.diagnostic.message);
-                    }
+    storage::with_prepared_store(*STORAGE,
+        |store| {
+            if let Some(ref prepare_store) = maybe_prepare_store {
+                prepare_store(store);
+            }
+            if let Some(probed) = probed {
+                cost_probe::install_probe_contracts(store, probed);
+            }
+
+            let mut contract_context = ContractContext::new(contract_identifier.clone());
+            if let (StorageBackend::Marf, Some(pre_contract)) = (*STORAGE, &pre_contract_opt) {
+                let clarity_db = match use_headers_db {
+                    true => ClarityDatabase::new(store, &headers_db, &burn_state_db),
+                    false => ClarityDatabase::new(store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB),
                 };
-            global_context
-                .execute(|g| eval_all(&pre_contract_ast.expressions, &mut contract_context, g))
-                .unwrap();
+                commit_setup(clarity_db, &mut contract_context, &pre_contract_identifier, pre_contract);
+            }
+            contract_context
+        },
+        |store, mut contract_context| {
+            let mut clarity_db = match use_headers_db {
+                true => ClarityDatabase::new(store, &headers_db, &burn_state_db),
+                false => ClarityDatabase::new(store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB),
+            };
+
+            let cost_tracker = match probed {
+                Some(_) => cost_probe::unlimited_tracker(&mut clarity_db).unwrap_or_else(|e| panic!("{}", e)),
+                None => LimitedCostTracker::new_free(),
+            };
+            let mut global_context = GlobalContext::new(false, clarity_db, cost_tracker);
+            global_context.begin();
+
+            if let (StorageBackend::Memory, Some(pre_contract)) = (*STORAGE, &pre_contract_opt) {
+                eval_setup(&mut global_context, &mut contract_context, &pre_contract_identifier, pre_contract);
+            }
+
+            then(computed_input_size, &contract_ast, &mut global_context, &mut contract_context, &headers_db)
+        })
+}
+
+/// Evaluates a benchmark's setup contract, defining it in `contract_context`.
+fn eval_setup(
+    global_context: &mut GlobalContext,
+    contract_context: &mut ContractContext,
+    pre_contract_identifier: &QualifiedContractIdentifier,
+    pre_contract: &str,
+) {
+    let pre_contract_ast = match ast::build_ast(pre_contract_identifier, pre_contract, &mut ()) {
+        Ok(res) => res,
+        Err(error) => {
+            panic!("Parsing error: {}", error.diagnostic.message);
         }
-        _ => {}
-    }
+    };
+    global_context
+        .execute(|g| eval_all(&pre_contract_ast.expressions, contract_context, g))
+        .unwrap();
+}
 
-    group.throughput(Throughput::Bytes(computed_input_size.clone() as u64));
-    group.bench_with_input(
-        BenchmarkId::from_parameter(computed_input_size),
-        &input_size,
-        |b, &_| {
-            b.iter(|| {
-                code_to_bench(&contract_ast, &mut global_context, &mut contract_context);
-            })
-        },
-    );
+/// Evaluates a benchmark's setup contract in a context of its own and commits it to the
+/// store. On a MARF this is the parent block, so the benchmark reads the setup from storage
+/// rather than from its context's pending writes; the memory backend evaluates setup in the
+/// benchmark's context instead, as it always has.
+fn commit_setup(
+    clarity_db: ClarityDatabase,
+    contract_context: &mut ContractContext,
+    pre_contract_identifier: &QualifiedContractIdentifier,
+    pre_contract: &str,
+) {
+    let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+    global_context.begin();
+    eval_setup(&mut global_context, contract_context, pre_contract_identifier, pre_contract);
+    global_context.commit().unwrap();
+}
+
+/// Checks sizes or records charges for a benchmark, depending on the probe mode. `probe`
+/// runs the benchmarked code once under a probe tracker for the given cost function, and
+/// returns the benchmark's input size with the charges made. Returns false, without
+/// probing, if the benchmark should be timed instead.
+fn probe_bench<P>(function: ClarityCostFunction, mut probe: P) -> bool
+where
+    P: FnMut(ClarityCostFunction) -> (u64, Charges),
+{
+    match ProbeMode::from_env() {
+        ProbeMode::Off => false,
+        ProbeMode::CheckSizes => {
+            let (input_size, charges) = probe(function);
+            report_size_check(function, input_size, &charges);
+            true
+        }
+        ProbeMode::RecordCharges(_) => {
+            let input_size = probe(function).0;
+            let record = ChargeRecord::collect(function, input_size, |probed| probe(probed).1);
+            println!(
+                "{} at input size {}: {:.1}% of {} charges were to {}",
+                function,
+                input_size,
+                record.target_fraction() * 100.0,
+                record.total_count(),
+                function
+            );
+            for (contaminant, charges) in record.contaminants().iter().take(5) {
+                println!("  {}: {} charges", contaminant, charges.count);
+            }
+            CHARGE_RECORDS.lock().unwrap().push(record);
+            true
+        }
+    }
 }
 
 fn dummy_setup_code(
@@ -246,14 +667,14 @@ fn dummy_setup_code(
 }
 
 fn bench_analysis<F, G>(
-    c: &mut Criterion,
+    c: &mut Criterion<Metered>,
     function: ClarityCostFunction,
     scale: u16,
     input_sizes: Vec<u64>,
     setup_code: G,
     code_to_bench: F,
 ) where
-    F: Fn(&mut ContractAST, &mut TypingContext, &mut TypeChecker, u64, &mut LimitedCostTracker),
+    F: Fn(&mut ContractAST, &mut TypingContext, &mut TypeChecker, u64, u16, &mut LimitedCostTracker),
     G: Fn(&mut ContractAST, &mut TypingContext, &mut TypeChecker, u64, &mut LimitedCostTracker),
 {
     let mut group = c.benchmark_group(function.to_string());
@@ -265,7 +686,7 @@ fn bench_analysis<F, G>(
             setup: _,
             body: contract,
             input_size: computed_input_size,
-        } = gen(function, scale, *input_size);
+        } = bench_gen(function, scale, *input_size);
 
         let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
             Ok(res) => res,
@@ -299,6 +720,7 @@ fn bench_analysis<F, G>(
                         &mut local_context,
                         &mut type_checker,
                         computed_input_size,
+                        scale,
                         &mut cost_tracker,
                     );
                 })
@@ -307,16 +729,16 @@ fn bench_analysis<F, G>(
     }
 }
 
-fn bench_analysis_pass<F>(c: &mut Criterion, function: AnalysisCostFunction, code_to_bench: F) -> ()
+fn bench_analysis_pass<F>(c: &mut Criterion<Metered>, spec: &BenchSpec, function: AnalysisCostFunction, code_to_bench: F) -> ()
 where
     F: Fn(&mut ContractAnalysis, &mut AnalysisDatabase) -> CheckResult<()>,
 {
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in INPUT_SIZES_ANALYSIS_PASS.iter() {
+    for input_size in spec.input_sizes().iter() {
         let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
 
-        let contract = gen_analysis_pass(function, 1, *input_size).body;
+        let contract = bench_gen_analysis_pass(function, 1, *input_size).body;
         let contract_size = contract.len();
 
         let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
@@ -342,7 +764,7 @@ where
                 &contract_size,
                 |b, &_| {
                     b.iter(|| {
-                        for _ in 0..SCALE {
+                        for _ in 0..spec.scale {
                             code_to_bench(&mut contract_analysis, db);
                         }
                     })
@@ -355,11 +777,11 @@ where
     ()
 }
 
-fn bench_analysis_pass_read_only(c: &mut Criterion) {
-    bench_analysis_pass(c, AnalysisCostFunction::ReadOnly, ReadOnlyChecker::run_pass)
+fn bench_analysis_pass_read_only(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_analysis_pass(c, spec, AnalysisCostFunction::ReadOnly, ReadOnlyChecker::run_pass)
 }
 
-fn bench_analysis_pass_arithmetic_only_checker(c: &mut Criterion) {
+fn bench_analysis_pass_arithmetic_only_checker(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn wrapper_arithmetic_checker(
         contract_analysis: &mut ContractAnalysis,
         _db: &mut AnalysisDatabase,
@@ -369,22 +791,23 @@ fn bench_analysis_pass_arithmetic_only_checker(c: &mut Criterion) {
     }
     bench_analysis_pass(
         c,
+        spec,
         AnalysisCostFunction::ArithmeticOnlyChecker,
         wrapper_arithmetic_checker,
     )
 }
 
-fn bench_analysis_pass_trait_checker(c: &mut Criterion) {
+fn bench_analysis_pass_trait_checker(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = AnalysisCostFunction::TraitChecker;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in INPUT_SIZES_ANALYSIS_PASS.iter() {
+    for input_size in spec.input_sizes().iter() {
         // Parse the setup contract
         let GenOutput {
             setup: setup_opt,
             body: mut contract,
             input_size: computed_input_size,
-        } = gen_analysis_pass(function, 1, *input_size);
+        } = bench_gen_analysis_pass(function, 1, *input_size);
 
         let setup_contract = setup_opt.unwrap();
         let pre_contract_identifier =
@@ -462,7 +885,7 @@ fn bench_analysis_pass_trait_checker(c: &mut Criterion) {
                 &contract_size,
                 |b, &_| {
                     b.iter(|| {
-                        for _ in 0..SCALE {
+                        for _ in 0..spec.scale {
                             TraitChecker::run_pass(&mut contract_analysis, db);
                         }
                     })
@@ -475,17 +898,17 @@ fn bench_analysis_pass_trait_checker(c: &mut Criterion) {
     ()
 }
 
-fn bench_analysis_pass_type_checker(c: &mut Criterion) {
+fn bench_analysis_pass_type_checker(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = AnalysisCostFunction::TypeChecker;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in INPUT_SIZES_ANALYSIS_PASS.iter() {
+    for input_size in spec.input_sizes().iter() {
         // Parse the setup contract
         let GenOutput {
             setup: setup_opt,
             body: mut contract,
             input_size: computed_input_size,
-        } = gen_analysis_pass(function, 1, *input_size);
+        } = bench_gen_analysis_pass(function, 1, *input_size);
 
         let setup_contract = setup_opt.unwrap();
         let pre_contract_identifier =
@@ -552,7 +975,7 @@ fn bench_analysis_pass_type_checker(c: &mut Criterion) {
                 &contract_size,
                 |b, &_| {
                     b.iter(|| {
-                        for _ in 0..SCALE {
+                        for _ in 0..spec.scale {
                             TypeChecker::run_pass(&mut contract_analysis, db);
                         }
                     })
@@ -568,11 +991,12 @@ fn bench_analysis_pass_type_checker(c: &mut Criterion) {
 fn helper_deepen_typing_context(
     i: u64,
     input_size: u64,
+    scale: u16,
     context: &TypingContext,
-    group: &mut BenchmarkGroup<WallTime>,
+    group: &mut BenchmarkGroup<Metered>,
 ) {
     if i != 0 {
-        helper_deepen_typing_context(i - 1, input_size, &context.extend().unwrap(), group);
+        helper_deepen_typing_context(i - 1, input_size, scale, &context.extend().unwrap(), group);
     } else {
         let mut cost_tracker = LimitedCostTracker::new_free();
         let mut memory_backing_store = MemoryBackingStore::new();
@@ -585,7 +1009,7 @@ fn helper_deepen_typing_context(
             &input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..scale {
                         type_checker.bench_analysis_lookup_variable_depth_helper("dummy", &context);
                     }
                 })
@@ -594,24 +1018,25 @@ fn helper_deepen_typing_context(
     }
 }
 
-fn bench_analysis_lookup_variable_depth(c: &mut Criterion) {
+fn bench_analysis_lookup_variable_depth(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AnalysisLookupVariableDepth;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let mut local_context = TypingContext::new();
-        helper_deepen_typing_context(*input_size, *input_size, &local_context, &mut group);
+        helper_deepen_typing_context(*input_size, *input_size, spec.scale, &local_context, &mut group);
     }
 }
 
 fn helper_deepen_local_context(
     i: u64,
     input_size: u64,
+    scale: u16,
     context: &LocalContext,
-    group: &mut BenchmarkGroup<WallTime>,
+    group: &mut BenchmarkGroup<Metered>,
 ) {
     if i != 0 {
-        helper_deepen_local_context(i - 1, input_size, &context.extend().unwrap(), group);
+        helper_deepen_local_context(i - 1, input_size, scale, &context.extend().unwrap(), group);
     } else {
         let mut memory_backing_store = MemoryBackingStore::new();
         let clarity_db = memory_backing_store.as_clarity_db();
@@ -636,7 +1061,7 @@ fn helper_deepen_local_context(
             &input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..scale {
                         lookup_variable("dummy", &context, &mut environment);
                     }
                 })
@@ -645,29 +1070,29 @@ fn helper_deepen_local_context(
     }
 }
 
-fn bench_lookup_variable_depth(c: &mut Criterion) {
+fn bench_lookup_variable_depth(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::LookupVariableDepth;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let mut local_context = LocalContext::new();
-        helper_deepen_local_context(*input_size, *input_size, &local_context, &mut group);
+        helper_deepen_local_context(*input_size, *input_size, spec.scale, &local_context, &mut group);
     }
 }
 
 // note: could write `bench_run` function, and split out adding nodes to the graph from finding dependencies
-fn bench_ast_cycle_detection(c: &mut Criterion) {
+fn bench_ast_cycle_detection(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AstCycleDetection;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
 
         let GenOutput {
             setup: _,
             body: mut contract,
             input_size: computed_input_size,
-        } = gen(function, 1, *input_size);
+        } = bench_gen(function, 1, *input_size);
 
         let pre_expressions = parser::parse(&contract).unwrap();
         let mut contract_ast = ContractAST::new(contract_identifier.clone(), pre_expressions);
@@ -682,7 +1107,7 @@ fn bench_ast_cycle_detection(c: &mut Criterion) {
             &computed_input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..spec.scale {
                         def_sorter.clear_graph();
                         def_sorter.run(&mut contract_ast, &mut cost_tracker);
                     }
@@ -692,70 +1117,70 @@ fn bench_ast_cycle_detection(c: &mut Criterion) {
     }
 }
 
-fn bench_contract_storage(c: &mut Criterion) {
+fn bench_contract_storage(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::ContractStorage;
     let mut group = c.benchmark_group(function.to_string());
-    let mut rng = rand::thread_rng();
+    let mut rng = bench_rng();
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let headers_db = SimHeadersDB::new();
-        let mut memory_backing_store = MemoryBackingStore::new();
-        let clarity_db =
-            ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
+        storage::with_store(*STORAGE, |store| {
+            let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-        let mut global_context =
-            GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-        global_context.begin();
+            let mut global_context =
+                GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+            global_context.begin();
 
-        let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-        let mut contract_context = ContractContext::new(contract_identifier.clone());
+            let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+            let mut contract_context = ContractContext::new(contract_identifier.clone());
 
-        let GenOutput {
-            setup: _,
-            body: contract,
-            input_size: computed_input_size,
-        } = gen(function, 1, *input_size);
+            let GenOutput {
+                setup: _,
+                body: contract,
+                input_size: computed_input_size,
+            } = bench_gen(function, 1, *input_size);
 
-        let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
-            Ok(res) => res,
-            Err(error) => {
-                panic!("Parsing error: {}", error.diagnostic.message);
-            }
-        };
+            let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
+                Ok(res) => res,
+                Err(error) => {
+                    panic!("Parsing error: {}", error.diagnostic.message);
+                }
+            };
 
-        let mut call_stack = CallStack::new();
+            let mut call_stack = CallStack::new();
 
-        let mut environment = Environment::new(
-            global_context.borrow_mut(),
-            &contract_context,
-            &mut call_stack,
-            None,
-            None,
-        );
+            let mut environment = Environment::new(
+                global_context.borrow_mut(),
+                &contract_context,
+                &mut call_stack,
+                None,
+                None,
+            );
 
-        group.throughput(Throughput::Bytes(computed_input_size as u64));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(computed_input_size),
-            &computed_input_size,
-            |b, &_| {
-                b.iter(|| {
-                    for _ in 0..SCALE {
-                        let contract_identifier =
-                            QualifiedContractIdentifier::local(&*format!("c{}", rng.gen::<u32>()))
-                                .unwrap();
-                        environment.initialize_contract_from_ast(
-                            contract_identifier.clone(),
-                            &contract_ast,
-                            &contract,
-                        );
-                    }
-                })
-            },
-        );
+            group.throughput(Throughput::Bytes(computed_input_size as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(computed_input_size),
+                &computed_input_size,
+                |b, &_| {
+                    b.iter(|| {
+                        for _ in 0..spec.scale {
+                            let contract_identifier =
+                                QualifiedContractIdentifier::local(&*format!("c{}", rng.gen::<u32>()))
+                                    .unwrap();
+                            environment.initialize_contract_from_ast(
+                                contract_identifier.clone(),
+                                &contract_ast,
+                                &contract,
+                            );
+                        }
+                    })
+                },
+            );
+        });
     }
 }
 
-fn bench_principal_of(c: &mut Criterion) {
+fn bench_principal_of(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::PrincipalOf;
     let mut group = c.benchmark_group(function.to_string());
 
@@ -771,7 +1196,7 @@ fn bench_principal_of(c: &mut Criterion) {
         setup: _,
         body: contract,
         input_size: _,
-    } = gen(function, SCALE, 1);
+    } = bench_gen(function, spec.scale, 1);
 
     let contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
         Ok(res) => res,
@@ -800,47 +1225,76 @@ fn bench_principal_of(c: &mut Criterion) {
     });
 }
 
-fn bench_analysis_use_trait_entry(c: &mut Criterion) {
-    let function = ClarityCostFunction::AnalysisUseTraitEntry;
-    let mut group = c.benchmark_group(function.to_string());
+/// Type checks the first definition of `function`'s generated contract, stores its analysis,
+/// and calls `bench` with the analysis database and the contract's identifier. On a MARF the
+/// analysis is committed in the parent block; on the memory backend it is inserted in the
+/// same `execute` as `bench`, as it always has been.
+fn with_stored_analysis<B>(function: ClarityCostFunction, input_size: u64, bench: B)
+where
+    B: FnOnce(&mut AnalysisDatabase, &QualifiedContractIdentifier),
+{
+    let GenOutput {
+        setup: _,
+        body: contract,
+        input_size: _,
+    } = bench_gen(function, 1, input_size);
 
-    for input_size in &INPUT_SIZES {
-        let mut memory_backing_store = MemoryBackingStore::new();
+    let contract_identifier =
+        QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+    let contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
+        Ok(res) => res,
+        Err(error) => {
+            panic!("Parsing error: {}", error.diagnostic.message);
+        }
+    };
 
-        let mut analysis_db = memory_backing_store.as_analysis_db();
-        let mut cost_tracker = LimitedCostTracker::new_free();
-        let mut type_checker = TypeChecker::new(&mut analysis_db, cost_tracker.clone());
+    storage::with_prepared_store(*STORAGE,
+        |store| {
+            let mut analysis_db = AnalysisDatabase::new(store);
+            let cost_tracker = LimitedCostTracker::new_free();
+            let mut contract_analysis = ContractAnalysis::new(
+                contract_identifier.clone(),
+                contract_ast.expressions.clone(),
+                cost_tracker.clone(),
+            );
 
-        let GenOutput {
-            setup: _,
-            body: contract,
-            input_size: _,
-        } = gen(function, 1, *input_size);
+            let mut type_checker = TypeChecker::new(&mut analysis_db, cost_tracker);
+            let mut typing_context = TypingContext::new();
+            type_checker.try_type_check_define(&contract_ast.expressions[0], &mut typing_context);
+            type_checker
+                .contract_context
+                .into_contract_analysis(&mut contract_analysis);
 
-        let mut contract_identifier =
-            QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-        let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
-            Ok(res) => res,
-            Err(error) => {
-                panic!("Parsing error: {}", error.diagnostic.message);
+            if *STORAGE == StorageBackend::Marf {
+                analysis_db
+                    .execute(|db| db.insert_contract(&contract_identifier, &contract_analysis))
+                    .unwrap();
             }
-        };
+            contract_analysis
+        },
+        |store, contract_analysis| {
+            let mut analysis_db = AnalysisDatabase::new(store);
+            analysis_db.execute(|db| {
+                if *STORAGE == StorageBackend::Memory {
+                    db.insert_contract(&contract_identifier, &contract_analysis);
+                }
+                bench(db, &contract_identifier);
+                // this snippet is here since the "execute" context needs to determine the return type
+                if false {
+                    return Err(());
+                }
 
-        let cost_tracker = LimitedCostTracker::new_free();
-        let mut contract_analysis = ContractAnalysis::new(
-            contract_identifier.clone(),
-            contract_ast.expressions.clone(),
-            cost_tracker,
-        );
+                Ok(())
+            });
+        })
+}
 
-        let mut typing_context = TypingContext::new();
-        type_checker.try_type_check_define(&contract_ast.expressions[0], &mut typing_context);
-        type_checker
-            .contract_context
-            .into_contract_analysis(&mut contract_analysis);
+fn bench_analysis_use_trait_entry(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    let function = ClarityCostFunction::AnalysisUseTraitEntry;
+    let mut group = c.benchmark_group(function.to_string());
 
-        type_checker.db.execute(|db| {
-            db.insert_contract(&contract_identifier, &contract_analysis);
+    for input_size in &spec.input_sizes() {
+        with_stored_analysis(function, *input_size, |db, contract_identifier| {
             let trait_name = ClarityName::try_from("dummy-trait".to_string()).unwrap();
             let trait_id = TraitIdentifier {
                 name: trait_name.clone(),
@@ -849,7 +1303,7 @@ fn bench_analysis_use_trait_entry(c: &mut Criterion) {
 
             // get the size of the trait
             let trait_sig = db
-                .get_defined_trait(&contract_identifier, &trait_name)
+                .get_defined_trait(contract_identifier, &trait_name)
                 .unwrap()
                 .unwrap();
             let type_size = trait_type_size(&trait_sig).unwrap();
@@ -860,66 +1314,25 @@ fn bench_analysis_use_trait_entry(c: &mut Criterion) {
                 &type_size,
                 |b, &_| {
                     b.iter(|| {
-                        for _ in 0..SCALE {
+                        for _ in 0..spec.scale {
                             TypeChecker::bench_analysis_use_trait_entry_in_context(db, &trait_id);
                         }
                     })
                 },
             );
-            // this snippet is here since the "execute" context needs to determine the return type
-            if false {
-                return Err(());
-            }
-
-            Ok(())
         });
     }
 }
 
-fn bench_analysis_get_function_entry(c: &mut Criterion) {
+fn bench_analysis_get_function_entry(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AnalysisGetFunctionEntry;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
-        let mut memory_backing_store = MemoryBackingStore::new();
-
-        let mut analysis_db = memory_backing_store.as_analysis_db();
-        let mut cost_tracker = LimitedCostTracker::new_free();
-        let mut type_checker = TypeChecker::new(&mut analysis_db, cost_tracker.clone());
-
-        let GenOutput {
-            setup: _,
-            body: contract,
-            input_size: _,
-        } = gen(function, 1, *input_size);
-
-        let mut contract_identifier =
-            QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-        let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
-            Ok(res) => res,
-            Err(error) => {
-                panic!("Parsing error: {}", error.diagnostic.message);
-            }
-        };
-
-        let cost_tracker = LimitedCostTracker::new_free();
-        let mut contract_analysis = ContractAnalysis::new(
-            contract_identifier.clone(),
-            contract_ast.expressions.clone(),
-            cost_tracker,
-        );
-
-        let mut typing_context = TypingContext::new();
-        type_checker.try_type_check_define(&contract_ast.expressions[0], &mut typing_context);
-        type_checker
-            .contract_context
-            .into_contract_analysis(&mut contract_analysis);
-
-        type_checker.db.execute(|db| {
-            db.insert_contract(&contract_identifier, &contract_analysis);
+    for input_size in &spec.input_sizes() {
+        with_stored_analysis(function, *input_size, |db, contract_identifier| {
             let fn_name = ClarityName::try_from("dummy-fn".to_string()).unwrap();
             let type_size = match db
-                .get_read_only_function_type(&contract_identifier, "dummy-fn")
+                .get_read_only_function_type(contract_identifier, "dummy-fn")
                 .unwrap()
             {
                 Some(FunctionType::Fixed(function)) => {
@@ -935,31 +1348,25 @@ fn bench_analysis_get_function_entry(c: &mut Criterion) {
                 &type_size,
                 |b, &_| {
                     b.iter(|| {
-                        for _ in 0..SCALE {
+                        for _ in 0..spec.scale {
                             bench_analysis_get_function_entry_in_context(
                                 db,
-                                &contract_identifier,
+                                contract_identifier,
                                 &fn_name,
                             );
                         }
                     })
                 },
             );
-            // this snippet is here since the "execute" context needs to determine the return type
-            if false {
-                return Err(());
-            }
-
-            Ok(())
         });
     }
 }
 
-fn bench_inner_type_check_cost(c: &mut Criterion) {
+fn bench_inner_type_check_cost(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::InnerTypeCheckCost;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let mut memory_backing_store = MemoryBackingStore::new();
         let clarity_db = memory_backing_store.as_clarity_db();
         let mut global_context =
@@ -973,7 +1380,7 @@ fn bench_inner_type_check_cost(c: &mut Criterion) {
             setup: _,
             body: contract,
             input_size: _,
-        } = gen(function, 1, *input_size);
+        } = bench_gen(function, 1, *input_size);
 
         let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
             Ok(res) => res,
@@ -995,7 +1402,7 @@ fn bench_inner_type_check_cost(c: &mut Criterion) {
             &input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..spec.scale {
                         defined_fn.bench_execute_apply(&arg_list);
                     }
                 })
@@ -1004,12 +1411,12 @@ fn bench_inner_type_check_cost(c: &mut Criterion) {
     }
 }
 
-fn bench_user_function_application(c: &mut Criterion) {
+fn bench_user_function_application(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::UserFunctionApplication;
     let mut group = c.benchmark_group(function.to_string());
-    let mut rng = rand::thread_rng();
+    let mut rng = bench_rng();
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let mut memory_backing_store = MemoryBackingStore::new();
         let clarity_db = memory_backing_store.as_clarity_db();
         let mut global_context =
@@ -1023,7 +1430,7 @@ fn bench_user_function_application(c: &mut Criterion) {
             setup: _,
             body: contract,
             input_size: computed_input_size,
-        } = gen(function, 1, *input_size);
+        } = bench_gen(function, 1, *input_size);
 
         let contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
             Ok(res) => res,
@@ -1048,7 +1455,7 @@ fn bench_user_function_application(c: &mut Criterion) {
             &computed_input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..spec.scale {
                         defined_fn.bench_execute_apply(&arg_list).unwrap();
                     }
                 })
@@ -1057,11 +1464,11 @@ fn bench_user_function_application(c: &mut Criterion) {
     }
 }
 
-fn bench_analysis_lookup_function_types(c: &mut Criterion) {
+fn bench_analysis_lookup_function_types(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AnalysisLookupFunctionTypes;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let mut memory_backing_store = MemoryBackingStore::new();
         let clarity_db = memory_backing_store.as_clarity_db();
         let mut global_context =
@@ -1075,7 +1482,7 @@ fn bench_analysis_lookup_function_types(c: &mut Criterion) {
             setup: _,
             body: contract,
             input_size: _,
-        } = gen(function, 1, *input_size);
+        } = bench_gen(function, 1, *input_size);
 
         let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
             Ok(res) => res,
@@ -1117,7 +1524,7 @@ fn bench_analysis_lookup_function_types(c: &mut Criterion) {
             &curr_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..spec.scale {
                         bench_check_contract_call(&mut type_checker, &trait_id, &fn_name);
                     }
                 })
@@ -1126,72 +1533,72 @@ fn bench_analysis_lookup_function_types(c: &mut Criterion) {
     }
 }
 
-fn bench_lookup_function(c: &mut Criterion) {
+fn bench_lookup_function(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::LookupFunction;
     let mut group = c.benchmark_group(function.to_string());
 
     let headers_db = SimHeadersDB::new();
-    let mut memory_backing_store = MemoryBackingStore::new();
-    let clarity_db =
-        ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
-
-    let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-    global_context.begin();
+    storage::with_store(*STORAGE, |store| {
+        let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-    let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-    let mut contract_context = ContractContext::new(contract_identifier.clone());
+        let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+        global_context.begin();
 
-    let GenOutput {
-        setup: _,
-        body: contract,
-        input_size: _,
-    } = gen(function, SCALE, 1);
+        let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+        let mut contract_context = ContractContext::new(contract_identifier.clone());
 
-    let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
-        Ok(res) => res,
-        Err(error) => {
-            panic!("Parsing error: {}", error.diagnostic.message);
-        }
-    };
-    global_context
-        .execute(|g| eval_all(&contract_ast.expressions, &mut contract_context, g))
-        .unwrap();
+        let GenOutput {
+            setup: _,
+            body: contract,
+            input_size: _,
+        } = bench_gen(function, spec.scale, 1);
 
-    let mut call_stack = CallStack::new();
-    let mut environment = Environment::new(
-        global_context.borrow_mut(),
-        &contract_context,
-        &mut call_stack,
-        None,
-        None,
-    );
+        let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
+            Ok(res) => res,
+            Err(error) => {
+                panic!("Parsing error: {}", error.diagnostic.message);
+            }
+        };
+        global_context
+            .execute(|g| eval_all(&contract_ast.expressions, &mut contract_context, g))
+            .unwrap();
 
-    let mut rng = rand::thread_rng();
-    let mut fn_names = Vec::new();
-    for i in 0..SCALE {
-        match rng.gen_range(0..3) {
-            0 => fn_names.push("nonsense".to_string()),
-            1 => fn_names.push(format!("fn-{}", i)),
-            2 => fn_names.push("no-op".to_string()),
-            _ => unimplemented!(),
-        }
-    }
+        let mut call_stack = CallStack::new();
+        let mut environment = Environment::new(
+            global_context.borrow_mut(),
+            &contract_context,
+            &mut call_stack,
+            None,
+            None,
+        );
 
-    group.throughput(Throughput::Bytes(0));
-    group.bench_with_input(BenchmarkId::from_parameter(0), &0, |b, &_| {
-        b.iter(|| {
-            for name in &fn_names {
-                lookup_function(name, &mut environment);
+        let mut rng = bench_rng();
+        let mut fn_names = Vec::new();
+        for i in 0..spec.scale {
+            match rng.gen_range(0..3) {
+                0 => fn_names.push("nonsense".to_string()),
+                1 => fn_names.push(format!("fn-{}", i)),
+                2 => fn_names.push("no-op".to_string()),
+                _ => unimplemented!(),
             }
-        })
+        }
+
+        group.throughput(Throughput::Bytes(0));
+        group.bench_with_input(BenchmarkId::from_parameter(0), &0, |b, &_| {
+            b.iter(|| {
+                for name in &fn_names {
+                    lookup_function(name, &mut environment);
+                }
+            })
+        });
     });
 }
 
-fn bench_lookup_variable_size(c: &mut Criterion) {
+fn bench_lookup_variable_size(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::LookupVariableSize;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let mut memory_backing_store = MemoryBackingStore::new();
         let clarity_db = memory_backing_store.as_clarity_db();
         let mut global_context =
@@ -1219,8 +1626,9 @@ fn bench_lookup_variable_size(c: &mut Criterion) {
             .insert(clar_val_name, inner_val.clone());
 
         // add more values to the local context
+        let mut rng = bench_rng();
         for _ in 0..1000 {
-            let name = ClarityName::try_from(helper_generate_rand_char_string(10)).unwrap();
+            let name = ClarityName::try_from(helper_generate_rand_char_string(10, &mut rng)).unwrap();
             local_context.variables.insert(name, inner_val.clone());
         }
 
@@ -1230,7 +1638,7 @@ fn bench_lookup_variable_size(c: &mut Criterion) {
             &input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..spec.scale {
                         lookup_variable(val_name, &local_context, &mut environment);
                     }
                 })
@@ -1243,15 +1651,16 @@ fn bench_lookup_variable_size(c: &mut Criterion) {
 /// ANALYSIS FUNCTIONS
 /// ////////////////////////////////////
 
-fn bench_analysis_option_cons(c: &mut Criterion) {
+fn bench_analysis_option_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_some(
         _ast: &mut ContractAST,
         _lc: &mut TypingContext,
         _tc: &mut TypeChecker,
         _i: u64,
+        scale: u16,
         _c: &mut LimitedCostTracker,
     ) {
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             bench_analysis_option_cons_helper(TypeSignature::BoolType);
         }
     }
@@ -1259,22 +1668,23 @@ fn bench_analysis_option_cons(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisOptionCons,
-        SCALE,
-        vec![1],
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_some,
     )
 }
 
-fn bench_analysis_option_check(c: &mut Criterion) {
+fn bench_analysis_option_check(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_is_response(
         _ast: &mut ContractAST,
         _lc: &mut TypingContext,
         _tc: &mut TypeChecker,
         _i: u64,
+        scale: u16,
         _c: &mut LimitedCostTracker,
     ) {
-        for _ in 0..SCALE {
+        for _ in 0..scale {
            bench_analysis_option_check_helper(TypeSignature::ResponseType(Box::new((TypeSignature::BoolType, TypeSignature::BoolType))));
         }
     }
@@ -1282,20 +1692,21 @@ fn bench_analysis_option_check(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisOptionCheck,
-        SCALE,
-        vec![1],
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_is_response,
     )
 }
 
 // Cost of the match statement in inner_type_check - doesn't include cost of calls from the match
-fn bench_analysis_visit(c: &mut Criterion) {
+fn bench_analysis_visit(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_type_check(
         contract_ast: &mut ContractAST,
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         _i: u64,
+        _scale: u16,
         _c: &mut LimitedCostTracker,
     ) {
         for exp in &contract_ast.expressions {
@@ -1306,24 +1717,25 @@ fn bench_analysis_visit(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisVisit,
-        SCALE,
-        vec![1],
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_type_check,
     )
 }
 
-fn bench_analysis_bind_name(c: &mut Criterion) {
+fn bench_analysis_bind_name(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_type_check_define<T: CostTracker>(
         _ast: &mut ContractAST,
         _lc: &mut TypingContext,
         type_checker: &mut TypeChecker,
         input_size: u64,
+        scale: u16,
         _c: &mut T,
     ) {
         type_checker.contract_context.clear_variable_types();
         let type_sig = SIZED_TYPE_SIG.get(&input_size).unwrap();
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             type_checker.bench_analysis_bind_name_helper(type_sig.clone());
         }
     }
@@ -1331,23 +1743,24 @@ fn bench_analysis_bind_name(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisBindName,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_type_check_define,
     )
 }
 
-fn bench_analysis_list_items_check(c: &mut Criterion) {
+fn bench_analysis_list_items_check(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_list_cons<T: CostTracker>(
         _ast: &mut ContractAST,
         _lc: &mut TypingContext,
         _tc: &mut TypeChecker,
         input_size: u64,
+        scale: u16,
         _c: &mut T,
     ) {
         let type_sig_list = vec![SIZED_TYPE_SIG.get(&input_size).unwrap().clone()];
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             bench_analysis_list_items_check_helper(&*type_sig_list);
         }
     }
@@ -1355,14 +1768,14 @@ fn bench_analysis_list_items_check(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisListItemsCheck,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_list_cons,
     )
 }
 
-fn bench_analysis_check_tuple_get(c: &mut Criterion) {
+fn bench_analysis_check_tuple_get(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     // SIZED_TUPLE_SIG is a lazy static. This setup function makes sur eit is initialized before
     // the benchmarking function is called.
     fn setup_fn<T: CostTracker>(
@@ -1380,6 +1793,7 @@ fn bench_analysis_check_tuple_get(c: &mut Criterion) {
         _lc: &mut TypingContext,
         type_checker: &mut TypeChecker,
         i: u64,
+        _scale: u16,
         _c: &mut T,
     ) {
         let tuple_type_sig = SIZED_TUPLE_SIG.get(&i).unwrap();
@@ -1389,23 +1803,24 @@ fn bench_analysis_check_tuple_get(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisCheckTupleGet,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         setup_fn,
         eval_check_special_get,
     )
 }
 
-fn bench_analysis_check_tuple_merge(c: &mut Criterion) {
+fn bench_analysis_check_tuple_merge(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_merge<T: CostTracker>(
         _ast: &mut ContractAST,
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         input_size: u64,
+        scale: u16,
         _c: &mut T,
     ) {
         let sized_tuple_sig = TypeSignature::TupleType(SIZED_TUPLE_SIG.get(&input_size).unwrap().clone());
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             bench_analysis_check_tuple_merge_helper(type_checker, sized_tuple_sig.clone(), sized_tuple_sig.clone(), local_context);
         }
 
@@ -1414,20 +1829,21 @@ fn bench_analysis_check_tuple_merge(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisCheckTupleMerge,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_merge,
     )
 }
 
 
-fn bench_analysis_check_tuple_cons(c: &mut Criterion) {
+fn bench_analysis_check_tuple_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_tuple_cons<T: CostTracker>(
         contract_ast: &mut ContractAST,
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         _is: u64,
+        _scale: u16,
         _c: &mut T,
     ) {
         type_checker.type_map.delete_all();
@@ -1440,23 +1856,24 @@ fn bench_analysis_check_tuple_cons(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisCheckTupleCons,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_tuple_cons,
     )
 }
 
-fn bench_analysis_tuple_items_check(c: &mut Criterion) {
+fn bench_analysis_tuple_items_check(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_tuple_cons<T: CostTracker>(
         _ast: &mut ContractAST,
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         input_size: u64,
+        scale: u16,
         _c: &mut T,
     ) {
         let type_sig = SIZED_TYPE_SIG.get(&input_size).unwrap();
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             bench_analysis_tuple_items_check_helper(type_checker, type_sig.clone(), local_context);
         }
     }
@@ -1464,23 +1881,24 @@ fn bench_analysis_tuple_items_check(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisTupleItemsCheck,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_tuple_cons,
     )
 }
 
-fn bench_analysis_check_let(c: &mut Criterion) {
+fn bench_analysis_check_let(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_check_special_let<T: CostTracker>(
         _ast: &mut ContractAST,
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         input_size: u64,
+        scale: u16,
         _c: &mut T,
     ) {
         let type_sig_list = TYPE_SIG_LIST.get(&input_size).unwrap();
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             type_checker.bench_analysis_check_let_helper(type_sig_list.clone(), local_context);
         }
     }
@@ -1488,14 +1906,14 @@ fn bench_analysis_check_let(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisCheckLet,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_check_special_let,
     )
 }
 
-fn bench_analysis_lookup_function(c: &mut Criterion) {
+fn bench_analysis_lookup_function(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AnalysisLookupFunction;
     let mut group = c.benchmark_group(function.to_string());
 
@@ -1504,9 +1922,9 @@ fn bench_analysis_lookup_function(c: &mut Criterion) {
     let mut analysis_db = null_store.as_analysis_db();
     let mut type_checker = TypeChecker::new(&mut analysis_db, cost_tracker.clone());
 
-    let mut rng = rand::thread_rng();
+    let mut rng = bench_rng();
     let mut fn_names = Vec::new();
-    for _ in 0..SCALE {
+    for _ in 0..spec.scale {
         let fn_name = match rng.gen_range(0..3) {
             0 => {
                 // return simple native function
@@ -1538,7 +1956,7 @@ fn bench_analysis_lookup_function(c: &mut Criterion) {
     });
 }
 
-fn bench_analysis_type_annotate(c: &mut Criterion) {
+fn bench_analysis_type_annotate(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn setup_fn(
         contract_ast: &mut ContractAST,
         _lc: &mut TypingContext,
@@ -1560,6 +1978,7 @@ fn bench_analysis_type_annotate(c: &mut Criterion) {
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         input_size: u64,
+        _scale: u16,
         _c: &mut T,
     ) {
         type_checker.type_map.delete_all();
@@ -1572,14 +1991,14 @@ fn bench_analysis_type_annotate(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisTypeAnnotate,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         setup_fn,
         eval_inner_type_check,
     )
 }
 
-fn bench_analysis_type_check(c: &mut Criterion) {
+fn bench_analysis_type_check(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn setup_fn<T: CostTracker>(
         _ca: &mut ContractAST,
         _lc: &mut TypingContext,
@@ -1596,10 +2015,11 @@ fn bench_analysis_type_check(c: &mut Criterion) {
         _lc: &mut TypingContext,
         type_checker: &mut TypeChecker,
         i: u64,
+        scale: u16,
         _c: &mut T,
     ) {
         let tuple_type_sig = SIZED_TYPE_SIG.get(&i).unwrap().clone();
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             type_checker.track_return_type(tuple_type_sig.clone());
         }
     }
@@ -1607,18 +2027,18 @@ fn bench_analysis_type_check(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisTypeCheck,
-        100,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         setup_fn,
         eval_track_return_type,
     )
 }
 
-fn bench_analysis_iterable_func(c: &mut Criterion) {
+fn bench_analysis_iterable_func(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AnalysisIterableFunc;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let type_sig_list = vec![TypeSignature::SequenceType(SequenceSubtype::BufferType(BufferLength::try_from(15u32).unwrap())); *input_size as usize];
 
         let mut local_context = TypingContext::new();
@@ -1634,7 +2054,7 @@ fn bench_analysis_iterable_func(c: &mut Criterion) {
             &input_size,
             |b, &_| {
                 b.iter(|| {
-                    for _ in 0..SCALE {
+                    for _ in 0..spec.scale {
                         bench_analysis_iterable_function_helper(&mut type_checker, &type_sig_list, &mut local_context);
                     }
                 })
@@ -1644,18 +2064,18 @@ fn bench_analysis_iterable_func(c: &mut Criterion) {
 }
 
 // this is the cost of storing the contract - measure contract analysis serialization
-fn bench_analysis_storage(c: &mut Criterion) {
+fn bench_analysis_storage(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::AnalysisStorage;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
 
         let GenOutput {
             setup: _,
             body: contract,
             input_size: _,
-        } = gen(function, SCALE, *input_size);
+        } = bench_gen(function, spec.scale, *input_size);
 
         let mut contract_ast = match ast::build_ast(&contract_identifier, &contract, &mut ()) {
             Ok(res) => res,
@@ -1713,7 +2133,7 @@ fn bench_analysis_storage(c: &mut Criterion) {
     }
 }
 
-fn bench_analysis_type_lookup(c: &mut Criterion) {
+fn bench_analysis_type_lookup(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn setup_fn(
         contract_ast: &mut ContractAST,
         _lc: &mut TypingContext,
@@ -1736,6 +2156,7 @@ fn bench_analysis_type_lookup(c: &mut Criterion) {
         _lc: &mut TypingContext,
         type_checker: &mut TypeChecker,
         _i: u64,
+        _scale: u16,
         _c: &mut LimitedCostTracker,
     ) {
         for exp in &contract_ast.expressions {
@@ -1748,14 +2169,14 @@ fn bench_analysis_type_lookup(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisTypeLookup,
-        SCALE,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         setup_fn,
         eval_check_special_mint_asset,
     )
 }
 
-fn bench_analysis_lookup_variable_const(c: &mut Criterion) {
+fn bench_analysis_lookup_variable_const(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn setup_fn(
         contract_ast: &mut ContractAST,
         _lc: &mut TypingContext,
@@ -1763,7 +2184,7 @@ fn bench_analysis_lookup_variable_const(c: &mut Criterion) {
         _is: u64,
         _c: &mut LimitedCostTracker,
     ) {
-        let mut rng = rand::thread_rng();
+        let mut rng = bench_rng();
         let type_sig_list = [IntType, BoolType, NoType, PrincipalType, UIntType];
         for exp in &contract_ast.expressions {
             let var_name = exp.match_atom().unwrap();
@@ -1779,9 +2200,10 @@ fn bench_analysis_lookup_variable_const(c: &mut Criterion) {
         local_context: &mut TypingContext,
         type_checker: &mut TypeChecker,
         _i: u64,
+        scale: u16,
         _c: &mut LimitedCostTracker,
     ) {
-        for i in 0..SCALE {
+        for i in 0..scale {
             let var_name = format!("var-{}", i);
             type_checker.lookup_variable(&var_name, local_context);
 
@@ -1791,8 +2213,8 @@ fn bench_analysis_lookup_variable_const(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AnalysisLookupVariableConst,
-        SCALE,
-        vec![1],
+        spec.scale,
+        spec.input_sizes(),
         setup_fn,
         eval_lookup_variable,
     )
@@ -1801,7 +2223,7 @@ fn bench_analysis_lookup_variable_const(c: &mut Criterion) {
 /// ////////////////////////////////////
 /// AST FUNCTIONS
 /// ////////////////////////////////////
-fn bench_ast_parse(c: &mut Criterion) {
+fn bench_ast_parse(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     // SIZED_CONTRACTS will be generated the first time it is "invoked" in the code since it is
     //  defined in a lazy_static! macro call. The setup_fn uses the object to make sure it is
     //  created before being invoked in the actual benchmark.
@@ -1820,11 +2242,12 @@ fn bench_ast_parse(c: &mut Criterion) {
         _lc: &mut TypingContext,
         _tc: &mut TypeChecker,
         input_size: u64,
+        scale: u16,
         cost_tracker: &mut T,
     ) {
         let contract = SIZED_CONTRACTS.get(&input_size).unwrap();
         let contract_id = QualifiedContractIdentifier::transient();
-        for _ in 0..SCALE {
+        for _ in 0..scale {
             build_ast(&contract_id, &contract, cost_tracker);
         }
     }
@@ -1832,468 +2255,466 @@ fn bench_ast_parse(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::AstParse,
-        1,
-        INPUT_SIZES.into(),
+        spec.scale,
+        spec.input_sizes(),
         setup_fn,
         eval_build_ast,
     )
 }
 
-fn bench_add(c: &mut Criterion) {
+fn bench_add(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Add,
-        SCALE,
-        Some(INPUT_SIZES_ARITHMETIC.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_sub(c: &mut Criterion) {
+fn bench_sub(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Sub,
-        SCALE,
-        Some(INPUT_SIZES_ARITHMETIC.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_mul(c: &mut Criterion) {
+fn bench_mul(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Mul,
-        SCALE,
-        Some(INPUT_SIZES_ARITHMETIC.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_div(c: &mut Criterion) {
+fn bench_div(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Div,
-        SCALE,
-        Some(INPUT_SIZES_ARITHMETIC.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_le(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Le, SCALE, None, false, None)
+fn bench_le(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Le, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_leq(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Leq, SCALE, None, false, None)
+fn bench_leq(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Leq, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_ge(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Ge, SCALE, None, false, None)
+fn bench_ge(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Ge, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_geq(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Geq, SCALE, None, false, None)
+fn bench_geq(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Geq, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
 // boolean functions
-fn bench_and(c: &mut Criterion) {
+fn bench_and(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::And,
-        SCALE,
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_or(c: &mut Criterion) {
+fn bench_or(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Or,
-        SCALE,
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_xor(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Xor, SCALE, None, false, None)
+fn bench_xor(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Xor, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_not(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Not, SCALE, None, false, None)
+fn bench_not(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Not, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
 // note: only testing is-eq when the values are bools; could try doing it with ints?
-fn bench_eq(c: &mut Criterion) {
+fn bench_eq(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Eq,
-        SCALE,
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_mod(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Mod, SCALE, None, false, None)
+fn bench_mod(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Mod, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_pow(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Pow, SCALE, None, false, None)
+fn bench_pow(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Pow, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_sqrti(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Sqrti, SCALE, None, false, None)
+fn bench_sqrti(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Sqrti, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_log2(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Log2, SCALE, None, false, None)
+fn bench_log2(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Log2, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_tuple_get(c: &mut Criterion) {
+fn bench_tuple_get(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::TupleGet,
-        SCALE,
-        Some(MORE_INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_tuple_merge(c: &mut Criterion) {
+fn bench_tuple_merge(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::TupleMerge,
-        SCALE,
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_tuple_cons(c: &mut Criterion) {
+fn bench_tuple_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::TupleCons,
-        SCALE,
-        Some(MORE_INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
 // hash functions
-fn bench_hash160(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Hash160, SCALE, Some(INPUT_SIZES_DATA.into()), false, None)
+fn bench_hash160(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Hash160, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_sha256(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Sha256, SCALE, Some(INPUT_SIZES_DATA.into()), false, None)
+fn bench_sha256(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Sha256, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_sha512(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Sha512, SCALE, Some(INPUT_SIZES_DATA.into()), false, None)
+fn bench_sha512(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Sha512, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_sha512t256(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Sha512t256, SCALE, Some(INPUT_SIZES_DATA.into()), false, None)
+fn bench_sha512t256(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Sha512t256, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_keccak256(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Keccak256, SCALE, Some(INPUT_SIZES_DATA.into()), false, None)
+fn bench_keccak256(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Keccak256, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_secp256k1recover(c: &mut Criterion) {
+fn bench_secp256k1recover(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Secp256k1recover,
-        SCALE,
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_secp256k1verify(c: &mut Criterion) {
+fn bench_secp256k1verify(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Secp256k1verify,
-        SCALE,
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_create_ft_old(c: &mut Criterion) {
+fn bench_create_ft_old(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::CreateFt,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
 // note: verify that we want a warmed-up marf for this
-fn bench_create_ft(c: &mut Criterion) {
+fn bench_create_ft(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::CreateFt;
     let mut group = c.benchmark_group(function.to_string());
 
     let headers_db = SimHeadersDB::new();
-    let mut memory_backing_store = MemoryBackingStore::new();
+    storage::with_store(*STORAGE, |store| {
+        let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-    let clarity_db =
-        ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
-
-    let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-    global_context.begin();
+        let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+        global_context.begin();
 
-    let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-    let mut contract_context = ContractContext::new(contract_identifier.clone());
+        let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+        let mut contract_context = ContractContext::new(contract_identifier.clone());
 
-    group.throughput(Throughput::Bytes(0));
-    group.bench_with_input(BenchmarkId::from_parameter(0), &0, |b, &_| {
-        b.iter(|| {
-            for _ in 0..SCALE {
-                bench_create_ft_in_context(&mut global_context, &mut contract_context);
-            }
-        })
+        group.throughput(Throughput::Bytes(0));
+        group.bench_with_input(BenchmarkId::from_parameter(0), &0, |b, &_| {
+            b.iter(|| {
+                for _ in 0..spec.scale {
+                    bench_create_ft_in_context(&mut global_context, &mut contract_context);
+                }
+            })
+        });
     });
 }
 
-fn bench_mint_ft(c: &mut Criterion) {
+fn bench_mint_ft(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FtMint,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_ft_transfer(c: &mut Criterion) {
+fn bench_ft_transfer(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FtTransfer,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_ft_balance(c: &mut Criterion) {
+fn bench_ft_balance(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FtBalance,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_ft_supply(c: &mut Criterion) {
+fn bench_ft_supply(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FtSupply,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_ft_burn(c: &mut Criterion) {
+fn bench_ft_burn(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FtBurn,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
 // note: verify that we want a warmed-up marf for this
-fn bench_create_nft(c: &mut Criterion) {
+fn bench_create_nft(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::CreateNft;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
-        let mut memory_backing_store = MemoryBackingStore::new();
-
-        let headers_db = SimHeadersDB::new();
-        let clarity_db =
-            ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
+    for input_size in &spec.input_sizes() {
+        storage::with_store(*STORAGE, |store| {
+            let headers_db = SimHeadersDB::new();
+            let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-        let mut global_context =
-            GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-        global_context.begin();
+            let mut global_context =
+                GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+            global_context.begin();
 
-        let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-        let mut contract_context = ContractContext::new(contract_identifier.clone());
+            let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+            let mut contract_context = ContractContext::new(contract_identifier.clone());
 
-        let asset_type = SIZED_TYPE_SIG.get(input_size).unwrap();
-        let asset_type_size = asset_type.size();
-        group.throughput(Throughput::Bytes(asset_type_size as u64));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(asset_type_size),
-            &asset_type_size,
-            |b, &_| {
-                b.iter(|| {
-                    for _ in 0..SCALE {
-                        bench_create_nft_in_context(
-                            &mut global_context,
-                            &mut contract_context,
-                            &asset_type,
-                        );
-                    }
-                })
-            },
-        );
+            let asset_type = SIZED_TYPE_SIG.get(input_size).unwrap();
+            let asset_type_size = asset_type.size();
+            group.throughput(Throughput::Bytes(asset_type_size as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(asset_type_size),
+                &asset_type_size,
+                |b, &_| {
+                    b.iter(|| {
+                        for _ in 0..spec.scale {
+                            bench_create_nft_in_context(
+                                &mut global_context,
+                                &mut contract_context,
+                                &asset_type,
+                            );
+                        }
+                    })
+                },
+            );
+        });
     }
 }
 
-fn bench_nft_mint(c: &mut Criterion) {
+fn bench_nft_mint(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::NftMint,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_nft_transfer(c: &mut Criterion) {
+fn bench_nft_transfer(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::NftTransfer,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_nft_owner(c: &mut Criterion) {
+fn bench_nft_owner(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::NftOwner,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_nft_burn(c: &mut Criterion) {
+fn bench_nft_burn(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::NftBurn,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_is_none(c: &mut Criterion) {
+fn bench_is_none(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::IsNone,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_is_some(c: &mut Criterion) {
+fn bench_is_some(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::IsSome,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_is_ok(c: &mut Criterion) {
+fn bench_is_ok(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::IsOkay,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_is_err(c: &mut Criterion) {
+fn bench_is_err(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::IsErr,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_unwrap(c: &mut Criterion) {
+fn bench_unwrap(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Unwrap,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_unwrap_ret(c: &mut Criterion) {
+fn bench_unwrap_ret(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::UnwrapRet,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_unwrap_err(c: &mut Criterion) {
+fn bench_unwrap_err(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::UnwrapErr,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_unwrap_err_or_ret(c: &mut Criterion) {
+fn bench_unwrap_err_or_ret(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::UnwrapErrOrRet,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
@@ -2301,488 +2722,550 @@ fn bench_unwrap_err_or_ret(c: &mut Criterion) {
 
 // note: verify that we want a warmed-up marf for this
 // note: time to clone the type signature for the value in the benching code may be significant
-fn bench_create_map(c: &mut Criterion) {
+fn bench_create_map(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::CreateMap;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let headers_db = SimHeadersDB::new();
-        let mut memory_backing_store = MemoryBackingStore::new();
-
-        let clarity_db =
-            ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
+        storage::with_store(*STORAGE, |store| {
+            let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-        let mut global_context =
-            GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-        global_context.begin();
+            let mut global_context =
+                GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+            global_context.begin();
 
-        let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-        let mut contract_context = ContractContext::new(contract_identifier.clone());
+            let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+            let mut contract_context = ContractContext::new(contract_identifier.clone());
 
-        let key_type = TypeSignature::BoolType;
-        let value_type = SIZED_TYPE_SIG.get(input_size).unwrap();
-        let total_size = (key_type.size() + value_type.size()) as u64;
+            let key_type = TypeSignature::BoolType;
+            let value_type = SIZED_TYPE_SIG.get(input_size).unwrap();
+            let total_size = (key_type.size() + value_type.size()) as u64;
 
-        group.throughput(Throughput::Bytes(total_size));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(total_size),
-            &total_size,
-            |b, &_| {
-                b.iter(|| {
-                    for _ in 0..SCALE {
-                        bench_create_map_in_context(
-                            &mut global_context,
-                            &mut contract_context,
-                            key_type.clone(),
-                            value_type.clone(),
-                        );
-                    }
-                })
-            },
-        );
+            group.throughput(Throughput::Bytes(total_size));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(total_size),
+                &total_size,
+                |b, &_| {
+                    b.iter(|| {
+                        for _ in 0..spec.scale {
+                            bench_create_map_in_context(
+                                &mut global_context,
+                                &mut contract_context,
+                                key_type.clone(),
+                                value_type.clone(),
+                            );
+                        }
+                    })
+                },
+            );
+        });
     }
 }
 
 // note: verify that we want a warmed-up marf for this
 // note: time to clone the type signature for the value may be significant
-fn bench_create_var(c: &mut Criterion) {
+fn bench_create_var(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let function = ClarityCostFunction::CreateVar;
     let mut group = c.benchmark_group(function.to_string());
 
-    for input_size in &INPUT_SIZES {
+    for input_size in &spec.input_sizes() {
         let headers_db = SimHeadersDB::new();
-        let mut memory_backing_store = MemoryBackingStore::new();
+        storage::with_store(*STORAGE, |store| {
+            let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-        let clarity_db =
-            ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
+            let mut global_context =
+                GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
+            global_context.begin();
 
-        let mut global_context =
-            GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-        global_context.begin();
+            let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
+            let mut contract_context = ContractContext::new(contract_identifier.clone());
 
-        let contract_identifier = QualifiedContractIdentifier::local(&*format!("c{}", 0)).unwrap();
-        let mut contract_context = ContractContext::new(contract_identifier.clone());
+            let value_type = SIZED_TYPE_SIG.get(input_size).unwrap();
+            let value_type_size = value_type.size();
+            let value = helper_make_value_for_sized_type_sig(*input_size, &mut bench_rng());
+            assert!(value_type.admits(&value));
+            assert_eq!(value_type.size(), value.size());
 
-        let value_type = SIZED_TYPE_SIG.get(input_size).unwrap();
-        let value_type_size = value_type.size();
-        let value = helper_make_value_for_sized_type_sig(*input_size);
-        assert!(value_type.admits(&value));
-        assert_eq!(value_type.size(), value.size());
+            group.throughput(Throughput::Bytes(value_type_size as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(value_type_size),
+                &value_type_size,
+                |b, &_| {
+                    b.iter(|| {
+                        for _ in 0..spec.scale {
+                            bench_create_var_in_context(
+                                &mut global_context,
+                                &mut contract_context,
+                                value_type.clone(),
+                                value.clone(),
+                            );
+                        }
+                    })
+                },
+            );
+        });
+    }
+}
 
-        group.throughput(Throughput::Bytes(value_type_size as u64));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(value_type_size),
-            &value_type_size,
-            |b, &_| {
-                b.iter(|| {
-                    for _ in 0..SCALE {
-                        bench_create_var_in_context(
-                            &mut global_context,
-                            &mut contract_context,
-                            value_type.clone(),
-                            value.clone(),
-                        );
-                    }
+fn bench_wrapped_data_function(mut group: BenchmarkGroup<Metered>, cost_function: ClarityCostFunction, input_sizes: Vec<u64>, scale: u16) {
+    for input_size in input_sizes.iter() {
+        let probe = |probed| {
+            prepare_wrapped_data_function(cost_function, scale, *input_size, Some(probed),
+                |list_size, list_len, global_context, contract_context| {
+                    let before = global_context.cost_track.get_total();
+                    execute_wrapped_data_function(global_context, contract_context, list_len);
+                    (list_size, Charges::between(&before, &global_context.cost_track.get_total()))
                 })
-            },
-        );
+        };
+        if probe_bench(cost_function, probe) {
+            continue;
+        }
+
+        prepare_wrapped_data_function(cost_function, scale, *input_size, None,
+            |list_size, list_len, global_context, contract_context| {
+                group.throughput(Throughput::Bytes(list_size.clone() as u64));
+                group.bench_with_input(
+                    BenchmarkId::from_parameter(list_size),
+                    &list_size,
+                    |b, &_| {
+                        b.iter(|| execute_wrapped_data_function(global_context, contract_context, list_len))
+                    },
+                );
+            });
     }
 }
 
-fn bench_wrapped_data_function(mut group: BenchmarkGroup<WallTime>, cost_function: ClarityCostFunction, input_sizes: Vec<u64>, scale: u16) {
-    for input_size in input_sizes.iter() {
-        let mut memory_backing_store = MemoryBackingStore::new();
-        let clarity_db = memory_backing_store.as_clarity_db();
+/// Evaluates the setup of a wrapped data function benchmark, and hands its list size and
+/// length and contract to `then`. `probed` is as in `prepare_bench`.
+fn prepare_wrapped_data_function<T, R>(
+    cost_function: ClarityCostFunction,
+    scale: u16,
+    input_size: u64,
+    probed: Option<ClarityCostFunction>,
+    then: T,
+) -> R
+where
+    T: FnOnce(u64, u64, &mut GlobalContext, &ContractContext) -> R,
+{
+    let GenOutput {
+        setup: pre_contract_opt,
+        body: _,
+        input_size: _,
+    } = bench_gen(cost_function, scale, input_size);
 
-        let mut global_context = GlobalContext::new(false, clarity_db, LimitedCostTracker::new_free());
-        global_context.begin();
+    let list_len = helper_gen_clarity_list_type(input_size).1;
+    let list_size = 5 + 17 * list_len;
 
-        let GenOutput {
-            setup: pre_contract_opt,
-            body: _,
-            input_size: _,
-        } = gen(cost_function, scale, *input_size);
+    let contract_identifier =
+        QualifiedContractIdentifier::local(&*format!("c{}", list_size)).unwrap();
+    let pre_contract_identifier =
+        QualifiedContractIdentifier::local(&*format!("pre{}", list_size)).unwrap();
+
+    storage::with_prepared_store(*STORAGE,
+        |store| {
+            if let Some(probed) = probed {
+                cost_probe::install_probe_contracts(store, probed);
+            }
 
-        let list_len = helper_gen_clarity_list_type(*input_size).1;
-        let list_size = 5 + 17 * list_len;
+            let mut contract_context = ContractContext::new(contract_identifier.clone());
+            if let (StorageBackend::Marf, Some(pre_contract)) = (*STORAGE, &pre_contract_opt) {
+                let clarity_db = ClarityDatabase::new(store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB);
+                commit_setup(clarity_db, &mut contract_context, &pre_contract_identifier, pre_contract);
+            }
+            contract_context
+        },
+        |store, mut contract_context| {
+            let mut clarity_db = ClarityDatabase::new(store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB);
 
-        let contract_identifier =
-            QualifiedContractIdentifier::local(&*format!("c{}", list_size)).unwrap();
-        let mut contract_context = ContractContext::new(contract_identifier.clone());
-        let publisher: PrincipalData = contract_context.contract_identifier.issuer.clone().into();
-
-        match pre_contract_opt {
-            Some(pre_contract) => {
-                let pre_contract_identifier =
-                    QualifiedContractIdentifier::local(&*format!("pre{}", list_size))
-                        .unwrap();
-                let pre_contract_ast =
-                    match ast::build_ast(&pre_contract_identifier, &pre_contract, &mut ()) {
-                        Ok(res) => res,
-                        Err(error) => {
-                            panic!("Parsing error: {}", error.diagnostic.message);
-                        }
-                    };
-                global_context
-                    .execute(|g| eval_all(&pre_contract_ast.expressions, &mut contract_context, g))
-                    .unwrap();
+            let cost_tracker = match probed {
+                Some(_) => cost_probe::unlimited_tracker(&mut clarity_db).unwrap_or_else(|e| panic!("{}", e)),
+                None => LimitedCostTracker::new_free(),
+            };
+            let mut global_context = GlobalContext::new(false, clarity_db, cost_tracker);
+            global_context.begin();
+
+            if let (StorageBackend::Memory, Some(pre_contract)) = (*STORAGE, &pre_contract_opt) {
+                eval_setup(&mut global_context, &mut contract_context, &pre_contract_identifier, pre_contract);
             }
-            _ => {}
-        }
 
-        group.throughput(Throughput::Bytes(list_size.clone() as u64));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(list_size),
-            &list_size,
-            |b, &_| {
-                b.iter(|| {
-                    global_context
-                        .execute(|g| {
-                            let mut call_stack = CallStack::new();
-                            let mut env = Environment::new(g, &contract_context, &mut call_stack, Some(publisher.clone()), Some(publisher.clone()));
-                            let f = lookup_function("execute", &mut env).unwrap();
-                            let list = Value::list_from((0..list_len).map(|i| Value::UInt(i as u128)).collect()).unwrap();
-                            apply(&f, &[SymbolicExpression::literal_value(list)], &mut env, &LocalContext::new())
-                        })
-                        .unwrap()
-                })
-            },
-        );
-    }
+            then(list_size, list_len, &mut global_context, &contract_context)
+        })
+}
+
+fn execute_wrapped_data_function(global_context: &mut GlobalContext, contract_context: &ContractContext, list_len: u64) {
+    let publisher: PrincipalData = contract_context.contract_identifier.issuer.clone().into();
+    global_context
+        .execute(|g| {
+            let mut call_stack = CallStack::new();
+            let mut env = Environment::new(g, contract_context, &mut call_stack, Some(publisher.clone()), Some(publisher.clone()));
+            let f = lookup_function("execute", &mut env).unwrap();
+            let list = Value::list_from((0..list_len).map(|i| Value::UInt(i as u128)).collect()).unwrap();
+            apply(&f, &[SymbolicExpression::literal_value(list)], &mut env, &LocalContext::new())
+        })
+        .unwrap();
 }
 
-fn bench_set_var(c: &mut Criterion) {
+fn bench_set_var(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let cost_function = ClarityCostFunction::SetVar;
     let mut group = c.benchmark_group(cost_function.to_string());
     group.sample_size(50);
-    bench_wrapped_data_function(group, cost_function, INPUT_SIZES_DATA.into(), SCALE)
+    bench_wrapped_data_function(group, cost_function, spec.input_sizes(), spec.scale)
 }
 
-fn bench_fetch_var(c: &mut Criterion) {
+fn bench_fetch_var(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FetchVar,
-        SCALE.into(),
-        Some(INPUT_SIZES_DATA.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_print(c: &mut Criterion) {
+fn bench_print(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let cost_function = ClarityCostFunction::Print;
     let group = c.benchmark_group(cost_function.to_string());
-    bench_wrapped_data_function(group, cost_function, INPUT_SIZES_DATA.into(), SCALE)
+    bench_wrapped_data_function(group, cost_function, spec.input_sizes(), spec.scale)
 }
 
-fn bench_if(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::If, SCALE.into(), None, false, None)
+fn bench_if(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::If, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_asserts(c: &mut Criterion) {
+fn bench_asserts(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Asserts,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_ok_cons(c: &mut Criterion) {
+fn bench_ok_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::OkCons,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_err_cons(c: &mut Criterion) {
+fn bench_err_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::ErrCons,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_some_cons(c: &mut Criterion) {
+fn bench_some_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::SomeCons,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_concat(c: &mut Criterion) {
+fn bench_concat(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Concat,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_as_max_len(c: &mut Criterion) {
+fn bench_as_max_len(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::AsMaxLen,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_begin(c: &mut Criterion) {
+fn bench_begin(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Begin,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_bind_name(c: &mut Criterion) {
+fn bench_bind_name(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::BindName,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_default_to(c: &mut Criterion) {
+fn bench_default_to(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::DefaultTo,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_try(c: &mut Criterion) {
+fn bench_try(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::TryRet,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_int_cast(c: &mut Criterion) {
+fn bench_int_cast(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::IntCast,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_set_entry(c: &mut Criterion) {
+fn bench_set_entry(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let cost_function = ClarityCostFunction::SetEntry;
     let group = c.benchmark_group(cost_function.to_string());
-    bench_wrapped_data_function(group, cost_function, INPUT_SIZES_DATA.into(), SCALE)
+    bench_wrapped_data_function(group, cost_function, spec.input_sizes(), spec.scale)
 }
 
-fn bench_fetch_entry(c: &mut Criterion) {
+fn bench_fetch_entry(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::FetchEntry,
-        SCALE.into(),
-        Some(INPUT_SIZES_DATA.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_match(c: &mut Criterion) {
+fn bench_match(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Match,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_let(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Let, SCALE.into(), Some(INPUT_SIZES.into()), false, None)
+fn bench_let(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Let, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_index_of(c: &mut Criterion) {
+fn bench_index_of(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::IndexOf,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_element_at(c: &mut Criterion) {
+fn bench_element_at(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::ElementAt,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_len(c: &mut Criterion) {
-    bench_with_input_sizes(c, ClarityCostFunction::Len, SCALE.into(), None, false, None)
+fn bench_len(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    bench_with_input_sizes(c, ClarityCostFunction::Len, spec.scale, Some(spec.input_sizes()), false, None)
 }
 
-fn bench_list_cons(c: &mut Criterion) {
+fn bench_list_cons(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::ListCons,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_append(c: &mut Criterion) {
+fn bench_append(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Append,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_filter(c: &mut Criterion) {
+fn bench_filter(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Filter,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
 // note: this takes a lot of time to run; can shorten the list sizes to make it faster
-fn bench_map(c: &mut Criterion) {
+fn bench_map(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Map,
-        SCALE.into(),
-        Some(INPUT_SIZES.into()),
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_fold(c: &mut Criterion) {
+fn bench_fold(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::Fold,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_block_info(c: &mut Criterion) {
+fn bench_block_info(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::BlockInfo,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         true,
         None,
     )
 }
 
-fn bench_at_block(c: &mut Criterion) {
+fn bench_at_block(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::AtBlock,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         true,
         None,
     )
 }
 
-fn bench_load_contract(c: &mut Criterion) {
+fn bench_load_contract(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let mut group = c.benchmark_group(ClarityCostFunction::LoadContract.to_string());
 
-    let headers_db = SimHeadersDB::new();
-    let mut memory_backing_store = MemoryBackingStore::new();
-    let clarity_db =
-        ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
-
-    let mut owned_env = OwnedEnvironment::new_free(true, clarity_db);
-    owned_env.begin();
+    let contracts: Vec<(QualifiedContractIdentifier, String)> = spec
+        .input_sizes()
+        .iter()
+        .map(|size| {
+            let contract_identifier =
+                QualifiedContractIdentifier::local(format!("contract{}", size).as_str()).unwrap();
+
+            let GenOutput {
+                setup: _,
+                body: contract,
+                input_size: _,
+            } = bench_harness_code(ClarityCostFunction::LoadContract, "contract", *size, || {
+                gen_read_only_func(*size as u16, &mut bench_rng())
+            });
+
+            (contract_identifier, contract)
+        })
+        .collect();
+    let initialize_contracts = |env: &mut Environment| {
+        for (contract_identifier, contract) in &contracts {
+            env.initialize_contract(contract_identifier.clone(), contract)
+                .unwrap();
+        }
+    };
 
-    let mut env = owned_env.get_exec_environment(None);
+    let headers_db = SimHeadersDB::new();
+    storage::with_prepared_store(*STORAGE,
+        |store| {
+            // on a MARF, the contracts are loaded from the committed parent block
+            if *STORAGE == StorageBackend::Marf {
+                let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
+                let mut owned_env = OwnedEnvironment::new_free(true, clarity_db);
+                owned_env.begin();
+                initialize_contracts(&mut owned_env.get_exec_environment(None));
+                owned_env.commit().unwrap();
+            }
+        },
+        |store, _| {
+            let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-    for size in INPUT_SIZES.iter() {
-        let contract_identifier =
-            QualifiedContractIdentifier::local(format!("contract{}", size).as_str()).unwrap();
+            let mut owned_env = OwnedEnvironment::new_free(true, clarity_db);
+            owned_env.begin();
 
-        let GenOutput {
-            setup: _,
-            body: contract,
-            input_size: _,
-        } = gen_read_only_func(*size as u16);
+            let mut env = owned_env.get_exec_environment(None);
+            if *STORAGE == StorageBackend::Memory {
+                initialize_contracts(&mut env);
+            }
 
-        env.initialize_contract(contract_identifier.clone(), &contract)
-            .unwrap();
-        
-        let contract_size = env.global_context.database.get_contract_size(&contract_identifier).unwrap();
+            for (contract_identifier, _) in &contracts {
+                let contract_size = env.global_context.database.get_contract_size(contract_identifier).unwrap();
 
-        group.throughput(Throughput::Bytes(contract_size));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(contract_size),
-            &contract_size,
-            |b, &_| {
-                b.iter(|| {
-                    env.load_contract_for_bench(&contract_identifier).unwrap();
-                })
-            },
-        );
-    }
+                group.throughput(Throughput::Bytes(contract_size));
+                group.bench_with_input(
+                    BenchmarkId::from_parameter(contract_size),
+                    &contract_size,
+                    |b, &_| {
+                        b.iter(|| {
+                            env.load_contract_for_bench(contract_identifier).unwrap();
+                        })
+                    },
+                );
+            }
+        });
 }
 
-fn bench_type_parse_step(c: &mut Criterion) {
+fn bench_type_parse_step(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     fn eval_track_return_type<T: CostTracker>(
         contract_ast: &mut ContractAST,
         _lc: &mut TypingContext,
         _tc: &mut TypeChecker,
         _i: u64,
+        _scale: u16,
         cost_tracker: &mut T,
     ) {
         for exp in &contract_ast.expressions {
@@ -2793,100 +3276,99 @@ fn bench_type_parse_step(c: &mut Criterion) {
     bench_analysis(
         c,
         ClarityCostFunction::TypeParseStep,
-        SCALE,
-        vec![1],
+        spec.scale,
+        spec.input_sizes(),
         dummy_setup_code,
         eval_track_return_type,
     )
 }
 
-fn bench_stx_transfer(c: &mut Criterion) {
+fn bench_stx_transfer(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::StxTransfer,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_stx_get_balance(c: &mut Criterion) {
+fn bench_stx_get_balance(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::StxBalance,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_poison_microblock(c: &mut Criterion) {
+fn bench_poison_microblock(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     let mut group = c.benchmark_group(ClarityCostFunction::PoisonMicroblock.to_string());
 
     let headers_db = SimHeadersDB::new();
-    let mut memory_backing_store = MemoryBackingStore::new();
-    let clarity_db =
-        ClarityDatabase::new(&mut memory_backing_store, &headers_db, &NULL_BURN_STATE_DB);
+    storage::with_store(*STORAGE, |store| {
+        let clarity_db = ClarityDatabase::new(store, &headers_db, &NULL_BURN_STATE_DB);
 
-    let mut owned_env = OwnedEnvironment::new_free(true, clarity_db);
-    owned_env.begin();
-    let mut env = owned_env.get_exec_environment(None);
+        let mut owned_env = OwnedEnvironment::new_free(true, clarity_db);
+        owned_env.begin();
+        let mut env = owned_env.get_exec_environment(None);
 
-    let privk_string = "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01";
+        let privk_string = "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01";
 
-    let ref h1 = StacksMicroblockHeader {
-        version: 18,
-        sequence: 8,
-        prev_block: BlockHeaderHash::from_hex("06722a7d6537c3dd382a2cf1e56962ed36c26930e3b85faa4489caeb5097f724").unwrap(),
-        tx_merkle_root: Sha512Trunc256Sum::from_hex("ce7e657cb5af17c320b41a234efdd6f0d4e45272bfd3087efaf0a12eacb75eae").unwrap(),
-        signature: MessageSignature::from_hex("010eae2221e50cac44ef24fc35d691b02158c3697a4200e8573b13b14bf984526947318d6653216f6b73d490f44f2979ffb334e14706e01b350f18c946be1b0e2e").unwrap(),
-    };
+        let ref h1 = StacksMicroblockHeader {
+            version: 18,
+            sequence: 8,
+            prev_block: BlockHeaderHash::from_hex("06722a7d6537c3dd382a2cf1e56962ed36c26930e3b85faa4489caeb5097f724").unwrap(),
+            tx_merkle_root: Sha512Trunc256Sum::from_hex("ce7e657cb5af17c320b41a234efdd6f0d4e45272bfd3087efaf0a12eacb75eae").unwrap(),
+            signature: MessageSignature::from_hex("010eae2221e50cac44ef24fc35d691b02158c3697a4200e8573b13b14bf984526947318d6653216f6b73d490f44f2979ffb334e14706e01b350f18c946be1b0e2e").unwrap(),
+        };
 
-    let pubkh = h1.check_recover_pubkey().unwrap();
+        let pubkh = h1.check_recover_pubkey().unwrap();
 
-    env.global_context
-        .database
-        .insert_microblock_pubkey_hash_height(&pubkh, 60)
-        .unwrap();
+        env.global_context
+            .database
+            .insert_microblock_pubkey_hash_height(&pubkh, 60)
+            .unwrap();
 
-    let sk = StacksPrivateKey::from_hex(privk_string).unwrap();
-    let addr = StacksAddress::from_public_keys(
-        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-        &AddressHashMode::SerializeP2PKH,
-        1,
-        &vec![StacksPublicKey::from_private(&sk)],
-    )
-    .unwrap();
+        let sk = StacksPrivateKey::from_hex(privk_string).unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&sk)],
+        )
+        .unwrap();
 
-    env.sender = Some(addr.into());
+        env.sender = Some(addr.into());
 
-    group.throughput(Throughput::Bytes(1u64));
-    group.bench_with_input(BenchmarkId::from_parameter(1), &1, |b, &_| {
-        b.iter(|| {
-            for _ in 0..SCALE {
-                env.handle_poison_microblock(h1, h1).unwrap();
-            }
-        })
+        group.throughput(Throughput::Bytes(1u64));
+        group.bench_with_input(BenchmarkId::from_parameter(1), &1, |b, &_| {
+            b.iter(|| {
+                for _ in 0..spec.scale {
+                    env.handle_poison_microblock(h1, h1).unwrap();
+                }
+            })
+        });
     });
 }
 
-fn bench_contract_call(c: &mut Criterion) {
+fn bench_contract_call(c: &mut Criterion<Metered>, spec: &BenchSpec) {
     bench_with_input_sizes(
         c,
         ClarityCostFunction::ContractCall,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
         None,
     )
 }
 
-fn bench_contract_of(c: &mut Criterion) {
-    let make_store = || {
-        let mut memory_backing_store = MemoryBackingStore::new();
-        let clarity_db = memory_backing_store.as_clarity_db();
+fn bench_contract_of(c: &mut Criterion<Metered>, spec: &BenchSpec) {
+    let prepare_store = |store: &mut dyn ClarityBackingStore| {
+        let clarity_db = ClarityDatabase::new(store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB);
 
         let mut env = OwnedEnvironment::new_free(false, clarity_db);
 
@@ -2908,140 +3390,246 @@ fn bench_contract_of(c: &mut Criterion) {
                 (ok (contract-of contract)))";
         env.initialize_contract(use_identifier, use_contract)
             .unwrap();
-
-        memory_backing_store
     };
 
     bench_with_input_sizes(
         c,
         ClarityCostFunction::ContractOf,
-        SCALE.into(),
-        None,
+        spec.scale,
+        Some(spec.input_sizes()),
         false,
-        Some(Box::new(make_store)),
-    )
-}
-
-criterion_group!(
-    benches,
-    // bench_add,
-    // bench_sub,
-    // bench_mul,
-    // bench_div,
-    // bench_le,
-    // bench_leq,
-    // bench_ge,
-    // bench_geq,
-    // bench_and,
-    // bench_or,
-    // bench_xor,
-    // bench_not,
-    // bench_eq,
-    // bench_mod,
-    // bench_pow,
-    // bench_sqrti,
-    // bench_log2,
-    // bench_tuple_get,
-    // bench_tuple_merge,
-    // bench_tuple_cons,
-    // bench_hash160,
-    // bench_sha256,
-    // bench_sha512,
-    // bench_sha512t256,
-    // bench_keccak256,
-    // bench_secp256k1recover,
-    // bench_secp256k1verify,
-    // bench_create_ft,    // g
-    // bench_mint_ft,      // g
-    // bench_ft_transfer,  // g
-    // bench_ft_balance,   // g
-    // bench_ft_supply,    // g
-    // bench_ft_burn,      // g
-    // bench_create_nft,   // g
-    // bench_nft_mint,     // g
-    // bench_nft_transfer, // g
-    // bench_nft_owner,    // g
-    // bench_nft_burn,     // g
-    // bench_is_none,
-    // bench_is_some,
-    // bench_is_ok,
-    // bench_is_err,
-    // bench_unwrap,
-    // bench_unwrap_ret,
-    // bench_unwrap_err,
-    // bench_unwrap_err_or_ret,
-    // bench_create_map, // g
-    // bench_create_var, // g
-    // bench_set_var,    // g
-    // bench_fetch_var,  // g
-    // bench_print,
-    // bench_if,
-    // bench_asserts,
-    // bench_ok_cons,
-    // bench_some_cons,
-    // bench_err_cons,
-    // bench_concat,
-    // bench_as_max_len,
-    // bench_begin,
-    // bench_bind_name,
-    // bench_default_to,
-    // bench_try,
-    // bench_int_cast,
-    // bench_set_entry,   // g
-    // bench_fetch_entry, // g
-    // bench_match,
-    // bench_let,
-    // bench_index_of,
-    // bench_element_at,
-    // bench_len,
-    // bench_list_cons,
-    // bench_append,
-    // bench_filter,
-    // bench_fold,
-    // bench_at_block,
-    // bench_load_contract,
-    // bench_map,
-    // bench_block_info,
-    // bench_lookup_variable_depth,
-    // bench_lookup_variable_size,
-    // bench_lookup_function,
-    // bench_type_parse_step,
-    // bench_analysis_option_cons,
-    // bench_analysis_option_check,
-    // bench_analysis_visit,
-    // bench_analysis_bind_name,
-    // bench_analysis_list_items_check,
-    // bench_analysis_check_tuple_get,
-    // bench_analysis_check_tuple_merge,
-    // bench_analysis_check_tuple_cons,
-    // bench_analysis_tuple_items_check,
-    // bench_analysis_check_let,
-    // bench_analysis_lookup_function,
-    // bench_analysis_lookup_function_types,
-    // bench_analysis_type_annotate,
-    // bench_analysis_iterable_func,
-    // bench_analysis_storage,
-    // bench_analysis_type_check,
-    // bench_analysis_lookup_variable_depth,
-    // bench_analysis_type_lookup,
-    // bench_analysis_lookup_variable_const,
-    // bench_analysis_use_trait_entry,
-    // bench_analysis_get_function_entry,
-    // bench_inner_type_check_cost,
-    // bench_user_function_application,
-    // bench_ast_cycle_detection,
-    // bench_ast_parse,
-    // bench_contract_storage,
-    bench_principal_of,
-    // bench_stx_transfer,
-    // bench_stx_get_balance,
-    // bench_analysis_pass_read_only,               // g
-    // bench_analysis_pass_arithmetic_only_checker, // g
-    // bench_analysis_pass_trait_checker,           // g
-    // bench_analysis_pass_type_checker,            // g
-    // bench_poison_microblock,
-    // bench_contract_call,
-    // bench_contract_of,
-);
+        Some(Box::new(prepare_store)),
+    )
+}
+
+/// The benchmark routine for each registered cost function. Entries are looked up from
+/// `registry::REGISTRY`, which also holds the input sizes and scale each routine runs with.
+fn routine(function: CostFunction) -> Option<fn(&mut Criterion<Metered>, &BenchSpec)> {
+    let routine: fn(&mut Criterion<Metered>, &BenchSpec) = match function {
+        CostFunction::Clarity(function) => match function {
+            ClarityCostFunction::Add => bench_add,
+            ClarityCostFunction::Sub => bench_sub,
+            ClarityCostFunction::Mul => bench_mul,
+            ClarityCostFunction::Div => bench_div,
+            ClarityCostFunction::Le => bench_le,
+            ClarityCostFunction::Leq => bench_leq,
+            ClarityCostFunction::Ge => bench_ge,
+            ClarityCostFunction::Geq => bench_geq,
+            ClarityCostFunction::And => bench_and,
+            ClarityCostFunction::Or => bench_or,
+            ClarityCostFunction::Xor => bench_xor,
+            ClarityCostFunction::Not => bench_not,
+            ClarityCostFunction::Eq => bench_eq,
+            ClarityCostFunction::Mod => bench_mod,
+            ClarityCostFunction::Pow => bench_pow,
+            ClarityCostFunction::Sqrti => bench_sqrti,
+            ClarityCostFunction::Log2 => bench_log2,
+            ClarityCostFunction::IntCast => bench_int_cast,
+            ClarityCostFunction::TupleGet => bench_tuple_get,
+            ClarityCostFunction::TupleMerge => bench_tuple_merge,
+            ClarityCostFunction::TupleCons => bench_tuple_cons,
+            ClarityCostFunction::Hash160 => bench_hash160,
+            ClarityCostFunction::Sha256 => bench_sha256,
+            ClarityCostFunction::Sha512 => bench_sha512,
+            ClarityCostFunction::Sha512t256 => bench_sha512t256,
+            ClarityCostFunction::Keccak256 => bench_keccak256,
+            ClarityCostFunction::Secp256k1recover => bench_secp256k1recover,
+            ClarityCostFunction::Secp256k1verify => bench_secp256k1verify,
+            ClarityCostFunction::CreateFt => bench_create_ft,
+            ClarityCostFunction::FtMint => bench_mint_ft,
+            ClarityCostFunction::FtTransfer => bench_ft_transfer,
+            ClarityCostFunction::FtBalance => bench_ft_balance,
+            ClarityCostFunction::FtSupply => bench_ft_supply,
+            ClarityCostFunction::FtBurn => bench_ft_burn,
+            ClarityCostFunction::CreateNft => bench_create_nft,
+            ClarityCostFunction::NftMint => bench_nft_mint,
+            ClarityCostFunction::NftTransfer => bench_nft_transfer,
+            ClarityCostFunction::NftOwner => bench_nft_owner,
+            ClarityCostFunction::NftBurn => bench_nft_burn,
+            ClarityCostFunction::IsNone => bench_is_none,
+            ClarityCostFunction::IsSome => bench_is_some,
+            ClarityCostFunction::IsOkay => bench_is_ok,
+            ClarityCostFunction::IsErr => bench_is_err,
+            ClarityCostFunction::Unwrap => bench_unwrap,
+            ClarityCostFunction::UnwrapRet => bench_unwrap_ret,
+            ClarityCostFunction::UnwrapErr => bench_unwrap_err,
+            ClarityCostFunction::UnwrapErrOrRet => bench_unwrap_err_or_ret,
+            ClarityCostFunction::CreateMap => bench_create_map,
+            ClarityCostFunction::CreateVar => bench_create_var,
+            ClarityCostFunction::SetVar => bench_set_var,
+            ClarityCostFunction::FetchVar => bench_fetch_var,
+            ClarityCostFunction::SetEntry => bench_set_entry,
+            ClarityCostFunction::FetchEntry => bench_fetch_entry,
+            ClarityCostFunction::Print => bench_print,
+            ClarityCostFunction::If => bench_if,
+            ClarityCostFunction::Asserts => bench_asserts,
+            ClarityCostFunction::OkCons => bench_ok_cons,
+            ClarityCostFunction::SomeCons => bench_some_cons,
+            ClarityCostFunction::ErrCons => bench_err_cons,
+            ClarityCostFunction::Concat => bench_concat,
+            ClarityCostFunction::AsMaxLen => bench_as_max_len,
+            ClarityCostFunction::Begin => bench_begin,
+            ClarityCostFunction::BindName => bench_bind_name,
+            ClarityCostFunction::DefaultTo => bench_default_to,
+            ClarityCostFunction::TryRet => bench_try,
+            ClarityCostFunction::Match => bench_match,
+            ClarityCostFunction::Let => bench_let,
+            ClarityCostFunction::IndexOf => bench_index_of,
+            ClarityCostFunction::ElementAt => bench_element_at,
+            ClarityCostFunction::Len => bench_len,
+            ClarityCostFunction::ListCons => bench_list_cons,
+            ClarityCostFunction::Append => bench_append,
+            ClarityCostFunction::Filter => bench_filter,
+            ClarityCostFunction::Map => bench_map,
+            ClarityCostFunction::Fold => bench_fold,
+            ClarityCostFunction::BlockInfo => bench_block_info,
+            ClarityCostFunction::AtBlock => bench_at_block,
+            ClarityCostFunction::LoadContract => bench_load_contract,
+            ClarityCostFunction::LookupVariableDepth => bench_lookup_variable_depth,
+            ClarityCostFunction::LookupVariableSize => bench_lookup_variable_size,
+            ClarityCostFunction::LookupFunction => bench_lookup_function,
+            ClarityCostFunction::TypeParseStep => bench_type_parse_step,
+            ClarityCostFunction::AnalysisOptionCons => bench_analysis_option_cons,
+            ClarityCostFunction::AnalysisOptionCheck => bench_analysis_option_check,
+            ClarityCostFunction::AnalysisVisit => bench_analysis_visit,
+            ClarityCostFunction::AnalysisBindName => bench_analysis_bind_name,
+            ClarityCostFunction::AnalysisListItemsCheck => bench_analysis_list_items_check,
+            ClarityCostFunction::AnalysisCheckTupleGet => bench_analysis_check_tuple_get,
+            ClarityCostFunction::AnalysisCheckTupleMerge => bench_analysis_check_tuple_merge,
+            ClarityCostFunction::AnalysisCheckTupleCons => bench_analysis_check_tuple_cons,
+            ClarityCostFunction::AnalysisTupleItemsCheck => bench_analysis_tuple_items_check,
+            ClarityCostFunction::AnalysisCheckLet => bench_analysis_check_let,
+            ClarityCostFunction::AnalysisLookupFunction => bench_analysis_lookup_function,
+            ClarityCostFunction::AnalysisLookupFunctionTypes => bench_analysis_lookup_function_types,
+            ClarityCostFunction::AnalysisTypeAnnotate => bench_analysis_type_annotate,
+            ClarityCostFunction::AnalysisTypeCheck => bench_analysis_type_check,
+            ClarityCostFunction::AnalysisIterableFunc => bench_analysis_iterable_func,
+            ClarityCostFunction::AnalysisStorage => bench_analysis_storage,
+            ClarityCostFunction::AnalysisLookupVariableDepth => bench_analysis_lookup_variable_depth,
+            ClarityCostFunction::AnalysisTypeLookup => bench_analysis_type_lookup,
+            ClarityCostFunction::AnalysisLookupVariableConst => bench_analysis_lookup_variable_const,
+            ClarityCostFunction::AnalysisUseTraitEntry => bench_analysis_use_trait_entry,
+            ClarityCostFunction::AnalysisGetFunctionEntry => bench_analysis_get_function_entry,
+            ClarityCostFunction::InnerTypeCheckCost => bench_inner_type_check_cost,
+            ClarityCostFunction::UserFunctionApplication => bench_user_function_application,
+            ClarityCostFunction::AstCycleDetection => bench_ast_cycle_detection,
+            ClarityCostFunction::AstParse => bench_ast_parse,
+            ClarityCostFunction::ContractStorage => bench_contract_storage,
+            ClarityCostFunction::PrincipalOf => bench_principal_of,
+            ClarityCostFunction::StxTransfer => bench_stx_transfer,
+            ClarityCostFunction::StxBalance => bench_stx_get_balance,
+            ClarityCostFunction::PoisonMicroblock => bench_poison_microblock,
+            ClarityCostFunction::ContractCall => bench_contract_call,
+            ClarityCostFunction::ContractOf => bench_contract_of,
+            ClarityCostFunction::AnalysisFetchContractEntry => return None,
+        },
+        CostFunction::Analysis(function) => match function {
+            AnalysisCostFunction::ReadOnly => bench_analysis_pass_read_only,
+            AnalysisCostFunction::ArithmeticOnlyChecker => bench_analysis_pass_arithmetic_only_checker,
+            AnalysisCostFunction::TraitChecker => bench_analysis_pass_trait_checker,
+            AnalysisCostFunction::TypeChecker => bench_analysis_pass_type_checker,
+        },
+    };
+    Some(routine)
+}
+
+/// Benchmarks that time types or values drawn in Rust from the run seed, such as
+/// the arguments `UserFunctionApplication` applies, which a corpus can't hold.
+/// Replaying reuses the corpus's seed, so they only match the exported run while
+/// the code drawing them is unchanged.
+const SEEDED_VALUE_BENCHMARKS: &[ClarityCostFunction] = &[
+    ClarityCostFunction::UserFunctionApplication,
+    ClarityCostFunction::LookupFunction,
+    ClarityCostFunction::LookupVariableSize,
+    ClarityCostFunction::AnalysisBindName,
+    ClarityCostFunction::AnalysisListItemsCheck,
+    ClarityCostFunction::AnalysisCheckTupleGet,
+    ClarityCostFunction::AnalysisCheckTupleMerge,
+    ClarityCostFunction::AnalysisTupleItemsCheck,
+    ClarityCostFunction::AnalysisLookupFunction,
+    ClarityCostFunction::AnalysisTypeAnnotate,
+    ClarityCostFunction::AnalysisTypeCheck,
+    ClarityCostFunction::AnalysisTypeLookup,
+    ClarityCostFunction::AnalysisLookupVariableConst,
+    ClarityCostFunction::CreateNft,
+    ClarityCostFunction::CreateMap,
+    ClarityCostFunction::CreateVar,
+];
+
+/// Warns about the selected benchmarks a replayed corpus doesn't pin, see
+/// `SEEDED_VALUE_BENCHMARKS`.
+fn warn_unpinned_by_corpus(specs: &[BenchSpec]) {
+    let unpinned: Vec<String> = specs
+        .iter()
+        .filter(|spec| match spec.function {
+            CostFunction::Clarity(function) => SEEDED_VALUE_BENCHMARKS.contains(&function),
+            CostFunction::Analysis(_) => false,
+        })
+        .map(BenchSpec::name)
+        .collect();
+    if !unpinned.is_empty() {
+        eprintln!(
+            "WARNING: the corpus doesn't hold the values {} draw from the seed, so they only match the exported run if the code drawing them hasn't changed",
+            unpinned.join(", ")
+        );
+    }
+}
+
+/// Runs every registry entry selected by `COST_BENCH_SELECT` (all of them if unset).
+fn benches() {
+    // benchmarks open the chainstate as they go, so check it once up front
+    if let Err(e) = SimHeadersDB::open(DEFAULT_CHAINSTATE_PATH).and_then(|db| db.validate(MIN_BLOCK_COUNT)) {
+        eprintln!("Invalid chainstate: {}", e);
+        std::process::exit(1);
+    }
+    lazy_static::initialize(&STORAGE);
+    lazy_static::initialize(&BURN_STATE_PATH);
+    let probe_mode = ProbeMode::from_env();
+    if *METRIC != Metric::WallTime {
+        allocations::enable_counting();
+    }
+    let mut criterion = Criterion::default().with_measurement(Metered::new(*METRIC));
+    if probe_mode != ProbeMode::Off {
+        // routines that aren't probed still run, so keep their timing short, and
+        // keep their results away from real ones however the bench target was run
+        criterion = criterion
+            .sample_size(10)
+            .warm_up_time(Duration::from_millis(1))
+            .measurement_time(Duration::from_millis(1))
+            .output_directory(&criterion_output_dir());
+    }
+    let mut criterion = criterion.configure_from_args();
+    let specs = registry::select_from_env();
+    if CORPUS_REPLAY_DIR.is_some() {
+        warn_unpinned_by_corpus(&specs);
+    }
+    for spec in &specs {
+        let run = routine(spec.function)
+            .unwrap_or_else(|| panic!("No benchmark routine for registered function {}", spec.name()));
+        run(&mut criterion, spec);
+    }
+
+    match probe_mode {
+        ProbeMode::Off => record_run(&specs),
+        ProbeMode::CheckSizes => {
+            let failures = SIZE_CHECK_FAILURES.lock().unwrap();
+            if !failures.is_empty() {
+                eprintln!("{} input sizes disagree with the VM:", failures.len());
+                for failure in failures.iter() {
+                    eprintln!("  {}", failure);
+                }
+                std::process::exit(1);
+            }
+        }
+        ProbeMode::RecordCharges(path) => {
+            let records = CHARGE_RECORDS.lock().unwrap();
+            let mut file = File::create(&path)
+                .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e));
+            cost_probe::write_charge_report(&mut file, &records)
+                .expect("Failed to write charge report");
+            println!("Wrote charges of {} benchmarks to {}", records.len(), path.display());
+        }
+    }
+}
 
 criterion_main!(benches);
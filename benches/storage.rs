@@ -0,0 +1,92 @@
+//! Times storage reads and writes on a MARF store, over the sweeps in
+//! `storage_dimensions`. Results go in their own criterion directory, since they
+//! aren't cost functions and `fit` would otherwise give them cost constants.
+
+use benchmarking_lib::storage::{self, MarfChain};
+use benchmarking_lib::storage_dimensions::{
+    block_entries, group_name, sweep_entries, Operation, Sweep, FIXED_COUNT, FIXED_LENGTH,
+};
+use blockstack_lib::vm::Value;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Reads each entry's value from a committed block and deserializes it, as the
+/// VM does for `read_length`.
+fn bench_read(c: &mut Criterion, sweep: Sweep) {
+    let mut group = c.benchmark_group(group_name(Operation::Read, sweep));
+    for size in sweep.sizes() {
+        let entries = sweep_entries(sweep, *size);
+        let keys: Vec<String> = entries.iter().map(|(key, _)| key.clone()).collect();
+        let bytes: u64 = entries
+            .iter()
+            .map(|(_, value)| value.len() as u64 / 2)
+            .sum();
+
+        storage::with_committed_marf(entries, |store| {
+            group.throughput(Throughput::Bytes(bytes));
+            group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, _| {
+                b.iter(|| {
+                    for key in &keys {
+                        let hex = store.get(key).expect("entry was committed");
+                        Value::try_deserialize_hex_untyped(&hex).unwrap();
+                    }
+                })
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Blocks committed before the write sweep starts. Every iteration writes on top of
+/// the same tip, so the chain it reads through doesn't grow with the iteration count.
+const WRITE_CHAIN_HEIGHT: u64 = 16;
+
+/// Writes new entries in a new block on top of a chain of `WRITE_CHAIN_HEIGHT`
+/// blocks, and commits it, so every iteration pays for the trie and side store
+/// writes a node makes. Generating the entries isn't timed. The commit's fixed cost
+/// is in the intercept, which isn't fitted.
+fn bench_write(c: &mut Criterion, sweep: Sweep) {
+    let mut group = c.benchmark_group(group_name(Operation::Write, sweep));
+    for size in sweep.sizes() {
+        let bytes: u64 = sweep_entries(sweep, *size)
+            .iter()
+            .map(|(_, value)| value.len() as u64 / 2)
+            .sum();
+
+        let mut marf = MarfChain::open();
+        let mut rng = StdRng::seed_from_u64(*size);
+        let mut block = 0;
+        while marf.height() < WRITE_CHAIN_HEIGHT {
+            block += 1;
+            marf.commit_block(block_entries(sweep, *size, block, &mut rng));
+        }
+
+        group.throughput(Throughput::Bytes(bytes));
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, _| {
+            b.iter_batched(
+                || {
+                    block += 1;
+                    block_entries(sweep, *size, block, &mut rng)
+                },
+                |entries| marf.commit_sibling(entries),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    println!(
+        "Length sweeps use {} values per iteration, count sweeps {} byte values",
+        FIXED_COUNT, FIXED_LENGTH
+    );
+    for sweep in Sweep::ALL.iter() {
+        bench_read(c, *sweep);
+        bench_write(c, *sweep);
+    }
+}
+
+criterion_group!(storage_benches, benches);
+criterion_main!(storage_benches);
@@ -0,0 +1,82 @@
+//! Records how the crate was built, for `run_metadata::Environment`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Trimmed stdout of `program args`, if it ran and succeeded.
+fn output_of(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The `path` of the blockstack-core dependency in `manifest`, or None if it isn't
+/// a path dependency.
+fn blockstack_core_path(manifest: &str) -> Option<String> {
+    manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("blockstack-core"))
+        .find_map(|line| {
+            let path = &line[line.find("path")?..];
+            let start = path.find('"')? + 1;
+            let end = start + path[start..].find('"')?;
+            Some(path[start..end].to_string())
+        })
+}
+
+/// The commit blockstack-core is checked out at, suffixed with `-dirty` if it has
+/// uncommitted changes.
+fn blockstack_core_revision(dir: &str) -> Option<String> {
+    let revision = output_of("git", &["-C", dir, "rev-parse", "HEAD"])?;
+    let status = output_of("git", &["-C", dir, "status", "--porcelain"])?;
+    match status.is_empty() {
+        true => Some(revision),
+        false => Some(format!("{}-dirty", revision)),
+    }
+}
+
+/// Reruns the build script when blockstack-core's checkout or sources change, so the
+/// revision and its `-dirty` suffix stay current. Missing paths are skipped, since
+/// cargo would rerun every build for them.
+fn rerun_if_core_changed(core_dir: &Path) {
+    for path in &[".git/HEAD", ".git/index", "Cargo.toml", "src", "benches"] {
+        let path = core_dir.join(path);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let manifest =
+        fs::read_to_string(manifest_dir.join("Cargo.toml")).expect("failed to read Cargo.toml");
+    let core_dir = blockstack_core_path(&manifest).map(|path| manifest_dir.join(path));
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = output_of(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+    let profile = env::var("PROFILE").unwrap_or_else(|_| "unknown".to_string());
+    let revision = core_dir
+        .as_ref()
+        .and_then(|dir| blockstack_core_revision(dir.to_str()?))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=COST_BENCH_RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=COST_BENCH_PROFILE={}", profile);
+    println!(
+        "cargo:rustc-env=COST_BENCH_BLOCKSTACK_CORE_REV={}",
+        revision
+    );
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    if let Some(core_dir) = &core_dir {
+        rerun_if_core_changed(core_dir);
+    }
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
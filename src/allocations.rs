@@ -0,0 +1,146 @@
+//! Counts heap allocations, so benchmarks can measure how much cost functions
+//! allocate as well as how long they take.
+//!
+//! Functions like `concat`, `append`, `merge` and `list` spend most of their time
+//! allocating and cloning values. With `COST_BENCH_MEASUREMENT` set to
+//! `allocations` or `allocated_bytes`, the bench target measures those instead of
+//! wall time, through the same benchmark groups, so their criterion results can
+//! be fitted against input size like times are.
+//!
+//! Counting needs the binary to install `CountingAllocator` as its global
+//! allocator, and to call `enable_counting` before measuring.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Environment variable holding the name of the `Metric` the bench target measures.
+pub const MEASUREMENT_ENV_VAR: &str = "COST_BENCH_MEASUREMENT";
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting every allocation and the bytes it asks for once
+/// `enable_counting` has been called. A reallocation counts as an allocation of
+/// its new size, since that is what it costs when it moves.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn count(size: usize) {
+        if COUNTING.load(Ordering::Relaxed) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        CountingAllocator::count(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Starts counting allocations. Counting is off by default, so a binary that only
+/// measures wall time pays for one relaxed load per allocation.
+pub fn enable_counting() {
+    COUNTING.store(true, Ordering::SeqCst);
+}
+
+/// Allocations counted so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocationCounts {
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+impl AllocationCounts {
+    /// Totals since counting was enabled. Doesn't allocate.
+    pub fn now() -> AllocationCounts {
+        AllocationCounts {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Allocations made between `before` and these counts.
+    pub fn since(&self, before: &AllocationCounts) -> AllocationCounts {
+        AllocationCounts {
+            allocations: self.allocations - before.allocations,
+            bytes: self.bytes - before.bytes,
+        }
+    }
+}
+
+/// What the bench target measures per iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// nanoseconds, as criterion's `WallTime` measures them
+    WallTime,
+    Allocations,
+    AllocatedBytes,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [
+        Metric::WallTime,
+        Metric::Allocations,
+        Metric::AllocatedBytes,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::WallTime => "wall_time",
+            Metric::Allocations => "allocations",
+            Metric::AllocatedBytes => "allocated_bytes",
+        }
+    }
+
+    pub fn lookup_by_name(name: &str) -> Option<Metric> {
+        Metric::ALL.iter().find(|m| m.name() == name).copied()
+    }
+
+    /// The metric named by `COST_BENCH_MEASUREMENT`, or `WallTime` if it is unset.
+    pub fn from_env() -> Metric {
+        match env::var(MEASUREMENT_ENV_VAR) {
+            Ok(name) => Metric::lookup_by_name(&name).unwrap_or_else(|| {
+                panic!(
+                    "Invalid {}: '{}' is not one of wall_time, allocations, allocated_bytes",
+                    MEASUREMENT_ENV_VAR, name
+                )
+            }),
+            Err(_) => Metric::WallTime,
+        }
+    }
+
+    /// Unit of a counted metric's values, and of those values per unit of input.
+    pub fn units(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Metric::WallTime => None,
+            Metric::Allocations => Some(("allocs", "allocs/input")),
+            Metric::AllocatedBytes => Some(("B", "B/input")),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
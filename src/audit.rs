@@ -0,0 +1,356 @@
+//! Compares what a costs contract charges real contracts with how long they take.
+//!
+//! An audit deploys a contract and makes a list of calls to it under a
+//! `LimitedCostTracker` priced by a given `costs` contract, usually the
+//! `new_costs.clar` written by `emit`, and times each of them. Dividing the
+//! runtime charged by the nanoseconds taken, relative to the rate the proposal
+//! targets, shows which contracts the proposed costs under- or over-price.
+
+use crate::cost_probe;
+use crate::proposal::Scaling;
+use blockstack_lib::clarity_vm::database::MemoryBackingStore;
+use blockstack_lib::vm::contexts::OwnedEnvironment;
+use blockstack_lib::vm::database::{ClarityDatabase, NULL_BURN_STATE_DB, NULL_HEADER_DB};
+use blockstack_lib::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use blockstack_lib::vm::{SymbolicExpression, Value};
+use std::io;
+use std::io::Write;
+use std::time::Instant;
+
+/// Address the calls are made from, unless another is given.
+pub const DEFAULT_SENDER: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+/// Times the deployment and calls are repeated; the median of each is kept.
+pub const DEFAULT_REPETITIONS: usize = 10;
+
+/// A call to one of the audited contract's public or read-only functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    /// the call as written, e.g. `(transfer u10 'ST...)`
+    pub source: String,
+    pub function: String,
+    /// Clarity expressions for each argument, evaluated in the contract before the call
+    pub args: Vec<String>,
+}
+
+/// The top-level elements of `(...)`, or None if `source` isn't one list.
+fn split_list(source: &str) -> Option<Vec<String>> {
+    let inner = source.strip_prefix('(')?.strip_suffix(')')?;
+    let bytes = inner.as_bytes();
+    let mut elements = vec![];
+    let mut depth = 0i32;
+    let mut start = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                start.get_or_insert(i);
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'(' | b'{' => {
+                start.get_or_insert(i);
+                depth += 1;
+            }
+            b')' | b'}' => depth -= 1,
+            b if depth == 0 && (b.is_ascii_whitespace() || b == b',') => {
+                if let Some(start) = start.take() {
+                    elements.push(inner[start..i].to_string());
+                }
+            }
+            _ => {
+                start.get_or_insert(i);
+            }
+        }
+        if depth < 0 {
+            return None;
+        }
+        i += 1;
+    }
+    if depth != 0 || i > bytes.len() {
+        return None;
+    }
+    if let Some(start) = start {
+        elements.push(inner[start..].to_string());
+    }
+    Some(elements)
+}
+
+/// Reads calls written one per line as `(function arg ...)`. Blank lines and lines
+/// starting with `;` are skipped.
+pub fn parse_calls(source: &str) -> Result<Vec<Call>, String> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with(';'))
+        .map(|(line_number, line)| {
+            let mut elements = split_list(line)
+                .filter(|elements| !elements.is_empty())
+                .ok_or_else(|| {
+                    format!(
+                        "line {}: expected (function arg ...), found {}",
+                        line_number, line
+                    )
+                })?;
+            let function = elements.remove(0);
+            Ok(Call {
+                source: line.to_string(),
+                function,
+                args: elements,
+            })
+        })
+        .collect()
+}
+
+/// Runtime charged for, and time taken by, a deployment or call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// `(deploy)` or the call as written
+    pub name: String,
+    pub runtime: u64,
+    /// median nanoseconds
+    pub nanoseconds: f64,
+    /// why the call failed, if it did; failed calls are still charged and timed
+    pub error: Option<String>,
+}
+
+impl Measurement {
+    /// Zero for a call that failed before it was made.
+    pub fn runtime_per_nanosecond(&self) -> f64 {
+        match self.nanoseconds > 0.0 {
+            true => self.runtime as f64 / self.nanoseconds,
+            false => 0.0,
+        }
+    }
+}
+
+/// Measurements of one contract's deployment and calls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audit {
+    pub contract: QualifiedContractIdentifier,
+    pub deploy: Measurement,
+    pub calls: Vec<Measurement>,
+}
+
+impl Audit {
+    /// The deployment and calls together.
+    pub fn total(&self) -> Measurement {
+        let all = || std::iter::once(&self.deploy).chain(self.calls.iter());
+        Measurement {
+            name: "total".to_string(),
+            runtime: all().map(|m| m.runtime).sum(),
+            nanoseconds: all().map(|m| m.nanoseconds).sum(),
+            error: None,
+        }
+    }
+}
+
+/// Runtime charged and nanoseconds taken by each step of one run, plus any errors.
+type Run = Vec<(u64, f64, Option<String>)>;
+
+/// Deploys the contract and makes every call in a fresh store priced by `costs`.
+/// Only a failed deployment is an error: calls that fail are recorded as such.
+fn run_once(
+    contract: &QualifiedContractIdentifier,
+    source: &str,
+    calls: &[Call],
+    costs: &str,
+    sender: &PrincipalData,
+) -> Result<Run, String> {
+    let mut store = MemoryBackingStore::new();
+    cost_probe::install_costs_contracts(&mut store, costs)?;
+    let mut clarity_db = ClarityDatabase::new(&mut store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB);
+    let cost_tracker = cost_probe::unlimited_tracker(&mut clarity_db)?;
+    let mut owned_env = OwnedEnvironment::new_cost_limited(false, clarity_db, cost_tracker);
+
+    let mut run = Run::new();
+    let before = owned_env.get_cost_total().runtime;
+    let start = Instant::now();
+    owned_env
+        .initialize_contract(contract.clone(), source)
+        .map_err(|e| format!("failed to deploy {}: {}", contract, e))?;
+    let elapsed = start.elapsed().as_nanos() as f64;
+    run.push((owned_env.get_cost_total().runtime - before, elapsed, None));
+
+    for call in calls {
+        let args: Result<Vec<SymbolicExpression>, String> = call
+            .args
+            .iter()
+            .map(|arg| {
+                owned_env
+                    .eval_read_only(contract, arg)
+                    .map(|(value, _, _)| SymbolicExpression::atom_value(value))
+                    .map_err(|e| format!("argument {}: {}", arg, e))
+            })
+            .collect();
+        let args = match args {
+            Ok(args) => args,
+            Err(e) => {
+                run.push((0, 0.0, Some(e)));
+                continue;
+            }
+        };
+
+        let before = owned_env.get_cost_total().runtime;
+        let start = Instant::now();
+        let result = owned_env.execute_transaction(
+            Value::Principal(sender.clone()),
+            contract.clone(),
+            &call.function,
+            &args,
+        );
+        let elapsed = start.elapsed().as_nanos() as f64;
+        let runtime = owned_env.get_cost_total().runtime - before;
+        run.push((runtime, elapsed, result.err().map(|e| e.to_string())));
+    }
+    Ok(run)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values[values.len() / 2]
+}
+
+/// Deploys `source` as `contract` and makes `calls` from `sender`, `repetitions`
+/// times over, each time in a fresh store with `costs` as the costs contract.
+pub fn audit(
+    contract: &QualifiedContractIdentifier,
+    source: &str,
+    calls: &[Call],
+    costs: &str,
+    sender: &PrincipalData,
+    repetitions: usize,
+) -> Result<Audit, String> {
+    if repetitions == 0 {
+        return Err("an audit needs at least one repetition".to_string());
+    }
+    let runs = (0..repetitions)
+        .map(|_| run_once(contract, source, calls, costs, sender))
+        .collect::<Result<Vec<Run>, String>>()?;
+
+    let names =
+        std::iter::once("(deploy)".to_string()).chain(calls.iter().map(|c| c.source.clone()));
+    let mut measurements: Vec<Measurement> = names
+        .enumerate()
+        .map(|(step, name)| {
+            let (runtime, _, error) = runs[0][step].clone();
+            let mut times: Vec<f64> = runs.iter().map(|run| run[step].1).collect();
+            Measurement {
+                name,
+                runtime,
+                nanoseconds: median(&mut times),
+                error,
+            }
+        })
+        .collect();
+
+    let deploy = measurements.remove(0);
+    Ok(Audit {
+        contract: contract.clone(),
+        deploy,
+        calls: measurements,
+    })
+}
+
+/// Writes `audit` as a markdown table. Each row's ratio is its runtime per
+/// nanosecond over the runtime per nanosecond `scaling` targets: below 1 the step
+/// is charged less than its share of the block limit for the time it takes, so
+/// it is underpriced, and above 1 it is overpriced.
+pub fn write_audit<W: Write>(out: &mut W, audit: &Audit, scaling: &Scaling) -> io::Result<()> {
+    let target = scaling.runtime_per_ns();
+    writeln!(
+        out,
+        "{}: the target is {:.3} runtime units per nanosecond",
+        audit.contract, target
+    )?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "| Call | Runtime | ns | Runtime per ns | vs. target | Error |"
+    )?;
+    writeln!(
+        out,
+        "| ----------- | ----------- | ----------- | ----------- | ----------- | ----------- |"
+    )?;
+    let total = audit.total();
+    let rows = std::iter::once(&audit.deploy)
+        .chain(audit.calls.iter())
+        .chain(std::iter::once(&total));
+    for measurement in rows {
+        writeln!(
+            out,
+            "| `{}` | {} | {:.0} | {:.3} | {:.2} | {} |",
+            measurement.name.replace('|', "\\|"),
+            measurement.runtime,
+            measurement.nanoseconds,
+            measurement.runtime_per_nanosecond(),
+            measurement.runtime_per_nanosecond() / target,
+            measurement
+                .error
+                .as_deref()
+                .unwrap_or("")
+                .replace('|', "\\|")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_list_keeps_nested_lists_strings_and_tuples_whole() {
+        assert_eq!(
+            split_list(r#"(transfer (list u1 u2) "a \"b\" c" {a: 1, b: (+ 1 2)} 'ST1)"#),
+            Some(vec![
+                "transfer".to_string(),
+                "(list u1 u2)".to_string(),
+                r#""a \"b\" c""#.to_string(),
+                "{a: 1, b: (+ 1 2)}".to_string(),
+                "'ST1".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn split_list_rejects_anything_but_one_balanced_list() {
+        assert_eq!(split_list("()"), Some(vec![]));
+        assert_eq!(split_list("transfer u1"), None);
+        assert_eq!(split_list("(transfer (list u1)"), None);
+        assert_eq!(split_list("(transfer u1))"), None);
+        assert_eq!(split_list("(a) (b)"), None);
+        assert_eq!(split_list(r#"(a "unterminated)"#), None);
+    }
+
+    #[test]
+    fn parse_calls_skips_blank_and_comment_lines() {
+        let calls =
+            parse_calls("; setup\n\n  (get-balance 'ST1)  \n(transfer u10 'ST2)\n").unwrap();
+        assert_eq!(
+            calls,
+            vec![
+                Call {
+                    source: "(get-balance 'ST1)".to_string(),
+                    function: "get-balance".to_string(),
+                    args: vec!["'ST1".to_string()],
+                },
+                Call {
+                    source: "(transfer u10 'ST2)".to_string(),
+                    function: "transfer".to_string(),
+                    args: vec!["u10".to_string(), "'ST2".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_calls_reports_the_line_of_a_malformed_call() {
+        let error = parse_calls("(get-balance 'ST1)\n\n()\n").unwrap_err();
+        assert!(error.starts_with("line 3:"), "{}", error);
+        assert!(parse_calls("get-balance").is_err());
+    }
+}
@@ -0,0 +1,990 @@
+//! Drives the benchmarking pipeline from cargo.
+//!
+//! ```text
+//! cost-bench run [--select <selection>] [--seed <seed>] [--input-sizes <sizes>] [--scale <scale>] [--storage <backend>] [--headers-latency <micros>] [--measure <metric>] [--export-corpus <dir>] [--replay-corpus <dir>] [--output <criterion_dir>]
+//! cost-bench check-sizes [--select <selection>] [--seed <seed>] [--input-sizes <sizes>] [--scale <scale>] [--storage <backend>]
+//! cost-bench record-charges [--select <selection>] [--seed <seed>] [--input-sizes <sizes>] [--scale <scale>] [--storage <backend>] [--output <csv>]
+//! cost-bench fit <criterion_dir> [--overrides <csv>] [--resamples <n>] [--allow-mixed] [--output <analysis_dir>]
+//! cost-bench emit [--input <analysis_dir>] [--target-seconds <secs>] [--output <dir>]
+//! cost-bench compare <baseline_dir> <candidate_dir> [--threshold <percent>] [--allow-mixed] [--output <md>]
+//! cost-bench make-chainstate <path> [--blocks <n>] [--seed <seed>]
+//! cost-bench coverage [--criterion <criterion_dir>] [--analysis <analysis_dir>] [--output <md>]
+//! cost-bench search <cost_function> [--input-sizes <sizes>] [--seed <seed>] [--iterations <n>] [--analysis <analysis_dir>] [--output <dir>]
+//! cost-bench audit <contract> <calls> [--costs <clar>] [--sender <principal>] [--repetitions <n>] [--target-seconds <secs>] [--output <md>]
+//! cost-bench run-storage [--output <criterion_dir>]
+//! cost-bench fit-storage <criterion_dir> [--target-seconds <secs>] [--output <analysis_dir>]
+//! ```
+//!
+//! `run` runs `cargo bench` with the selection, seed, input sizes and scale passed
+//! through the `COST_BENCH_*` environment variables the bench target reads (see
+//! `registry`), the storage backend passed through `COST_BENCH_STORAGE` (see
+//! `storage`), and criterion's output directory set to `--output`. With
+//! `--export-corpus`, every piece of generated code it times is also written to
+//! that directory, and with `--replay-corpus` the code is read from a directory
+//! written that way instead of generated (see `corpus`). With `--measure
+//! allocations` or `--measure allocated_bytes`, every benchmark counts heap
+//! allocations or bytes allocated per iteration instead of timing, and results go
+//! to `target/criterion-<metric>` unless `--output` is given (see `allocations`);
+//! `fit` fits them against input size like times, but writes its files prefixed
+//! with the metric, e.g. `allocations_cost_constants.csv`, and `emit` refuses
+//! analysis directories whose runs didn't measure wall time. `check-sizes`
+//! runs the bench target with `COST_BENCH_CHECK_SIZES` set instead, and fails if
+//! any benchmark's input size disagrees with what the VM charges (see `cost_probe`).
+//! `record-charges` runs it with `COST_BENCH_RECORD_CHARGES` set, and writes every
+//! cost function each benchmark charges to `--output`.
+//!
+//! `fit` writes `cost_constants.csv` and `model_selection.csv`, and bootstrapped
+//! intervals on the constants to `cost_intervals.csv` when the results have their
+//! samples. `emit` reads them and writes a checked `new_costs.clar` and
+//! `updates_table.md`, and `compare` tabulates
+//! the change in median runtimes and fitted constants between two criterion or
+//! analysis directories, failing if any grew by more than `--threshold`.
+//! `make-chainstate` writes a synthetic chainstate database for `SimHeadersDB`.
+//! `coverage` tabulates which cost functions have a generator, a benchmark,
+//! results and a fit, and lists those missing any of them.
+//! `search` mutates the generated code of one cost function at each input size and
+//! scale 1, looking for variants that take the most time per cost unit they are charged,
+//! priced with the fitted model if `--analysis` has one (see `search`). It prints
+//! the worst it finds and writes their code to `--output`.
+//! `audit` deploys a contract and makes the calls listed in a file to it, charged
+//! by the `--costs` contract (`new_costs.clar` from `emit` by default), and
+//! tabulates the runtime charged per nanosecond of each call against the rate
+//! the proposal targets (see `audit`).
+//! `run-storage` runs the `storage` bench target, which times storage reads and
+//! writes on a MARF by value count and byte length, and `fit-storage` fits the
+//! time per operation and per byte to its results and writes
+//! `storage_constants.csv` (see `storage_dimensions`).
+//!
+//! Every timed run records its environment and settings in `run_metadata.json`
+//! (see `run_metadata`). `fit` refuses results from runs that differ in anything
+//! but the generator seed, and copies their metadata to the analysis directory.
+//! `compare` warns about every difference between the two sets of runs, and
+//! refuses those that differ in build profile or scale. `--allow-mixed` turns
+//! refusals into warnings.
+
+use benchmarking_lib::allocations::{Metric, MEASUREMENT_ENV_VAR};
+use benchmarking_lib::audit::{self, DEFAULT_REPETITIONS, DEFAULT_SENDER};
+use benchmarking_lib::bootstrap::{self, DEFAULT_BOOTSTRAP_SEED, DEFAULT_RESAMPLES};
+use benchmarking_lib::compare;
+use benchmarking_lib::corpus;
+use benchmarking_lib::cost_probe::{
+    ProbeMode, CHECK_SIZES_ENV_VAR, PROBE_CRITERION_DIR, RECORD_CHARGES_ENV_VAR,
+};
+use benchmarking_lib::costs_check;
+use benchmarking_lib::coverage;
+use benchmarking_lib::generators::try_gen;
+use benchmarking_lib::headers_db::{ChainstateBuilder, HEADERS_LATENCY_ENV_VAR, MIN_BLOCK_COUNT};
+use benchmarking_lib::proposal::{self, Scaling, DEFAULT_TARGET_WALL_TIME_SECS};
+use benchmarking_lib::registry::{
+    self, CostFunction, INPUT_SIZES_ENV_VAR, SCALE_ENV_VAR, SELECT_ENV_VAR,
+};
+use benchmarking_lib::regression::{self, Fit, Model, Report};
+use benchmarking_lib::run_metadata::{self, Difference, RunMetadata};
+use benchmarking_lib::search::{self, Pricing, SearchConfig};
+use benchmarking_lib::storage::{StorageBackend, STORAGE_ENV_VAR};
+use benchmarking_lib::storage_dimensions;
+use blockstack_lib::vm::costs::cost_functions::ClarityCostFunction;
+use blockstack_lib::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+const USAGE: &str = "Usage:
+  cost-bench run [--select <selection>] [--seed <seed>] [--input-sizes <sizes>] [--scale <scale>] [--storage <backend>] [--headers-latency <micros>] [--measure <metric>] [--export-corpus <dir>] [--replay-corpus <dir>] [--output <criterion_dir>]
+  cost-bench check-sizes [--select <selection>] [--seed <seed>] [--input-sizes <sizes>] [--scale <scale>] [--storage <backend>]
+  cost-bench record-charges [--select <selection>] [--seed <seed>] [--input-sizes <sizes>] [--scale <scale>] [--storage <backend>] [--output <csv>]
+  cost-bench fit <criterion_dir> [--overrides <csv>] [--resamples <n>] [--allow-mixed] [--output <analysis_dir>]
+  cost-bench emit [--input <analysis_dir>] [--target-seconds <secs>] [--output <dir>]
+  cost-bench compare <baseline_dir> <candidate_dir> [--threshold <percent>] [--allow-mixed] [--output <md>]
+  cost-bench make-chainstate <path> [--blocks <n>] [--seed <seed>]
+  cost-bench coverage [--criterion <criterion_dir>] [--analysis <analysis_dir>] [--output <md>]
+  cost-bench search <cost_function> [--input-sizes <sizes>] [--seed <seed>] [--iterations <n>] [--analysis <analysis_dir>] [--output <dir>]
+  cost-bench audit <contract> <calls> [--costs <clar>] [--sender <principal>] [--repetitions <n>] [--target-seconds <secs>] [--output <md>]
+  cost-bench run-storage [--output <criterion_dir>]
+  cost-bench fit-storage <criterion_dir> [--target-seconds <secs>] [--output <analysis_dir>]";
+
+const DEFAULT_CRITERION_DIR: &str = "target/criterion";
+const STORAGE_CRITERION_DIR: &str = "target/criterion-storage";
+const DEFAULT_CHARGE_REPORT: &str = "analysis_target/charge_report.csv";
+const DEFAULT_ANALYSIS_DIR: &str = "analysis_target";
+const DEFAULT_CHAINSTATE_BLOCKS: u32 = 70;
+const DEFAULT_SEARCH_DIR: &str = "analysis_target/search";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    fail(USAGE)
+}
+
+/// Options that take no value: they are set by being passed.
+const FLAGS: &[&str] = &["allow-mixed"];
+
+/// Positional arguments, `--option value` pairs and `--flag`s of a subcommand.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// Parses `args`, failing on options not in `known` and on a positional count
+    /// outside `positional_count`.
+    fn parse(args: &[String], known: &[&str], positional_count: (usize, usize)) -> Args {
+        let mut positional = vec![];
+        let mut options = HashMap::new();
+        let mut flags = vec![];
+
+        let mut rest = args.iter();
+        while let Some(arg) = rest.next() {
+            if let Some(option) = arg.strip_prefix("--") {
+                if !known.contains(&option) {
+                    fail(&format!("Unknown option --{}\n{}", option, USAGE));
+                }
+                if FLAGS.contains(&option) {
+                    flags.push(option.to_string());
+                    continue;
+                }
+                match rest.next() {
+                    Some(value) => options.insert(option.to_string(), value.clone()),
+                    None => fail(&format!("--{} needs a value", option)),
+                };
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+
+        let (min, max) = positional_count;
+        if positional.len() < min || positional.len() > max {
+            usage();
+        }
+        Args {
+            positional,
+            options,
+            flags,
+        }
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn path_or(&self, name: &str, default: &str) -> PathBuf {
+        PathBuf::from(self.option(name).unwrap_or(default))
+    }
+}
+
+/// Prints `differences`, and fails if any of them are `refused` unless mixing is
+/// allowed.
+fn check_differences<F>(what: &str, differences: &[Difference], refused: F, allow_mixed: bool)
+where
+    F: Fn(&Difference) -> bool,
+{
+    let mut refusals = 0;
+    for difference in differences {
+        let level = match refused(difference) && !allow_mixed {
+            true => {
+                refusals += 1;
+                "ERROR"
+            }
+            false => "WARNING",
+        };
+        eprintln!(
+            "{}: {} differ in {}: {} vs {}",
+            level, what, difference.field, difference.first, difference.second
+        );
+    }
+    if refusals > 0 {
+        fail(&format!(
+            "Refusing to mix runs with {} incompatible differences, pass --allow-mixed to override",
+            refusals
+        ));
+    }
+}
+
+fn create_file(path: &Path) -> File {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create output directory");
+    }
+    File::create(path).expect("Failed to create output file")
+}
+
+/// Runs the bench target. `mode` is only used to pick the subcommand's behavior,
+/// its report path is taken from `--output`. When probing nothing useful is timed,
+/// so criterion writes to a scratch directory rather than over real results.
+fn run(args: &[String], mode: ProbeMode) {
+    let mut known = vec!["select", "seed", "input-sizes", "scale", "storage"];
+    match mode {
+        ProbeMode::Off => known.extend(&[
+            "headers-latency",
+            "measure",
+            "export-corpus",
+            "replay-corpus",
+            "output",
+        ]),
+        ProbeMode::CheckSizes => {}
+        ProbeMode::RecordCharges(_) => known.push("output"),
+    }
+    let args = Args::parse(args, &known, (0, 0));
+    let current_dir = env::current_dir().expect("Failed to get current directory");
+
+    let mut command = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command.args(&["bench", "--bench", "bench"]);
+
+    // validate here, rather than after cargo has built the bench target
+    if let Some(selection) = args.option("select") {
+        if let Err(e) = registry::parse_selection(selection) {
+            fail(&format!("Invalid --select: {}", e));
+        }
+        command.env(SELECT_ENV_VAR, selection);
+    }
+    if let Some(seed) = args.option("seed") {
+        if seed.parse::<u64>().is_err() {
+            fail("--seed must be an unsigned 64-bit integer");
+        }
+        command.env("COST_BENCH_SEED", seed);
+    }
+    if let Some(sizes) = args.option("input-sizes") {
+        if let Err(e) = registry::parse_input_sizes(sizes) {
+            fail(&format!("Invalid --input-sizes: {}", e));
+        }
+        command.env(INPUT_SIZES_ENV_VAR, sizes);
+    }
+    if let Some(scale) = args.option("scale") {
+        if scale.parse::<u16>().is_err() {
+            fail("--scale must be an integer between 0 and 65535");
+        }
+        command.env(SCALE_ENV_VAR, scale);
+    }
+    if let Some(backend) = args.option("storage") {
+        if StorageBackend::lookup_by_name(backend).is_none() {
+            fail("--storage must be memory or marf");
+        }
+        command.env(STORAGE_ENV_VAR, backend);
+    }
+    if let Some(micros) = args.option("headers-latency") {
+        if micros.parse::<u64>().is_err() {
+            fail("--headers-latency must be a whole number of microseconds");
+        }
+        command.env(HEADERS_LATENCY_ENV_VAR, micros);
+    }
+    if let Some(dir) = args.option("export-corpus") {
+        command.env(corpus::EXPORT_ENV_VAR, current_dir.join(dir));
+    }
+    if let Some(dir) = args.option("replay-corpus") {
+        if !Path::new(dir).is_dir() {
+            fail(&format!("--replay-corpus: {} is not a directory", dir));
+        }
+        command.env(corpus::REPLAY_ENV_VAR, current_dir.join(dir));
+    }
+
+    let metric = match args.option("measure") {
+        Some(name) => Metric::lookup_by_name(name)
+            .unwrap_or_else(|| fail("--measure must be wall_time, allocations or allocated_bytes")),
+        None => Metric::WallTime,
+    };
+    command.env(MEASUREMENT_ENV_VAR, metric.name());
+
+    // cargo runs benches from the package root, so pass absolute paths. Counts
+    // default to their own directory, so they don't replace timings.
+    let default_output = match metric {
+        Metric::WallTime => DEFAULT_CRITERION_DIR.to_string(),
+        metric => format!("{}-{}", DEFAULT_CRITERION_DIR, metric),
+    };
+    let output = match mode {
+        ProbeMode::Off => current_dir.join(args.path_or("output", &default_output)),
+        _ => current_dir.join(PROBE_CRITERION_DIR),
+    };
+    command.env("CRITERION_HOME", &output);
+
+    let charge_report = current_dir.join(args.path_or("output", DEFAULT_CHARGE_REPORT));
+    match mode {
+        ProbeMode::Off => {}
+        ProbeMode::CheckSizes => {
+            command.env(CHECK_SIZES_ENV_VAR, "1");
+        }
+        ProbeMode::RecordCharges(_) => {
+            if let Some(parent) = charge_report.parent() {
+                fs::create_dir_all(parent).expect("Failed to create output directory");
+            }
+            command.env(RECORD_CHARGES_ENV_VAR, &charge_report);
+        }
+    }
+
+    let status = command
+        .status()
+        .unwrap_or_else(|e| fail(&format!("Failed to run cargo bench: {}", e)));
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+    match mode {
+        ProbeMode::Off => println!("Wrote benchmark results to {}", output.display()),
+        ProbeMode::CheckSizes => println!("Every checked input size agrees with the VM"),
+        ProbeMode::RecordCharges(_) => println!("Wrote {}", charge_report.display()),
+    }
+}
+
+/// The metric `runs` measured: wall time, unless one of them counted something else.
+fn measured_metric(runs: &[RunMetadata]) -> Metric {
+    runs.iter()
+        .map(|run| {
+            Metric::lookup_by_name(&run.measurement)
+                .unwrap_or_else(|| fail(&format!("Unknown measurement {}", run.measurement)))
+        })
+        .find(|metric| *metric != Metric::WallTime)
+        .unwrap_or(Metric::WallTime)
+}
+
+/// Name of a file `fit` writes for `metric`. Fits of counted metrics aren't runtime
+/// costs, so their files are prefixed with the metric, e.g.
+/// `allocations_cost_constants.csv`, and `emit` never reads them.
+fn fit_file_name(metric: Metric, name: &str) -> String {
+    match metric {
+        Metric::WallTime => name.to_string(),
+        metric => format!("{}_{}", metric, name),
+    }
+}
+
+fn fit(args: &[String]) {
+    let args = Args::parse(
+        args,
+        &["overrides", "resamples", "allow-mixed", "output"],
+        (1, 1),
+    );
+    let criterion_dir = Path::new(&args.positional[0]);
+    let output_dir = args.path_or("output", DEFAULT_ANALYSIS_DIR);
+
+    let runs = run_metadata::load_runs(criterion_dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load run metadata: {}", e)));
+    if runs.is_empty() {
+        eprintln!(
+            "WARNING: {} has no run metadata, so it can't be checked for mixed runs",
+            criterion_dir.display()
+        );
+    }
+    check_differences(
+        "results of runs",
+        &run_metadata::mixed_run_differences(&runs),
+        |difference| difference.field != "generator_seed",
+        args.flag("allow-mixed"),
+    );
+    let metric = measured_metric(&runs);
+    if metric != Metric::WallTime {
+        println!(
+            "Fitting {} rather than wall time: writing {} files, which emit won't read",
+            metric,
+            fit_file_name(metric, "*")
+        );
+    }
+    let resamples = match args.option("resamples") {
+        Some(n) => n
+            .parse()
+            .unwrap_or_else(|_| fail("--resamples must be a non-negative integer")),
+        None => DEFAULT_RESAMPLES,
+    };
+
+    let report = regression::load_reports(criterion_dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load criterion results: {}", e)));
+    let overrides = match args.option("overrides") {
+        Some(path) => regression::load_overrides(Path::new(path))
+            .unwrap_or_else(|e| fail(&format!("Failed to load overrides: {}", e))),
+        None => BTreeMap::new(),
+    };
+
+    let selections = regression::select_models(&report, &overrides);
+    for (function, selection) in &selections {
+        let chosen = selection.chosen();
+        println!(
+            "{}: {} a={} b={} r2={}",
+            function, chosen.fit.model, chosen.fit.a, chosen.fit.b, chosen.quality.r_squared
+        );
+        if selection.disagrees() {
+            println!(
+                "  WARNING: overridden as {}, but the data fits {} best",
+                selection.model(),
+                selection.recommended
+            );
+            for (size, residual) in &chosen.quality.residuals {
+                println!("    residual at {}: {}", size, residual);
+            }
+        }
+    }
+    for function in overrides.keys().filter(|f| !report.contains_key(*f)) {
+        println!(
+            "WARNING: override for {} has no benchmark results",
+            function
+        );
+    }
+
+    let constants_path = output_dir.join(fit_file_name(metric, "cost_constants.csv"));
+    regression::write_cost_constants(
+        &mut create_file(&constants_path),
+        &regression::chosen_fits(&selections),
+    )
+    .expect("Failed to write cost constants");
+    println!("Wrote {}", constants_path.display());
+
+    let selection_path = output_dir.join(fit_file_name(metric, "model_selection.csv"));
+    regression::write_model_selection(&mut create_file(&selection_path), &selections)
+        .expect("Failed to write model selection");
+    println!("Wrote {}", selection_path.display());
+
+    if !runs.is_empty() {
+        run_metadata::write_runs(&output_dir, &runs)
+            .unwrap_or_else(|e| fail(&format!("Failed to write run metadata: {}", e)));
+    }
+
+    // intervals of an earlier fit would otherwise be attached to this one by emit
+    let intervals_path = output_dir.join(fit_file_name(metric, "cost_intervals.csv"));
+    if resamples == 0 {
+        if intervals_path.is_file() {
+            fs::remove_file(&intervals_path)
+                .unwrap_or_else(|e| fail(&format!("Failed to remove stale intervals: {}", e)));
+            println!(
+                "Removed {}, which was of an earlier fit",
+                intervals_path.display()
+            );
+        }
+        return;
+    }
+    let samples = bootstrap::load_samples(criterion_dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load criterion samples: {}", e)));
+    let mut intervals = BTreeMap::new();
+    for (function, selection) in &selections {
+        // an interval from a subset of the sizes wouldn't be of the same fit
+        let function_samples = match samples.get(function) {
+            Some(s) if s.len() == report[function].len() => s,
+            _ => {
+                println!(
+                    "WARNING: {} has no samples for some sizes, so no interval",
+                    function
+                );
+                continue;
+            }
+        };
+        let interval = bootstrap::bootstrap(
+            selection.model(),
+            function_samples,
+            resamples,
+            DEFAULT_BOOTSTRAP_SEED,
+        );
+        if interval.slope_undetermined(selection.model()) {
+            println!(
+                "WARNING: {} slope interval [{}, {}] includes 0",
+                function, interval.a.lower, interval.a.upper
+            );
+        }
+        intervals.insert(function.clone(), interval);
+    }
+    bootstrap::write_cost_intervals(&mut create_file(&intervals_path), &intervals)
+        .expect("Failed to write cost intervals");
+    println!("Wrote {}", intervals_path.display());
+}
+
+fn emit(args: &[String]) {
+    let args = Args::parse(args, &["input", "target-seconds", "output"], (0, 0));
+    let input_dir = args.path_or("input", DEFAULT_ANALYSIS_DIR);
+    let output_dir = args
+        .option("output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| input_dir.clone());
+    let target_seconds = match args.option("target-seconds") {
+        Some(secs) => secs
+            .parse()
+            .unwrap_or_else(|_| fail("--target-seconds must be a number")),
+        None => DEFAULT_TARGET_WALL_TIME_SECS,
+    };
+
+    // runtime costs are priced from wall time, so refuse fits of anything else
+    let runs = run_metadata::load_runs(&input_dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load run metadata: {}", e)));
+    let metric = measured_metric(&runs);
+    if metric != Metric::WallTime {
+        fail(&format!(
+            "{} was fitted to {}, not wall_time, so it can't be priced as runtime",
+            input_dir.display(),
+            metric
+        ));
+    }
+
+    let fits = regression::load_fits(
+        &input_dir.join("cost_constants.csv"),
+        &input_dir.join("model_selection.csv"),
+    )
+    .unwrap_or_else(|e| fail(&format!("Failed to load fitted models: {}", e)));
+    let intervals_path = input_dir.join("cost_intervals.csv");
+    let intervals = match intervals_path.is_file() {
+        true => bootstrap::load_cost_intervals(&intervals_path)
+            .unwrap_or_else(|e| fail(&format!("Failed to load cost intervals: {}", e))),
+        false => BTreeMap::new(),
+    };
+    // each fit is of one benchmark iteration, which runs the function `scale` times
+    let executions = proposal::executions_per_sample(fits.keys(), &runs)
+        .unwrap_or_else(|e| fail(&format!("Can't scale the fitted models: {}", e)));
+    let scaling =
+        Scaling::with_target_wall_time(target_seconds).with_executions_per_sample(executions);
+
+    let contract_path = output_dir.join("new_costs.clar");
+    proposal::write_costs_contract(&mut create_file(&contract_path), &fits, &scaling)
+        .expect("Failed to write costs contract");
+    println!("Wrote {}", contract_path.display());
+    let unfitted = proposal::unfitted_functions(&fits);
+    if !unfitted.is_empty() {
+        println!(
+            "WARNING: {} cost functions have no fitted model and keep the placeholder cost: {}",
+            unfitted.len(),
+            unfitted.join(", ")
+        );
+    }
+
+    let table_path = output_dir.join("updates_table.md");
+    proposal::write_updates_table(&mut create_file(&table_path), &fits, &intervals, &scaling)
+        .expect("Failed to write updates table");
+    println!("Wrote {}", table_path.display());
+
+    let source = fs::read_to_string(&contract_path).expect("Failed to read costs contract");
+    let report = costs_check::check_costs_contract(&source, &fits, &scaling);
+    for error in &report.errors {
+        println!("ERROR: {}", error);
+    }
+    if !report.is_ok() {
+        fail(&format!(
+            "{} failed its check with {} errors",
+            contract_path.display(),
+            report.errors.len()
+        ));
+    }
+    println!(
+        "Checked {}: {} cost function evaluations match the fitted models",
+        contract_path.display(),
+        report.evaluated
+    );
+}
+
+/// Results given to `compare`: a criterion directory, whose fits are made with
+/// `models` where given and otherwise the models the data fits best, or an
+/// analysis directory written by `fit`, which only has fits.
+fn load_results(
+    dir: &str,
+    models: &BTreeMap<String, Model>,
+) -> (Option<Report>, BTreeMap<String, Fit>) {
+    let dir = Path::new(dir);
+    let constants_path = dir.join("cost_constants.csv");
+    if constants_path.is_file() {
+        let fits = regression::load_fits(&constants_path, &dir.join("model_selection.csv"))
+            .unwrap_or_else(|e| fail(&format!("Failed to load fitted models: {}", e)));
+        return (None, fits);
+    }
+
+    let report = regression::load_reports(dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load criterion results: {}", e)));
+    let fits = regression::chosen_fits(&regression::select_models(&report, models));
+    (Some(report), fits)
+}
+
+fn compare(args: &[String]) {
+    let args = Args::parse(args, &["threshold", "allow-mixed", "output"], (2, 2));
+    let threshold = args.option("threshold").map(|percent| {
+        percent
+            .parse::<f64>()
+            .unwrap_or_else(|_| fail("--threshold must be a percentage"))
+            / 100.0
+    });
+    let (baseline_report, baseline_fits) = load_results(&args.positional[0], &BTreeMap::new());
+    let (candidate_report, candidate_fits) = load_results(
+        &args.positional[1],
+        &compare::baseline_models(&baseline_fits),
+    );
+
+    let load_runs = |dir: &str| {
+        let runs = run_metadata::load_runs(Path::new(dir))
+            .unwrap_or_else(|e| fail(&format!("Failed to load run metadata: {}", e)));
+        if runs.is_empty() {
+            eprintln!("WARNING: {} has no run metadata", dir);
+        }
+        runs
+    };
+    let (baseline_runs, candidate_runs) = (
+        load_runs(&args.positional[0]),
+        load_runs(&args.positional[1]),
+    );
+    check_differences(
+        "baseline and candidate",
+        &run_metadata::cross_run_differences(&baseline_runs, &candidate_runs),
+        Difference::incommensurable,
+        args.flag("allow-mixed"),
+    );
+
+    // medians can only be compared between two criterion directories
+    let comparisons = match (&baseline_report, &candidate_report) {
+        (Some(baseline), Some(candidate)) => compare::compare_reports(baseline, candidate),
+        _ => vec![],
+    };
+    let fit_comparisons = compare::compare_fits(&baseline_fits, &candidate_fits);
+
+    let write = |mut out: &mut dyn Write| -> io::Result<()> {
+        if !comparisons.is_empty() {
+            compare::write_comparison(&mut out, &comparisons)?;
+            writeln!(out)?;
+        }
+        compare::write_fit_comparison(&mut out, &fit_comparisons)
+    };
+    match args.option("output") {
+        Some(path) => {
+            write(&mut create_file(Path::new(path))).expect("Failed to write comparison");
+            println!("Wrote {}", path);
+        }
+        None => write(&mut io::stdout()).expect("Failed to write comparison"),
+    }
+
+    if let Some(threshold) = threshold {
+        let mut regressions = vec![];
+        for comparison in comparisons.iter().filter(|c| c.regressed(threshold)) {
+            regressions.push(format!(
+                "{} at input size {}: median {:+.1}%",
+                comparison.function,
+                comparison.input_size,
+                comparison.change().unwrap() * 100.0
+            ));
+        }
+        for comparison in fit_comparisons.iter().filter(|c| c.regressed(threshold)) {
+            regressions.push(match (comparison.baseline, comparison.candidate) {
+                (Some(baseline), Some(candidate)) if comparison.model_changed() => format!(
+                    "{}: model changed from {} to {}",
+                    comparison.function, baseline.model, candidate.model
+                ),
+                _ => format!(
+                    "{}: a {}, b {}",
+                    comparison.function,
+                    compare::format_change(comparison.a_change()),
+                    compare::format_change(comparison.b_change())
+                ),
+            });
+        }
+        if !regressions.is_empty() {
+            eprintln!(
+                "{} regressions above {}%:",
+                regressions.len(),
+                threshold * 100.0
+            );
+            for regression in &regressions {
+                eprintln!("  {}", regression);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+fn make_chainstate(args: &[String]) {
+    let args = Args::parse(args, &["blocks", "seed"], (1, 1));
+    let path = &args.positional[0];
+    if Path::new(path).exists() {
+        fail(&format!("{} already exists", path));
+    }
+
+    let mut builder = ChainstateBuilder::new(DEFAULT_CHAINSTATE_BLOCKS);
+    if let Some(blocks) = args.option("blocks") {
+        builder.block_count = blocks
+            .parse()
+            .unwrap_or_else(|_| fail("--blocks must be a non-negative integer"));
+    }
+    if let Some(seed) = args.option("seed") {
+        builder.seed = seed
+            .parse()
+            .unwrap_or_else(|_| fail("--seed must be an unsigned 64-bit integer"));
+    }
+
+    builder
+        .build(path)
+        .and_then(|headers_db| headers_db.validate(MIN_BLOCK_COUNT))
+        .unwrap_or_else(|e| fail(&format!("Failed to write chainstate: {}", e)));
+    println!("Wrote {} blocks to {}", builder.block_count, path);
+}
+
+/// Missing directories are reported as nothing measured or fitted, so coverage
+/// can be checked before anything has been run.
+fn coverage(args: &[String]) {
+    let args = Args::parse(args, &["criterion", "analysis", "output"], (0, 0));
+    let criterion_dir = args.path_or("criterion", DEFAULT_CRITERION_DIR);
+    let analysis_dir = args.path_or("analysis", DEFAULT_ANALYSIS_DIR);
+
+    let measurements = match criterion_dir.is_dir() {
+        true => Some(
+            coverage::load_measurements(&criterion_dir)
+                .unwrap_or_else(|e| fail(&format!("Failed to load criterion results: {}", e))),
+        ),
+        false => None,
+    };
+    let constants_path = analysis_dir.join("cost_constants.csv");
+    let fits = match constants_path.is_file() {
+        true => Some(
+            regression::load_fits(&constants_path, &analysis_dir.join("model_selection.csv"))
+                .unwrap_or_else(|e| fail(&format!("Failed to load fitted models: {}", e))),
+        ),
+        false => None,
+    };
+    if measurements.is_none() {
+        eprintln!(
+            "WARNING: no criterion results in {}",
+            criterion_dir.display()
+        );
+    }
+    if fits.is_none() {
+        eprintln!("WARNING: no fitted models in {}", analysis_dir.display());
+    }
+
+    let rows = coverage::coverage(measurements.as_ref(), fits.as_ref());
+    match args.option("output") {
+        Some(path) => {
+            coverage::write_coverage(&mut create_file(Path::new(path)), &rows)
+                .expect("Failed to write coverage report");
+            println!("Wrote {}", path);
+        }
+        None => coverage::write_coverage(&mut io::stdout(), &rows)
+            .expect("Failed to write coverage report"),
+    }
+}
+
+/// The input sizes searched default to the largest the function is benchmarked at,
+/// where per-unit differences are least masked by constant overhead.
+fn search(args: &[String]) {
+    let args = Args::parse(
+        args,
+        &["input-sizes", "seed", "iterations", "analysis", "output"],
+        (1, 1),
+    );
+    let name = &args.positional[0];
+    let function = *ClarityCostFunction::ALL
+        .iter()
+        .find(|function| function.to_string() == *name || function.get_name() == *name)
+        .unwrap_or_else(|| fail(&format!("Unknown cost function {}", name)));
+    let spec = registry::spec_for(CostFunction::Clarity(function));
+
+    let number = |option: &str, default: u64| match args.option(option) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| fail(&format!("--{} must be a non-negative integer", option))),
+        None => default,
+    };
+    let input_sizes = match args.option("input-sizes") {
+        Some(sizes) => registry::parse_input_sizes(sizes)
+            .unwrap_or_else(|e| fail(&format!("Invalid --input-sizes: {}", e))),
+        None => vec![spec
+            .and_then(|spec| spec.input_sizes().last().copied())
+            .unwrap_or(1)],
+    };
+    let config = SearchConfig {
+        iterations: number("iterations", search::DEFAULT_ITERATIONS as u64) as usize,
+        seed: number("seed", 0),
+        ..SearchConfig::default()
+    };
+
+    let analysis_dir = args.path_or("analysis", DEFAULT_ANALYSIS_DIR);
+    let constants_path = analysis_dir.join("cost_constants.csv");
+    let fits = match constants_path.is_file() {
+        true => regression::load_fits(&constants_path, &analysis_dir.join("model_selection.csv"))
+            .unwrap_or_else(|e| fail(&format!("Failed to load fitted models: {}", e))),
+        false => BTreeMap::new(),
+    };
+    let fit_name = function.to_string();
+    let fit = fits.get(&fit_name);
+    if fit.is_none() {
+        eprintln!(
+            "WARNING: no fitted model for {} in {}, counting each charge as one cost unit",
+            function,
+            analysis_dir.display()
+        );
+    }
+    let runs = run_metadata::load_runs(&analysis_dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load run metadata: {}", e)));
+    let executions = proposal::executions_per_sample(fit.map(|_| &fit_name), &runs)
+        .unwrap_or_else(|e| fail(&format!("Can't scale the fitted model: {}", e)));
+    let scaling = Scaling::with_target_wall_time(DEFAULT_TARGET_WALL_TIME_SECS)
+        .with_executions_per_sample(executions);
+    let pricing = Pricing::new(function, fit, &scaling);
+
+    for input_size in input_sizes {
+        let output = try_gen(function, search::SEARCH_SCALE, input_size, config.seed)
+            .unwrap_or_else(|reason| fail(&format!("No generator for {}: {}", function, reason)));
+        let result = search::search(function, &output, &pricing, &config).unwrap_or_else(|e| {
+            fail(&format!(
+                "The generated code for {} at input size {} can't be searched: {}",
+                function, input_size, e
+            ))
+        });
+        search::write_search_report(&mut io::stdout(), &result)
+            .expect("Failed to write search report");
+
+        let output_dir = args
+            .path_or("output", DEFAULT_SEARCH_DIR)
+            .join(function.to_string())
+            .join(format!("input_{}", input_size));
+        for (rank, variant) in result.worst.iter().enumerate() {
+            let path = output_dir.join(format!("rank_{}.clar", rank + 1));
+            create_file(&path)
+                .write_all(variant.body.as_bytes())
+                .expect("Failed to write variant");
+        }
+        if let Some(setup) = &output.setup {
+            create_file(&output_dir.join("setup.clar"))
+                .write_all(setup.as_bytes())
+                .expect("Failed to write setup");
+        }
+        println!(
+            "Wrote {} variants to {}",
+            result.worst.len(),
+            output_dir.display()
+        );
+    }
+}
+
+/// The contract is named after its file, and deployed at a local address.
+fn audit(args: &[String]) {
+    let args = Args::parse(
+        args,
+        &["costs", "sender", "repetitions", "target-seconds", "output"],
+        (2, 2),
+    );
+    let contract_path = Path::new(&args.positional[0]);
+    let calls_path = Path::new(&args.positional[1]);
+    let costs_path = args
+        .option("costs")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(DEFAULT_ANALYSIS_DIR).join("new_costs.clar"));
+    let read = |path: &Path| {
+        fs::read_to_string(path)
+            .unwrap_or_else(|e| fail(&format!("Failed to read {}: {}", path.display(), e)))
+    };
+    let source = read(contract_path);
+    let costs = read(&costs_path);
+    let calls = audit::parse_calls(&read(calls_path))
+        .unwrap_or_else(|e| fail(&format!("{}: {}", calls_path.display(), e)));
+
+    let name = contract_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let contract = QualifiedContractIdentifier::local(name)
+        .unwrap_or_else(|_| fail(&format!("{} is not a valid contract name", name)));
+    let sender = args.option("sender").unwrap_or(DEFAULT_SENDER);
+    let sender = PrincipalData::parse(sender)
+        .unwrap_or_else(|_| fail(&format!("{} is not a valid principal", sender)));
+    let repetitions = match args.option("repetitions") {
+        Some(n) => match n.parse() {
+            Ok(n) if n > 0 => n,
+            _ => fail("--repetitions must be a positive integer"),
+        },
+        None => DEFAULT_REPETITIONS,
+    };
+    let target_seconds = match args.option("target-seconds") {
+        Some(secs) => secs
+            .parse()
+            .unwrap_or_else(|_| fail("--target-seconds must be a number")),
+        None => DEFAULT_TARGET_WALL_TIME_SECS,
+    };
+    let scaling = Scaling::with_target_wall_time(target_seconds);
+
+    let audit = audit::audit(&contract, &source, &calls, &costs, &sender, repetitions)
+        .unwrap_or_else(|e| fail(&e));
+    match args.option("output") {
+        Some(path) => {
+            audit::write_audit(&mut create_file(Path::new(path)), &audit, &scaling)
+                .expect("Failed to write audit");
+            println!("Wrote {}", path);
+        }
+        None => {
+            audit::write_audit(&mut io::stdout(), &audit, &scaling).expect("Failed to write audit")
+        }
+    }
+}
+
+fn run_storage(args: &[String]) {
+    let args = Args::parse(args, &["output"], (0, 0));
+    let current_dir = env::current_dir().expect("Failed to get current directory");
+    let output = current_dir.join(args.path_or("output", STORAGE_CRITERION_DIR));
+
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(&["bench", "--bench", "storage"])
+        .env("CRITERION_HOME", &output)
+        .status()
+        .unwrap_or_else(|e| fail(&format!("Failed to run cargo bench: {}", e)));
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+    println!("Wrote storage benchmark results to {}", output.display());
+}
+
+fn fit_storage(args: &[String]) {
+    let args = Args::parse(args, &["target-seconds", "output"], (1, 1));
+    let criterion_dir = Path::new(&args.positional[0]);
+    let output_dir = args.path_or("output", DEFAULT_ANALYSIS_DIR);
+    let target_seconds = match args.option("target-seconds") {
+        Some(secs) => secs
+            .parse()
+            .unwrap_or_else(|_| fail("--target-seconds must be a number")),
+        None => DEFAULT_TARGET_WALL_TIME_SECS,
+    };
+    let scaling = Scaling::with_target_wall_time(target_seconds);
+
+    let report = regression::load_reports(criterion_dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to load criterion results: {}", e)));
+    let fits = storage_dimensions::fit_dimensions(&report);
+    if fits.is_empty() {
+        fail(&format!(
+            "{} has no storage benchmark results, run run-storage first",
+            criterion_dir.display()
+        ));
+    }
+    for (operation, fit) in &fits {
+        let (count_limit, length_limit) = storage_dimensions::block_limits(*operation);
+        let (count_seconds, length_seconds) = fit.seconds_at_limits(count_limit, length_limit);
+        println!(
+            "{}: {} ns per operation, {} ns per byte; the block limits of {} operations and {} bytes take {:.2}s and {:.2}s",
+            operation,
+            fit.ns_per_operation,
+            fit.ns_per_byte,
+            count_limit,
+            length_limit,
+            count_seconds,
+            length_seconds
+        );
+    }
+
+    let constants_path = output_dir.join("storage_constants.csv");
+    storage_dimensions::write_storage_constants(&mut create_file(&constants_path), &fits, &scaling)
+        .expect("Failed to write storage constants");
+    println!("Wrote {}", constants_path.display());
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (subcommand, rest) = match args.split_first() {
+        Some((subcommand, rest)) => (subcommand.as_str(), rest),
+        None => usage(),
+    };
+
+    match subcommand {
+        "run" => run(rest, ProbeMode::Off),
+        "check-sizes" => run(rest, ProbeMode::CheckSizes),
+        "record-charges" => run(rest, ProbeMode::RecordCharges(PathBuf::new())),
+        "fit" => fit(rest),
+        "emit" => emit(rest),
+        "compare" => compare(rest),
+        "make-chainstate" => make_chainstate(rest),
+        "coverage" => coverage(rest),
+        "search" => search(rest),
+        "audit" => audit(rest),
+        "run-storage" => run_storage(rest),
+        "fit-storage" => fit_storage(rest),
+        _ => usage(),
+    }
+}
@@ -0,0 +1,278 @@
+//! Confidence intervals on fitted constants, bootstrapped from criterion's
+//! per-sample measurements.
+//!
+//! A fit only sees the median of each input size's samples. Resampling those
+//! samples with replacement, taking the median again and refitting shows how
+//! much the constants move with the noise in the measurements: a constant whose
+//! interval is narrow is well determined, one whose interval is wide (or, for a
+//! slope, includes zero) is mostly noise.
+
+use crate::regression::{fit, result_dirs, Error, Model};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+/// Per-iteration nanoseconds of every sample, keyed by input size, for each cost function.
+pub type Samples = BTreeMap<String, BTreeMap<u64, Vec<f64>>>;
+
+pub const DEFAULT_RESAMPLES: usize = 1000;
+
+/// Seed of the resampling RNG, so the same samples always give the same intervals.
+pub const DEFAULT_BOOTSTRAP_SEED: u64 = 0;
+
+/// Probability that an interval contains the constant.
+pub const CONFIDENCE_LEVEL: f64 = 0.95;
+
+/// Reads every `<function>/<size>/base/sample.json` under `criterion_dir`. Sizes
+/// without one, e.g. results copied without their samples, are skipped.
+pub fn load_samples(criterion_dir: &Path) -> Result<Samples, Error> {
+    let mut samples = Samples::new();
+    for (function_name, size, base_dir) in result_dirs(criterion_dir)? {
+        let sample_path = base_dir.join("sample.json");
+        if !sample_path.is_file() {
+            continue;
+        }
+        samples
+            .entry(function_name)
+            .or_insert_with(BTreeMap::new)
+            .insert(size, read_sample(&sample_path)?);
+    }
+    Ok(samples)
+}
+
+/// The `times` of a sample divided by its `iters`, which are the values criterion
+/// takes the median of.
+fn read_sample(path: &Path) -> Result<Vec<f64>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let sample: serde_json::Value =
+        serde_json::from_str(&contents).map_err(|e| Error::Json(path.to_path_buf(), e))?;
+    let parse_error = |msg: &str| Error::Parse(path.to_path_buf(), msg.to_string());
+    let values = |field: &str| -> Result<Vec<f64>, Error> {
+        sample[field]
+            .as_array()
+            .ok_or_else(|| parse_error(&format!("no {} array", field)))?
+            .iter()
+            .map(|v| {
+                v.as_f64()
+                    .ok_or_else(|| parse_error(&format!("non-numeric {}", field)))
+            })
+            .collect()
+    };
+    let (iters, times) = (values("iters")?, values("times")?);
+    if iters.len() != times.len() || iters.is_empty() {
+        return Err(parse_error("iters and times differ in length or are empty"));
+    }
+    Ok(times.iter().zip(iters.iter()).map(|(t, n)| t / n).collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Bootstrapped intervals on the constants of one fit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitInterval {
+    pub a: Interval,
+    pub b: Interval,
+}
+
+impl FitInterval {
+    /// True when the data can't tell the slope of a non-constant `model` from zero.
+    pub fn slope_undetermined(&self, model: Model) -> bool {
+        model != Model::Constant && self.a.lower <= 0.0
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// The `q` quantile of `sorted`, by nearest rank.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let rank = (q * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank]
+}
+
+fn interval(mut values: Vec<f64>) -> Interval {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let tail = (1.0 - CONFIDENCE_LEVEL) / 2.0;
+    Interval {
+        lower: quantile(&values, tail),
+        upper: quantile(&values, 1.0 - tail),
+    }
+}
+
+/// Percentile intervals on `a` and `b` of `model` fitted to `samples`, from
+/// `resamples` refits to medians of samples drawn with replacement.
+pub fn bootstrap(
+    model: Model,
+    samples: &BTreeMap<u64, Vec<f64>>,
+    resamples: usize,
+    seed: u64,
+) -> FitInterval {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut a_values = Vec::with_capacity(resamples);
+    let mut b_values = Vec::with_capacity(resamples);
+    let mut resampled = vec![];
+
+    for _ in 0..resamples {
+        let mut points = BTreeMap::new();
+        for (size, values) in samples {
+            resampled.clear();
+            resampled.extend((0..values.len()).map(|_| values[rng.gen_range(0..values.len())]));
+            points.insert(*size, median(&mut resampled));
+        }
+        let fit = fit(model, &points);
+        a_values.push(fit.a);
+        b_values.push(fit.b);
+    }
+
+    FitInterval {
+        a: interval(a_values),
+        b: interval(b_values),
+    }
+}
+
+/// Writes intervals in the `function,a_lower,a_upper,b_lower,b_upper` format of
+/// `cost_intervals.csv`.
+pub fn write_cost_intervals<W: Write>(
+    out: &mut W,
+    intervals: &BTreeMap<String, FitInterval>,
+) -> io::Result<()> {
+    writeln!(out, "function,a_lower,a_upper,b_lower,b_upper")?;
+    for (function, interval) in intervals {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            function, interval.a.lower, interval.a.upper, interval.b.lower, interval.b.upper
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads back the intervals written by `write_cost_intervals`.
+pub fn load_cost_intervals(path: &Path) -> Result<BTreeMap<String, FitInterval>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let mut intervals = BTreeMap::new();
+    for line in contents.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let values: Option<Vec<f64>> = fields
+            .get(1..5)
+            .and_then(|bounds| bounds.iter().map(|b| b.parse().ok()).collect());
+        let values = values.ok_or_else(|| {
+            Error::Parse(
+                path.to_path_buf(),
+                format!(
+                    "expected function,a_lower,a_upper,b_lower,b_upper: {}",
+                    line
+                ),
+            )
+        })?;
+        intervals.insert(
+            fields[0].to_string(),
+            FitInterval {
+                a: Interval {
+                    lower: values[0],
+                    upper: values[1],
+                },
+                b: Interval {
+                    lower: values[2],
+                    upper: values[3],
+                },
+            },
+        );
+    }
+    Ok(intervals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZES: [u64; 6] = [1, 2, 8, 16, 32, 64];
+
+    /// Samples of `3n + 10` at every size, each offset by one of `noise`.
+    fn linear_samples(noise: &[f64]) -> BTreeMap<u64, Vec<f64>> {
+        SIZES
+            .iter()
+            .map(|n| {
+                let y = 3.0 * *n as f64 + 10.0;
+                (*n, noise.iter().map(|e| y + e).collect())
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn takes_the_median_and_nearest_rank_quantiles() {
+        assert_close(median(&mut [3.0, 1.0, 2.0]), 2.0);
+        assert_close(median(&mut [4.0, 1.0, 3.0, 2.0]), 2.5);
+
+        let values: Vec<f64> = (0..=100).map(f64::from).collect();
+        assert_close(quantile(&values, 0.5), 50.0);
+        let bounds = interval(values.into_iter().rev().collect());
+        assert_close(bounds.lower, 3.0);
+        assert_close(bounds.upper, 98.0);
+    }
+
+    #[test]
+    fn noiseless_samples_give_the_exact_constants() {
+        let intervals = bootstrap(Model::Linear, &linear_samples(&[0.0; 10]), 100, 0);
+        assert_close(intervals.a.lower, 3.0);
+        assert_close(intervals.a.upper, 3.0);
+        assert_close(intervals.b.lower, 10.0);
+        assert_close(intervals.b.upper, 10.0);
+        assert!(!intervals.slope_undetermined(Model::Linear));
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_intervals() {
+        let samples = linear_samples(&[-2.0, -1.0, 0.0, 1.0, 2.0]);
+        let first = bootstrap(Model::Linear, &samples, 200, 7);
+        assert_eq!(first, bootstrap(Model::Linear, &samples, 200, 7));
+        assert_ne!(first, bootstrap(Model::Linear, &samples, 200, 8));
+    }
+
+    #[test]
+    fn intervals_bracket_the_constants_within_the_noise() {
+        let samples = linear_samples(&[-2.0, -1.0, 0.0, 1.0, 2.0]);
+        let intervals = bootstrap(Model::Linear, &samples, DEFAULT_RESAMPLES, 0);
+        for (bounds, constant) in [(intervals.a, 3.0), (intervals.b, 10.0)].iter() {
+            assert!(bounds.lower <= *constant && *constant <= bounds.upper);
+        }
+        // no resampled median is more than 2 from the line, so neither is the fit
+        assert!(intervals.a.lower > 2.9 && intervals.a.upper < 3.1);
+        assert!(intervals.b.lower >= 8.0 && intervals.b.upper <= 12.0);
+    }
+
+    #[test]
+    fn a_slope_in_the_noise_is_undetermined() {
+        let samples: BTreeMap<u64, Vec<f64>> = SIZES
+            .iter()
+            .map(|n| (*n, vec![98.0, 99.0, 100.0, 101.0, 102.0]))
+            .collect();
+        let intervals = bootstrap(Model::Linear, &samples, DEFAULT_RESAMPLES, 0);
+        assert!(intervals.a.lower < 0.0 && intervals.a.upper > 0.0);
+        assert!(intervals.slope_undetermined(Model::Linear));
+        assert!(!intervals.slope_undetermined(Model::Constant));
+    }
+}
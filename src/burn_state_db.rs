@@ -0,0 +1,241 @@
+//! A `BurnStateDB` over synthetic sortitions, so benchmarks that consult
+//! burnchain state don't measure the `NULL_BURN_STATE_DB` path.
+//!
+//! Sortitions are derived from a chainstate: every burn height up to the
+//! chainstate's last block has one, and where the chainstate has a Stacks block
+//! the sortition has that block's burn header hash. There are no forks.
+//!
+//! PoX parameters aren't part of `BurnStateDB`: the VM only asks it for burn
+//! block heights and hashes, and the PoX contract keeps its reward cycle
+//! parameters in its own data, so none are stored here.
+
+use crate::headers_db::{Error, SimHeadersDB};
+use blockstack_lib::types::chainstate::{BurnchainHeaderHash, SortitionId};
+use blockstack_lib::vm::database::BurnStateDB;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BURN_STATE_SCHEMA: &str = "
+    CREATE TABLE sortitions(
+        sortition_id TEXT PRIMARY KEY,
+        parent_sortition_id TEXT NOT NULL,
+        block_height INTEGER UNIQUE NOT NULL,
+        burn_header_hash TEXT NOT NULL
+    );";
+
+/// Sortition of the burn block with hash `burn_header_hash`, as the node derives it
+/// on the stubbed PoX fork, the only fork here.
+fn sortition_id(burn_header_hash: &BurnchainHeaderHash) -> SortitionId {
+    SortitionId::stubbed(burn_header_hash)
+}
+
+/// Burnchain state of a sortition database, opened read-only.
+pub struct SimBurnStateDB {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SimBurnStateDB {
+    /// Writes sortitions for the blocks of `headers_db` to a new database at `path`,
+    /// and opens it. Burn blocks without a Stacks block get hashes drawn from an RNG
+    /// seeded with `seed`.
+    pub fn build_from_chainstate<P: AsRef<Path>>(
+        headers_db: &SimHeadersDB,
+        seed: u64,
+        path: P,
+    ) -> Result<SimBurnStateDB, Error> {
+        let path = path.as_ref().to_path_buf();
+        let mut burn_hashes = BTreeMap::new();
+        for (height, hash) in headers_db.burn_blocks()? {
+            burn_hashes.entry(height).or_insert(hash);
+        }
+        let last_height = burn_hashes.keys().next_back().copied().unwrap_or(0);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::Io(path.clone(), e))?;
+        }
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+        let mut conn = Connection::open_with_flags(&path, flags)
+            .map_err(|e| Error::Sqlite(path.clone(), e))?;
+        write_sortitions(&mut conn, &burn_hashes, last_height, seed)
+            .map_err(|e| Error::Sqlite(path.clone(), e))?;
+        drop(conn);
+
+        SimBurnStateDB::open(path)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SimBurnStateDB, Error> {
+        let path = path.as_ref().to_path_buf();
+        if !path.is_file() {
+            return Err(Error::NotFound(path));
+        }
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| Error::Sqlite(path.clone(), e))?;
+        Ok(SimBurnStateDB { path, conn })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn write_sortitions(
+    conn: &mut Connection,
+    burn_hashes: &BTreeMap<u32, BurnchainHeaderHash>,
+    last_height: u32,
+    seed: u64,
+) -> rusqlite::Result<()> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let tx = conn.transaction()?;
+    tx.execute_batch(BURN_STATE_SCHEMA)?;
+
+    let mut parent = SortitionId([0xff; 32]);
+    for height in 0..=last_height {
+        let burn_header_hash = match burn_hashes.get(&height) {
+            Some(hash) => hash.clone(),
+            None => BurnchainHeaderHash(rng.gen()),
+        };
+        let sortition = sortition_id(&burn_header_hash);
+        tx.execute(
+            "INSERT INTO sortitions VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                sortition.to_hex(),
+                parent.to_hex(),
+                height,
+                burn_header_hash.to_hex()
+            ],
+        )?;
+        parent = sortition;
+    }
+
+    tx.commit()
+}
+
+impl BurnStateDB for SimBurnStateDB {
+    fn get_burn_block_height(&self, sortition_id: &SortitionId) -> Option<u32> {
+        self.conn
+            .query_row(
+                "SELECT block_height FROM sortitions WHERE sortition_id = ?",
+                &[sortition_id.to_hex()],
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("Unexpected SQL failure querying sortitions table")
+    }
+
+    /// Hash of the burn block at `height` in the fork of `sortition_id`. There is
+    /// one fork, so that is any block at or below the sortition.
+    fn get_burn_header_hash(
+        &self,
+        height: u32,
+        sortition_id: &SortitionId,
+    ) -> Option<BurnchainHeaderHash> {
+        let tip_height = self.get_burn_block_height(sortition_id)?;
+        if height > tip_height {
+            return None;
+        }
+        self.conn
+            .query_row(
+                "SELECT burn_header_hash FROM sortitions WHERE block_height = ?",
+                &[height],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .expect("Unexpected SQL failure querying sortitions table")
+            .map(|hash| BurnchainHeaderHash::from_hex(&hash).expect("Bad burn header hash"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers_db::ChainstateBuilder;
+    use blockstack_lib::clarity_vm::database::MemoryBackingStore;
+    use blockstack_lib::types::chainstate::StacksBlockId;
+    use blockstack_lib::vm::database::ClarityDatabase;
+    use rusqlite::NO_PARAMS;
+    use std::env;
+    use std::process;
+
+    /// Index block hash, burn height and burn header hash of every block in the
+    /// chainstate at `path`.
+    fn blocks(path: &Path) -> Vec<(StacksBlockId, u32, BurnchainHeaderHash)> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT index_block_hash, burn_header_height, burn_header_hash
+                 FROM block_headers ORDER BY burn_header_height",
+            )
+            .unwrap();
+        let rows = stmt
+            .query_map(NO_PARAMS, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .unwrap();
+        rows.map(|row| {
+            let (id_bhh, height, hash) = row.unwrap();
+            (
+                StacksBlockId::from_hex(&id_bhh).unwrap(),
+                height,
+                BurnchainHeaderHash::from_hex(&hash).unwrap(),
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn resolves_chainstate_blocks_through_clarity_database() {
+        let dir = env::temp_dir().join(format!("burn_state_db_test_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let chainstate_path = dir.join("chainstate.sqlite");
+        let headers_db = ChainstateBuilder::new(8).build(&chainstate_path).unwrap();
+        let burn_state_db =
+            SimBurnStateDB::build_from_chainstate(&headers_db, 0, dir.join("burnchain.sqlite"))
+                .unwrap();
+        let blocks = blocks(&chainstate_path);
+        assert_eq!(blocks.len(), 8);
+
+        let mut store = MemoryBackingStore::new();
+        let mut clarity_db = ClarityDatabase::new(&mut store, &headers_db, &burn_state_db);
+        let (_, tip_height, tip_hash) = blocks.last().unwrap().clone();
+        let tip = sortition_id(&tip_hash);
+        for (id_bhh, height, hash) in &blocks {
+            // the VM reaches the burn height through the headers DB, and the
+            // sortition at that height has the block's burn header hash
+            let burn_height = clarity_db.get_burnchain_block_height(id_bhh);
+            assert_eq!(burn_height, Some(*height));
+
+            let sortition = sortition_id(hash);
+            assert_eq!(
+                burn_state_db.get_burn_block_height(&sortition),
+                Some(*height)
+            );
+            assert_eq!(
+                burn_state_db.get_burn_header_hash(*height, &sortition),
+                Some(hash.clone())
+            );
+            assert_eq!(
+                burn_state_db.get_burn_header_hash(*height, &tip),
+                Some(hash.clone())
+            );
+        }
+        // heights above a sortition aren't in its fork
+        assert_eq!(
+            burn_state_db.get_burn_header_hash(tip_height + 1, &tip),
+            None
+        );
+        assert_eq!(
+            burn_state_db.get_burn_block_height(&SortitionId([0; 32])),
+            None
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
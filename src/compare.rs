@@ -0,0 +1,359 @@
+//! Compares two sets of criterion results, e.g. before and after a change to the
+//! Clarity VM, by median runtime and by fitted constants.
+
+use crate::regression::{Fit, Model, Report};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::io::Write;
+
+/// Median runtimes of one cost function at one input size in two result sets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub function: String,
+    pub input_size: u64,
+    /// nanoseconds, or None if the size wasn't benchmarked in that set
+    pub baseline: Option<f64>,
+    pub candidate: Option<f64>,
+}
+
+impl Comparison {
+    /// Relative change from baseline to candidate, e.g. 0.1 for 10% slower, if
+    /// both sets have the size.
+    pub fn change(&self) -> Option<f64> {
+        match (self.baseline, self.candidate) {
+            (Some(baseline), Some(candidate)) => Some(relative_change(baseline, candidate)),
+            _ => None,
+        }
+    }
+
+    /// True if the candidate is more than `threshold` (e.g. 0.1 for 10%) slower.
+    pub fn regressed(&self, threshold: f64) -> bool {
+        self.change().map_or(false, |change| change > threshold)
+    }
+}
+
+/// Fitted constants of one cost function in two result sets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitComparison {
+    pub function: String,
+    /// None if the function wasn't fitted in that set
+    pub baseline: Option<Fit>,
+    pub candidate: Option<Fit>,
+}
+
+/// Relative change from `baseline` to `candidate`. A baseline of zero or less
+/// can't be divided by, so any growth from it is an infinite change, any drop an
+/// infinitely negative one, and growth from zero still counts as a regression.
+fn relative_change(baseline: f64, candidate: f64) -> f64 {
+    if baseline > 0.0 {
+        (candidate - baseline) / baseline
+    } else if candidate > baseline {
+        f64::INFINITY
+    } else if candidate < baseline {
+        f64::NEG_INFINITY
+    } else {
+        0.0
+    }
+}
+
+impl FitComparison {
+    /// Fits of both sets, if both have one with the same model. Constants of
+    /// different models can't be compared, so candidates from criterion results
+    /// are fitted with the baseline's models, see `baseline_models`.
+    fn comparable(&self) -> Option<(Fit, Fit)> {
+        match (self.baseline, self.candidate) {
+            (Some(baseline), Some(candidate)) if baseline.model == candidate.model => {
+                Some((baseline, candidate))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn model_changed(&self) -> bool {
+        match (self.baseline, self.candidate) {
+            (Some(baseline), Some(candidate)) => baseline.model != candidate.model,
+            _ => false,
+        }
+    }
+
+    /// Relative change of the slope `a`.
+    pub fn a_change(&self) -> Option<f64> {
+        self.comparable()
+            .map(|(baseline, candidate)| relative_change(baseline.a, candidate.a))
+    }
+
+    /// Relative change of the intercept `b`.
+    pub fn b_change(&self) -> Option<f64> {
+        self.comparable()
+            .map(|(baseline, candidate)| relative_change(baseline.b, candidate.b))
+    }
+
+    /// True if `a` or `b` grew by more than `threshold`, or the model changed, since
+    /// then whether the cost grew can't be told.
+    pub fn regressed(&self, threshold: f64) -> bool {
+        self.model_changed()
+            || self
+                .a_change()
+                .into_iter()
+                .chain(self.b_change())
+                .any(|change| change > threshold)
+    }
+}
+
+/// Model of each fit in `fits`, to fit the candidate's criterion results with the
+/// same models as the baseline's, so that their constants can be compared.
+pub fn baseline_models(fits: &BTreeMap<String, Fit>) -> BTreeMap<String, Model> {
+    fits.iter()
+        .map(|(function, fit)| (function.clone(), fit.model))
+        .collect()
+}
+
+/// Pairs up the fits of every function found in either set.
+pub fn compare_fits(
+    baseline: &BTreeMap<String, Fit>,
+    candidate: &BTreeMap<String, Fit>,
+) -> Vec<FitComparison> {
+    let functions: BTreeSet<&String> = baseline.keys().chain(candidate.keys()).collect();
+    functions
+        .into_iter()
+        .map(|function| FitComparison {
+            function: function.clone(),
+            baseline: baseline.get(function).copied(),
+            candidate: candidate.get(function).copied(),
+        })
+        .collect()
+}
+
+/// Pairs up every (function, input size) found in either report.
+pub fn compare_reports(baseline: &Report, candidate: &Report) -> Vec<Comparison> {
+    let functions: BTreeSet<&String> = baseline.keys().chain(candidate.keys()).collect();
+    let mut comparisons = vec![];
+
+    for function in functions {
+        let baseline_points = baseline.get(function);
+        let candidate_points = candidate.get(function);
+        let sizes: BTreeSet<u64> = baseline_points
+            .into_iter()
+            .chain(candidate_points)
+            .flat_map(|points| points.keys().copied())
+            .collect();
+
+        for input_size in sizes {
+            comparisons.push(Comparison {
+                function: function.clone(),
+                input_size,
+                baseline: baseline_points.and_then(|p| p.get(&input_size).copied()),
+                candidate: candidate_points.and_then(|p| p.get(&input_size).copied()),
+            });
+        }
+    }
+
+    comparisons
+}
+
+/// A relative change as a signed percentage, or `-` if there is none.
+pub fn format_change(change: Option<f64>) -> String {
+    match change {
+        Some(change) => format!("{:+.1}%", change * 100.0),
+        None => "-".to_string(),
+    }
+}
+
+/// Writes comparisons as a markdown table.
+pub fn write_comparison<W: Write>(out: &mut W, comparisons: &[Comparison]) -> io::Result<()> {
+    let format_ns = |ns: Option<f64>| match ns {
+        Some(ns) => format!("{:.0}", ns),
+        None => "-".to_string(),
+    };
+
+    writeln!(
+        out,
+        "| Function | Input size | Baseline (ns) | Candidate (ns) | Change |"
+    )?;
+    writeln!(
+        out,
+        "| ----------- | ----------- | ----------- | ----------- | ----------- |"
+    )?;
+    for comparison in comparisons {
+        let change = format_change(comparison.change());
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} |",
+            comparison.function,
+            comparison.input_size,
+            format_ns(comparison.baseline),
+            format_ns(comparison.candidate),
+            change
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes fit comparisons as a markdown table.
+pub fn write_fit_comparison<W: Write>(
+    out: &mut W,
+    comparisons: &[FitComparison],
+) -> io::Result<()> {
+    let format_fit = |fit: Option<Fit>| match fit {
+        Some(fit) => format!("{} a={:.3} b={:.3}", fit.model, fit.a, fit.b),
+        None => "-".to_string(),
+    };
+
+    writeln!(
+        out,
+        "| Function | Baseline | Candidate | Change in a | Change in b |"
+    )?;
+    writeln!(
+        out,
+        "| ----------- | ----------- | ----------- | ----------- | ----------- |"
+    )?;
+    for comparison in comparisons {
+        let (a_change, b_change) = match comparison.model_changed() {
+            true => ("model changed".to_string(), "model changed".to_string()),
+            false => (
+                format_change(comparison.a_change()),
+                format_change(comparison.b_change()),
+            ),
+        };
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} |",
+            comparison.function,
+            format_fit(comparison.baseline),
+            format_fit(comparison.candidate),
+            a_change,
+            b_change
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fit(model: Model, a: f64, b: f64) -> Fit {
+        Fit { model, a, b }
+    }
+
+    fn fits(entries: &[(&str, Fit)]) -> BTreeMap<String, Fit> {
+        entries
+            .iter()
+            .map(|(function, fit)| (function.to_string(), *fit))
+            .collect()
+    }
+
+    #[test]
+    fn relative_change_is_signed_fraction_of_baseline() {
+        assert_eq!(relative_change(100.0, 110.0), 0.1);
+        assert_eq!(relative_change(200.0, 100.0), -0.5);
+        assert_eq!(relative_change(5.0, 5.0), 0.0);
+    }
+
+    #[test]
+    fn relative_change_from_zero_is_infinite() {
+        assert_eq!(relative_change(0.0, 3.0), f64::INFINITY);
+        assert_eq!(relative_change(-2.0, 0.0), f64::INFINITY);
+        assert_eq!(relative_change(0.0, -1.0), f64::NEG_INFINITY);
+        assert_eq!(relative_change(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn growth_from_zero_regresses() {
+        let comparisons = compare_fits(
+            &fits(&[("cost_add", fit(Model::Linear, 2.0, 0.0))]),
+            &fits(&[("cost_add", fit(Model::Linear, 2.0, 50.0))]),
+        );
+        assert_eq!(comparisons[0].a_change(), Some(0.0));
+        assert_eq!(comparisons[0].b_change(), Some(f64::INFINITY));
+        assert!(comparisons[0].regressed(0.1));
+    }
+
+    #[test]
+    fn compare_fits_pairs_every_function_of_either_set() {
+        let comparisons = compare_fits(
+            &fits(&[
+                ("cost_add", fit(Model::Linear, 10.0, 100.0)),
+                ("cost_mul", fit(Model::Linear, 10.0, 100.0)),
+            ]),
+            &fits(&[
+                ("cost_add", fit(Model::Linear, 10.5, 130.0)),
+                ("cost_sub", fit(Model::Linear, 1.0, 1.0)),
+            ]),
+        );
+        let functions: Vec<&str> = comparisons.iter().map(|c| c.function.as_str()).collect();
+        assert_eq!(functions, ["cost_add", "cost_mul", "cost_sub"]);
+
+        let add = &comparisons[0];
+        assert!((add.a_change().unwrap() - 0.05).abs() < 1e-12);
+        assert!((add.b_change().unwrap() - 0.3).abs() < 1e-12);
+        assert!(add.regressed(0.1));
+        assert!(!add.regressed(0.5));
+
+        // a function only one set has is not a regression
+        assert_eq!(comparisons[1].candidate, None);
+        assert_eq!(comparisons[1].a_change(), None);
+        assert!(!comparisons[1].regressed(0.0));
+        assert!(!comparisons[2].regressed(0.0));
+    }
+
+    #[test]
+    fn model_change_regresses_without_comparing_constants() {
+        let comparisons = compare_fits(
+            &fits(&[("cost_map", fit(Model::Linear, 10.0, 100.0))]),
+            &fits(&[("cost_map", fit(Model::NLogN, 1.0, 10.0))]),
+        );
+        assert!(comparisons[0].model_changed());
+        assert_eq!(comparisons[0].a_change(), None);
+        assert_eq!(comparisons[0].b_change(), None);
+        assert!(comparisons[0].regressed(1.0));
+    }
+
+    #[test]
+    fn baseline_models_are_the_fitted_models() {
+        let models = baseline_models(&fits(&[
+            ("cost_add", fit(Model::Linear, 1.0, 1.0)),
+            ("cost_hash160", fit(Model::Constant, 0.0, 1.0)),
+        ]));
+        assert_eq!(models["cost_add"], Model::Linear);
+        assert_eq!(models["cost_hash160"], Model::Constant);
+    }
+
+    #[test]
+    fn compare_reports_pairs_every_size_of_either_report() {
+        let report = |entries: &[(&str, &[(u64, f64)])]| -> Report {
+            entries
+                .iter()
+                .map(|(function, points)| (function.to_string(), points.iter().copied().collect()))
+                .collect()
+        };
+        let comparisons = compare_reports(
+            &report(&[("cost_add", &[(1, 100.0), (2, 200.0)])]),
+            &report(&[
+                ("cost_add", &[(2, 250.0), (8, 800.0)]),
+                ("cost_sub", &[(1, 50.0)]),
+            ]),
+        );
+        let keys: Vec<(&str, u64)> = comparisons
+            .iter()
+            .map(|c| (c.function.as_str(), c.input_size))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("cost_add", 1),
+                ("cost_add", 2),
+                ("cost_add", 8),
+                ("cost_sub", 1)
+            ]
+        );
+
+        assert_eq!(comparisons[0].candidate, None);
+        assert_eq!(comparisons[0].change(), None);
+        assert_eq!(comparisons[1].change(), Some(0.25));
+        assert!(comparisons[1].regressed(0.2));
+        assert!(!comparisons[1].regressed(0.3));
+        assert_eq!(comparisons[2].baseline, None);
+        assert!(!comparisons[3].regressed(0.0));
+    }
+}
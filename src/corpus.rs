@@ -0,0 +1,255 @@
+//! The Clarity code benchmarks time, written to disk.
+//!
+//! Each generator call is stored under
+//! `<dir>/<cost function>/scale_<scale>/input_<input size>/`, keyed by the
+//! arguments it was called with: `body.clar`, `setup.clar` if the generator
+//! returned setup code, and `meta.json` with the cost function, scale, requested
+//! and claimed input sizes and the generator seed. Contracts a benchmark builds
+//! itself rather than with `gen`, such as the ones `AstParse` parses, are stored
+//! the same way under `<dir>/<cost function>/<name>/input_<input size>/`, see
+//! `export_harness`.
+//!
+//! A corpus can be replayed: the bench target then reads each benchmark's code
+//! from it instead of generating it, so results can be re-measured on other
+//! hardware or another blockstack-core revision against the same inputs, even
+//! after the generators change. Only code is stored: benchmarks that time types
+//! or values drawn in Rust from the run seed draw them again from the corpus's
+//! seed, and the bench target warns that those only match while the code
+//! drawing them is unchanged.
+
+use crate::generators::GenOutput;
+use crate::registry::CostFunction;
+use serde_json::{json, Value};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding a directory. When set, the bench target writes
+/// every generator output it times there.
+pub const EXPORT_ENV_VAR: &str = "COST_BENCH_EXPORT_CORPUS";
+
+/// Environment variable holding a corpus directory. When set, the bench target
+/// reads the code it times from there instead of generating it.
+pub const REPLAY_ENV_VAR: &str = "COST_BENCH_REPLAY_CORPUS";
+
+#[derive(Debug)]
+pub enum Error {
+    /// the corpus has no entry for a generator call, e.g. it was exported with a
+    /// different selection or input sizes
+    Missing(PathBuf),
+    Io(PathBuf, io::Error),
+    /// a `meta.json` that can't be read, or doesn't describe its directory
+    BadMeta(PathBuf, String),
+    /// entries were generated with different seeds
+    MixedSeeds(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Missing(path) => write!(f, "{}: no such corpus entry", path.display()),
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::BadMeta(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::MixedSeeds(path) => write!(
+                f,
+                "{}: entries were generated with different seeds",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The corpus directory named by `COST_BENCH_REPLAY_CORPUS`, if any.
+pub fn replay_dir_from_env() -> Option<PathBuf> {
+    env::var_os(REPLAY_ENV_VAR).map(PathBuf::from)
+}
+
+/// The corpus directory named by `COST_BENCH_EXPORT_CORPUS`, if any.
+pub fn export_dir_from_env() -> Option<PathBuf> {
+    env::var_os(EXPORT_ENV_VAR).map(PathBuf::from)
+}
+
+/// Directory of the output of one generator call.
+pub fn entry_dir(dir: &Path, function: CostFunction, scale: u16, input_size: u64) -> PathBuf {
+    dir.join(function.to_string())
+        .join(format!("scale_{}", scale))
+        .join(format!("input_{}", input_size))
+}
+
+/// Directory of a contract a benchmark of `function` builds itself, which it names
+/// `name`.
+pub fn harness_entry_dir(
+    dir: &Path,
+    function: CostFunction,
+    name: &str,
+    input_size: u64,
+) -> PathBuf {
+    dir.join(function.to_string())
+        .join(name)
+        .join(format!("input_{}", input_size))
+}
+
+/// Writes `output`, generated for `function` with `scale`, `input_size` and `seed`,
+/// replacing any earlier output of the same call.
+pub fn export(
+    dir: &Path,
+    function: CostFunction,
+    scale: u16,
+    input_size: u64,
+    seed: u64,
+    output: &GenOutput,
+) -> io::Result<()> {
+    let meta = json!({
+        "cost_function": function.to_string(),
+        "scale": scale,
+        "input_size": input_size,
+        "claimed_input_size": output.input_size,
+        "seed": seed,
+    });
+    write_entry(&entry_dir(dir, function, scale, input_size), meta, output)
+}
+
+/// Writes `output`, a contract named `name` that a benchmark of `function` built
+/// for `input_size` from `seed`, replacing any earlier one.
+pub fn export_harness(
+    dir: &Path,
+    function: CostFunction,
+    name: &str,
+    input_size: u64,
+    seed: u64,
+    output: &GenOutput,
+) -> io::Result<()> {
+    let meta = json!({
+        "cost_function": function.to_string(),
+        "harness": name,
+        "input_size": input_size,
+        "claimed_input_size": output.input_size,
+        "seed": seed,
+    });
+    write_entry(
+        &harness_entry_dir(dir, function, name, input_size),
+        meta,
+        output,
+    )
+}
+
+fn write_entry(entry_dir: &Path, meta: Value, output: &GenOutput) -> io::Result<()> {
+    fs::create_dir_all(entry_dir)?;
+
+    fs::write(entry_dir.join("body.clar"), &output.body)?;
+    let setup_path = entry_dir.join("setup.clar");
+    match &output.setup {
+        Some(setup) => fs::write(&setup_path, setup)?,
+        None if setup_path.exists() => fs::remove_file(&setup_path)?,
+        None => {}
+    }
+
+    let meta = serde_json::to_string_pretty(&meta).expect("corpus metadata is valid JSON");
+    fs::write(entry_dir.join("meta.json"), meta + "\n")
+}
+
+fn read_meta(path: &Path) -> Result<Value, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    serde_json::from_str(&contents).map_err(|e| Error::BadMeta(path.to_path_buf(), e.to_string()))
+}
+
+/// Reads the output `export` wrote for `function` with `scale` and `input_size`.
+pub fn load(
+    dir: &Path,
+    function: CostFunction,
+    scale: u16,
+    input_size: u64,
+) -> Result<GenOutput, Error> {
+    read_entry(
+        &entry_dir(dir, function, scale, input_size),
+        &[
+            ("cost_function", json!(function.to_string())),
+            ("scale", json!(scale)),
+            ("input_size", json!(input_size)),
+        ],
+    )
+}
+
+/// Reads the contract `export_harness` wrote for `function` under `name` and
+/// `input_size`.
+pub fn load_harness(
+    dir: &Path,
+    function: CostFunction,
+    name: &str,
+    input_size: u64,
+) -> Result<GenOutput, Error> {
+    read_entry(
+        &harness_entry_dir(dir, function, name, input_size),
+        &[
+            ("cost_function", json!(function.to_string())),
+            ("harness", json!(name)),
+            ("input_size", json!(input_size)),
+        ],
+    )
+}
+
+/// Reads the entry in `entry_dir`, whose `meta.json` must have the `expected` keys.
+fn read_entry(entry_dir: &Path, expected: &[(&str, Value)]) -> Result<GenOutput, Error> {
+    let meta_path = entry_dir.join("meta.json");
+    if !meta_path.is_file() {
+        return Err(Error::Missing(entry_dir.to_path_buf()));
+    }
+    let meta = read_meta(&meta_path)?;
+    let bad_meta = |msg: &str| Error::BadMeta(meta_path.clone(), msg.to_string());
+    if expected.iter().any(|(key, value)| meta[*key] != *value) {
+        return Err(bad_meta("does not match the entry's directory"));
+    }
+    let claimed_input_size = meta["claimed_input_size"]
+        .as_u64()
+        .ok_or_else(|| bad_meta("missing claimed_input_size"))?;
+
+    let read = |name: &str| {
+        let path = entry_dir.join(name);
+        fs::read_to_string(&path).map_err(|e| Error::Io(path, e))
+    };
+    let setup = match entry_dir.join("setup.clar").is_file() {
+        true => Some(read("setup.clar")?),
+        false => None,
+    };
+    Ok(GenOutput::new(
+        setup,
+        read("body.clar")?,
+        claimed_input_size,
+    ))
+}
+
+/// The seed every entry in the corpus was generated with, or None if it is empty.
+pub fn seed(dir: &Path) -> Result<Option<u64>, Error> {
+    let mut seed = None;
+    for meta_path in meta_paths(dir)? {
+        let entry_seed = read_meta(&meta_path)?["seed"]
+            .as_u64()
+            .ok_or_else(|| Error::BadMeta(meta_path.clone(), "missing seed".to_string()))?;
+        match seed {
+            Some(seed) if seed != entry_seed => return Err(Error::MixedSeeds(dir.to_path_buf())),
+            _ => seed = Some(entry_seed),
+        }
+    }
+    Ok(seed)
+}
+
+/// Every `meta.json` under `dir`.
+fn meta_paths(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).map_err(|e| Error::Io(dir.clone(), e))? {
+            let path = entry.map_err(|e| Error::Io(dir.clone(), e))?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.file_name().and_then(|n| n.to_str()) == Some("meta.json") {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
@@ -0,0 +1,306 @@
+//! Observes which inputs the Clarity VM charges a cost function with.
+//!
+//! The VM only accepts a `LimitedCostTracker`, which is a concrete type rather than
+//! a `CostTracker` trait object, so a recording tracker can't be passed in its place
+//! and the individual `(function, input)` charges are never visible. The tracker
+//! evaluates the `costs` boot contract to price every charge, and only exposes its
+//! running total. A probe installs its own `costs` contract, in which the target
+//! cost function returns a tuple encoding its input and every other cost function
+//! returns zero, so the total accumulates the number of charges to the target, the
+//! sum of their inputs and the sum of their squares.
+//!
+//! Probing every cost function in turn, one run each, records all the charges a
+//! piece of code makes, which shows how much of a benchmark's time is spent on cost
+//! functions other than the one it is meant to measure. The contamination is only
+//! reported: `fit` doesn't subtract it, since that would need the contaminants'
+//! own fitted costs at the inputs they were charged with, of which a probe only
+//! keeps the mean.
+
+use blockstack_lib::chainstate::stacks::boot::boot_code_id;
+use blockstack_lib::vm::contexts::OwnedEnvironment;
+use blockstack_lib::vm::costs::cost_functions::ClarityCostFunction;
+use blockstack_lib::vm::costs::{ExecutionCost, LimitedCostTracker};
+use blockstack_lib::vm::database::{
+    ClarityBackingStore, ClarityDatabase, NULL_BURN_STATE_DB, NULL_HEADER_DB,
+};
+use std::env;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Environment variable that switches the bench target into checking input sizes
+/// instead of timing, see `benches/bench.rs`.
+pub const CHECK_SIZES_ENV_VAR: &str = "COST_BENCH_CHECK_SIZES";
+
+/// Stands in for the `cost-voting` boot contract, which `LimitedCostTracker::new`
+/// reads to find cost function overrides. With no confirmed proposals, every cost
+/// function is looked up in the `costs` contract.
+const COST_VOTING_STUB: &str = "(define-data-var confirmed-proposal-count uint u0)";
+
+/// Environment variable holding the path of a charge report. When set, the bench
+/// target records every cost function each benchmark charges instead of timing.
+pub const RECORD_CHARGES_ENV_VAR: &str = "COST_BENCH_RECORD_CHARGES";
+
+/// Criterion directory of probe runs, relative to the package root. Benchmarks that
+/// aren't probed are still timed briefly, so their results go here rather than over
+/// real ones.
+pub const PROBE_CRITERION_DIR: &str = "target/criterion-probe";
+
+/// What the bench target does with each benchmark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeMode {
+    /// time it
+    Off,
+    /// check its input size, see `check_input_size`
+    CheckSizes,
+    /// record its charges, and write a `ChargeRecord` report to the path
+    RecordCharges(PathBuf),
+}
+
+impl ProbeMode {
+    pub fn from_env() -> ProbeMode {
+        if let Some(path) = env::var_os(RECORD_CHARGES_ENV_VAR) {
+            return ProbeMode::RecordCharges(PathBuf::from(path));
+        }
+        match env::var(CHECK_SIZES_ENV_VAR) {
+            Ok(v) if v != "0" && !v.is_empty() => ProbeMode::CheckSizes,
+            _ => ProbeMode::Off,
+        }
+    }
+}
+
+/// A `costs` contract that records charges to `target`: `read_count` counts them,
+/// `read_length` sums their inputs and `write_length` sums the squared inputs.
+pub fn probe_contract(target: ClarityCostFunction) -> String {
+    let mut contract = String::new();
+    for function in ClarityCostFunction::ALL.iter() {
+        let cost = if *function == target {
+            "{runtime: u0, write_length: (* n n), write_count: u0, read_count: u1, read_length: n}"
+        } else {
+            "{runtime: u0, write_length: u0, write_count: u0, read_count: u0, read_length: u0}"
+        };
+        contract.push_str(&format!(
+            "(define-read-only ({} (n uint)) {})\n",
+            function.get_name(),
+            cost
+        ));
+    }
+    contract
+}
+
+/// Deploys the probe `costs` contract for `target`, and a stub `cost-voting`
+/// contract, to the testnet boot addresses in `store`.
+pub fn install_probe_contracts(store: &mut dyn ClarityBackingStore, target: ClarityCostFunction) {
+    install_costs_contracts(store, &probe_contract(target))
+        .expect("failed to install probe costs contract");
+}
+
+/// Deploys `costs` as the `costs` contract, and a stub `cost-voting` contract, to
+/// the testnet boot addresses in `store`.
+pub fn install_costs_contracts(
+    store: &mut dyn ClarityBackingStore,
+    costs: &str,
+) -> Result<(), String> {
+    let mut owned_env = OwnedEnvironment::new(ClarityDatabase::new(
+        store,
+        &NULL_HEADER_DB,
+        &NULL_BURN_STATE_DB,
+    ));
+    owned_env
+        .initialize_contract(boot_code_id("cost-voting", false), COST_VOTING_STUB)
+        .expect("failed to install cost-voting stub");
+    owned_env
+        .initialize_contract(boot_code_id("costs", false), costs)
+        .map(|_| ())
+        .map_err(|e| format!("failed to install costs contract: {}", e))
+}
+
+/// A cost tracker priced by the `costs` contract in `clarity_db`, with no limit.
+pub fn unlimited_tracker(clarity_db: &mut ClarityDatabase) -> Result<LimitedCostTracker, String> {
+    clarity_db.begin();
+    let tracker = LimitedCostTracker::new(false, ExecutionCost::max_value(), clarity_db)
+        .map_err(|e| format!("failed to load costs contract: {:?}", e));
+    clarity_db.roll_back();
+    tracker
+}
+
+/// Charges to the probed cost function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Charges {
+    pub count: u64,
+    pub input_sum: u64,
+    pub input_square_sum: u64,
+}
+
+impl Charges {
+    /// Charges accumulated in a probe tracker's total.
+    pub fn from_total(total: &ExecutionCost) -> Charges {
+        Charges {
+            count: total.read_count,
+            input_sum: total.read_length,
+            input_square_sum: total.write_length,
+        }
+    }
+
+    /// Charges made between two totals of the same probe tracker.
+    pub fn between(before: &ExecutionCost, after: &ExecutionCost) -> Charges {
+        let (before, after) = (Charges::from_total(before), Charges::from_total(after));
+        Charges {
+            count: after.count - before.count,
+            input_sum: after.input_sum - before.input_sum,
+            input_square_sum: after.input_square_sum - before.input_square_sum,
+        }
+    }
+
+    pub fn mean_input(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count => Some(self.input_sum as f64 / count as f64),
+        }
+    }
+
+    /// True when every charge had the same input. By Cauchy-Schwarz,
+    /// `count * sum(x^2) == sum(x)^2` only when all `x` are equal.
+    pub fn uniform(&self) -> bool {
+        (self.count as u128) * (self.input_square_sum as u128)
+            == (self.input_sum as u128) * (self.input_sum as u128)
+    }
+}
+
+/// Outcome of comparing a benchmark's recorded input size to the VM's charges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeCheck {
+    /// every charge used the claimed input size
+    Agrees,
+    /// the generated code never charged the target cost function
+    NotCharged,
+    Disagrees {
+        claimed: u64,
+        charges: Charges,
+    },
+}
+
+impl fmt::Display for SizeCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SizeCheck::Agrees => write!(f, "input size agrees with the VM"),
+            SizeCheck::NotCharged => write!(f, "the VM never charged this cost function"),
+            SizeCheck::Disagrees { claimed, charges } => {
+                write!(
+                    f,
+                    "input size {} disagrees with the VM: {} charges with mean input {:.1}",
+                    claimed,
+                    charges.count,
+                    charges.mean_input().unwrap_or(0.0)
+                )?;
+                if !charges.uniform() {
+                    write!(f, ", and not all charges had the same input")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn check_input_size(claimed: u64, charges: &Charges) -> SizeCheck {
+    if charges.count == 0 {
+        SizeCheck::NotCharged
+    } else if charges.uniform() && charges.input_sum == claimed * charges.count {
+        SizeCheck::Agrees
+    } else {
+        SizeCheck::Disagrees {
+            claimed,
+            charges: *charges,
+        }
+    }
+}
+
+/// Every charge a benchmark's code made, by cost function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargeRecord {
+    /// name of the benchmark, which is its intended cost function
+    pub benchmark: String,
+    pub target: ClarityCostFunction,
+    pub input_size: u64,
+    /// charges to each cost function that was charged at all, in `ClarityCostFunction::ALL` order
+    pub charges: Vec<(ClarityCostFunction, Charges)>,
+}
+
+impl ChargeRecord {
+    /// Records charges by calling `probe` once for every cost function. `probe`
+    /// should run the benchmark's code once under the probe contracts for it.
+    pub fn collect<P>(target: ClarityCostFunction, input_size: u64, mut probe: P) -> ChargeRecord
+    where
+        P: FnMut(ClarityCostFunction) -> Charges,
+    {
+        let charges = ClarityCostFunction::ALL
+            .iter()
+            .map(|function| (*function, probe(*function)))
+            .filter(|(_, charges)| charges.count > 0)
+            .collect();
+        ChargeRecord {
+            benchmark: target.to_string(),
+            target,
+            input_size,
+            charges,
+        }
+    }
+
+    pub fn total_count(&self) -> u64 {
+        self.charges.iter().map(|(_, charges)| charges.count).sum()
+    }
+
+    pub fn target_charges(&self) -> Charges {
+        self.charges
+            .iter()
+            .find(|(function, _)| *function == self.target)
+            .map(|(_, charges)| *charges)
+            .unwrap_or_default()
+    }
+
+    /// Fraction of charged invocations that were of the intended cost function.
+    pub fn target_fraction(&self) -> f64 {
+        match self.total_count() {
+            0 => 0.0,
+            total => self.target_charges().count as f64 / total as f64,
+        }
+    }
+
+    /// Charges to other cost functions, most frequent first.
+    pub fn contaminants(&self) -> Vec<(ClarityCostFunction, Charges)> {
+        let mut contaminants: Vec<_> = self
+            .charges
+            .iter()
+            .filter(|(function, _)| *function != self.target)
+            .copied()
+            .collect();
+        contaminants.sort_by(|a, b| b.1.count.cmp(&a.1.count));
+        contaminants
+    }
+}
+
+/// Writes one row per benchmark, input size and charged cost function.
+pub fn write_charge_report<W: Write>(out: &mut W, records: &[ChargeRecord]) -> io::Result<()> {
+    writeln!(
+        out,
+        "benchmark,input_size,cost_function,count,mean_input,fraction,is_target"
+    )?;
+    for record in records {
+        let total = record.total_count();
+        for (function, charges) in &record.charges {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                record.benchmark,
+                record.input_size,
+                function,
+                charges.count,
+                charges.mean_input().unwrap_or(0.0),
+                charges.count as f64 / total as f64,
+                *function == record.target
+            )?;
+        }
+    }
+    Ok(())
+}
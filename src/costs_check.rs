@@ -0,0 +1,290 @@
+//! Checks an emitted costs contract before it is proposed: that it parses and
+//! type-checks, that it defines every cost function the node looks up with the
+//! signature the node expects, and that evaluating those functions gives the
+//! runtime costs of the fitted models they were generated from.
+
+use crate::proposal::{apply_matched_functions, ScaledCost, Scaling};
+use crate::regression::{Fit, Model};
+use blockstack_lib::clarity_vm::database::MemoryBackingStore;
+use blockstack_lib::vm::analysis::{type_check, ContractAnalysis};
+use blockstack_lib::vm::ast;
+use blockstack_lib::vm::contexts::OwnedEnvironment;
+use blockstack_lib::vm::costs::cost_functions::ClarityCostFunction;
+use blockstack_lib::vm::types::signatures::TypeSignature::UIntType;
+use blockstack_lib::vm::types::{
+    FunctionType, QualifiedContractIdentifier, TupleTypeSignature, TypeSignature,
+};
+use blockstack_lib::vm::{ClarityName, Value};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Input sizes every cost function is evaluated at. Starts at 1, since `log2 u0`
+/// is a runtime error, and goes high enough to catch overflows.
+pub const INPUT_GRID: [u64; 10] = [1, 2, 3, 8, 10, 64, 100, 1000, 4096, 1_000_000];
+
+/// Fields of the tuple every cost function returns.
+const COST_DIMENSIONS: [&str; 5] = [
+    "runtime",
+    "write_length",
+    "write_count",
+    "read_count",
+    "read_length",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckError {
+    Parse(String),
+    TypeCheck(String),
+    /// the contract could not be initialized in a Clarity environment
+    Initialize(String),
+    /// a cost function the node looks up is not defined as `define-read-only`
+    MissingFunction(String),
+    WrongSignature {
+        function: String,
+        found: String,
+    },
+    Evaluate {
+        function: String,
+        n: u64,
+        error: String,
+    },
+    /// the runtime returned by the contract isn't the scaled fitted model
+    RuntimeMismatch {
+        function: String,
+        n: u64,
+        expected: u64,
+        actual: u64,
+    },
+    /// the contract agrees with the scaled model, but is further from the
+    /// unrounded fit than truncating the constants can explain
+    RuntimeOutOfTolerance {
+        function: String,
+        n: u64,
+        fitted: f64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Parse(e) => write!(f, "parse error: {}", e),
+            CheckError::TypeCheck(e) => write!(f, "type check error: {}", e),
+            CheckError::Initialize(e) => write!(f, "failed to initialize contract: {}", e),
+            CheckError::MissingFunction(function) => {
+                write!(f, "{}: not defined as a read-only function", function)
+            }
+            CheckError::WrongSignature { function, found } => write!(
+                f,
+                "{}: expected (n uint) returning the cost tuple, found {}",
+                function, found
+            ),
+            CheckError::Evaluate { function, n, error } => {
+                write!(f, "{} at n={}: evaluation failed: {}", function, n, error)
+            }
+            CheckError::RuntimeMismatch {
+                function,
+                n,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} at n={}: runtime {} but the fitted model gives {}",
+                function, n, actual, expected
+            ),
+            CheckError::RuntimeOutOfTolerance {
+                function,
+                n,
+                fitted,
+                actual,
+            } => write!(
+                f,
+                "{} at n={}: runtime {} is too far from the unrounded fit {}",
+                function, n, actual, fitted
+            ),
+        }
+    }
+}
+
+/// Outcome of `check_costs_contract`.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub errors: Vec<CheckError>,
+    /// number of cost function calls compared against a fitted model
+    pub evaluated: usize,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// The cost tuple type, `{runtime: uint, write_length: uint, ...}`.
+fn cost_tuple_type() -> TypeSignature {
+    let fields = COST_DIMENSIONS
+        .iter()
+        .map(|name| (ClarityName::try_from(name.to_string()).unwrap(), UIntType))
+        .collect::<Vec<_>>();
+    TypeSignature::TupleType(TupleTypeSignature::try_from(fields).unwrap())
+}
+
+fn check_signature(function: &str, analysis: &ContractAnalysis) -> Option<CheckError> {
+    let function_type = match analysis.get_read_only_function_type(function) {
+        Some(function_type) => function_type,
+        None => return Some(CheckError::MissingFunction(function.to_string())),
+    };
+    match function_type {
+        FunctionType::Fixed(fixed)
+            if fixed.args.len() == 1
+                && fixed.args[0].signature == UIntType
+                && fixed.returns == cost_tuple_type() =>
+        {
+            None
+        }
+        _ => Some(CheckError::WrongSignature {
+            function: function.to_string(),
+            found: format!("{:?}", function_type),
+        }),
+    }
+}
+
+fn runtime_of(value: Value) -> Result<u64, String> {
+    let tuple = match value {
+        Value::Tuple(tuple) => tuple,
+        other => return Err(format!("returned {}, not a tuple", other)),
+    };
+    match tuple.get("runtime") {
+        Ok(Value::UInt(runtime)) => u64::try_from(*runtime).map_err(|e| e.to_string()),
+        Ok(other) => Err(format!("runtime is {}, not a uint", other)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `transform` of the model, with the integer `log2` that Clarity uses.
+fn integer_transform(model: Model, n: u64) -> f64 {
+    let log2 = (63 - n.leading_zeros()) as f64;
+    match model {
+        Model::Constant => 0.0,
+        Model::Linear => n as f64,
+        Model::LogN => log2,
+        Model::NLogN => n as f64 * log2,
+    }
+}
+
+/// Checks `source`, the costs contract emitted for `fits` with `scaling`.
+///
+/// Every `ClarityCostFunction` must be defined. Functions in `fits` that are defined
+/// in the contract are evaluated over `INPUT_GRID` and compared two ways: exactly
+/// against the scaled integer model, which catches emitting mistakes, and against
+/// the unrounded fit within the error that truncating the constants can introduce,
+/// which catches scaling mistakes.
+pub fn check_costs_contract(
+    source: &str,
+    fits: &BTreeMap<String, Fit>,
+    scaling: &Scaling,
+) -> CheckReport {
+    let mut report = CheckReport::default();
+    let contract_identifier = QualifiedContractIdentifier::local("costs").unwrap();
+
+    let mut contract_ast = match ast::build_ast(&contract_identifier, source, &mut ()) {
+        Ok(contract_ast) => contract_ast,
+        Err(error) => {
+            report
+                .errors
+                .push(CheckError::Parse(error.diagnostic.message));
+            return report;
+        }
+    };
+
+    let mut memory_backing_store = MemoryBackingStore::new();
+    let analysis = {
+        let mut analysis_db = memory_backing_store.as_analysis_db();
+        match type_check(
+            &contract_identifier,
+            &mut contract_ast.expressions,
+            &mut analysis_db,
+            false,
+        ) {
+            Ok(analysis) => analysis,
+            Err(error) => {
+                report
+                    .errors
+                    .push(CheckError::TypeCheck(error.diagnostic.message));
+                return report;
+            }
+        }
+    };
+
+    for function in ClarityCostFunction::ALL.iter() {
+        if let Some(error) = check_signature(&function.get_name(), &analysis) {
+            report.errors.push(error);
+        }
+    }
+
+    let mut owned_env = OwnedEnvironment::new(memory_backing_store.as_clarity_db());
+    if let Err(error) = owned_env.initialize_contract(contract_identifier.clone(), source) {
+        report
+            .errors
+            .push(CheckError::Initialize(error.to_string()));
+        return report;
+    }
+
+    for (function, fit) in apply_matched_functions(fits, scaling).iter() {
+        if analysis.get_read_only_function_type(function).is_none() {
+            continue;
+        }
+        let cost = ScaledCost::new(function, fit, scaling);
+        let factor = scaling.factor(function);
+
+        for n in INPUT_GRID.iter().copied() {
+            let program = format!("({} u{})", function, n);
+            let actual = match owned_env
+                .eval_read_only(&contract_identifier, &program)
+                .map_err(|e| e.to_string())
+                .and_then(|(value, _, _)| runtime_of(value))
+            {
+                Ok(actual) => actual,
+                Err(error) => {
+                    report.errors.push(CheckError::Evaluate {
+                        function: function.clone(),
+                        n,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            report.evaluated += 1;
+
+            let expected = cost.evaluate(n);
+            if actual != expected {
+                report.errors.push(CheckError::RuntimeMismatch {
+                    function: function.clone(),
+                    n,
+                    expected,
+                    actual,
+                });
+                continue;
+            }
+
+            // truncating or raising a and b to 1 moves the cost by less than one
+            // unit of each
+            let x = integer_transform(cost.model, n);
+            let fitted = match cost.model {
+                Model::Constant => fit.b * factor,
+                _ => fit.a * factor * x + fit.b * factor,
+            };
+            if (actual as f64 - fitted).abs() > x + 1.0 {
+                report.errors.push(CheckError::RuntimeOutOfTolerance {
+                    function: function.clone(),
+                    n,
+                    fitted,
+                    actual,
+                });
+            }
+        }
+    }
+
+    report
+}
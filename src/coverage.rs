@@ -0,0 +1,283 @@
+//! Reports how well the benchmarks cover the cost functions: for every
+//! `ClarityCostFunction` and `AnalysisCostFunction`, whether it has a generator
+//! and a registered benchmark, which input sizes were measured and when, and
+//! which model was fitted to it.
+
+use crate::generators::{try_gen, NoGenerator};
+use crate::registry::{spec_for, BenchSpec, CostFunction, InputSizes, SCALE};
+use crate::regression::{result_dirs, Error, Fit};
+use blockstack_lib::vm::costs::cost_functions::{AnalysisCostFunction, ClarityCostFunction};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Every analysis pass. `gen_analysis_pass` matches on all of them, so a new
+/// pass fails to compile there before it can be missed here.
+const ANALYSIS_COST_FUNCTIONS: [AnalysisCostFunction; 4] = [
+    AnalysisCostFunction::ReadOnly,
+    AnalysisCostFunction::TypeChecker,
+    AnalysisCostFunction::TraitChecker,
+    AnalysisCostFunction::ArithmeticOnlyChecker,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorStatus {
+    /// `try_gen` or `gen_analysis_pass` produces code for it
+    Generated,
+    Missing(NoGenerator),
+}
+
+impl fmt::Display for GeneratorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorStatus::Generated => write!(f, "yes"),
+            GeneratorStatus::Missing(reason) => write!(f, "no, {}", reason),
+        }
+    }
+}
+
+/// Results of one cost function in a criterion directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub input_sizes: Vec<u64>,
+    /// modification time of the newest `estimates.json`
+    pub last_measured: SystemTime,
+}
+
+/// Something that keeps a cost function's constants from being derived from
+/// its benchmark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Gap {
+    /// the VM never charges it, so there is nothing to measure
+    NotCharged,
+    /// it is missing from `registry::REGISTRY`, so `cargo bench` never runs it
+    NotRegistered,
+    NotMeasured,
+    /// registered input sizes with no results
+    MissingSizes(Vec<u64>),
+    NotFitted,
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gap::NotCharged => write!(f, "never charged"),
+            Gap::NotRegistered => write!(f, "not registered"),
+            Gap::NotMeasured => write!(f, "not measured"),
+            Gap::MissingSizes(sizes) => write!(f, "missing sizes {}", format_sizes(sizes)),
+            Gap::NotFitted => write!(f, "not fitted"),
+        }
+    }
+}
+
+/// Coverage of one cost function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageRow {
+    pub function: CostFunction,
+    pub generator: GeneratorStatus,
+    pub spec: Option<&'static BenchSpec>,
+    /// None if there were no criterion results to look in, or none for this function
+    pub measurement: Option<Measurement>,
+    pub fit: Option<Fit>,
+    /// whether `fit` was looked up, i.e. fits were given to `coverage`
+    fits_known: bool,
+}
+
+impl CoverageRow {
+    pub fn gaps(&self) -> Vec<Gap> {
+        if self.generator == GeneratorStatus::Missing(NoGenerator::NotCharged) {
+            return vec![Gap::NotCharged];
+        }
+        let spec = match self.spec {
+            Some(spec) => spec,
+            None => return vec![Gap::NotRegistered],
+        };
+
+        let mut gaps = vec![];
+        match &self.measurement {
+            None => gaps.push(Gap::NotMeasured),
+            Some(measurement) => {
+                let missing: Vec<u64> = spec
+                    .input_sizes()
+                    .into_iter()
+                    .filter(|size| !measurement.input_sizes.contains(size))
+                    .collect();
+                if !missing.is_empty() {
+                    gaps.push(Gap::MissingSizes(missing));
+                }
+            }
+        }
+        if self.fits_known && self.fit.is_none() {
+            gaps.push(Gap::NotFitted);
+        }
+        gaps
+    }
+}
+
+fn generator_status(function: CostFunction) -> GeneratorStatus {
+    match function {
+        CostFunction::Clarity(function) => {
+            let input_size = spec_for(CostFunction::Clarity(function))
+                .map(|spec| spec.input_sizes()[0])
+                .unwrap_or(1);
+            match try_gen(function, SCALE, input_size, 0) {
+                Ok(_) => GeneratorStatus::Generated,
+                Err(reason) => GeneratorStatus::Missing(reason),
+            }
+        }
+        CostFunction::Analysis(_) => GeneratorStatus::Generated,
+    }
+}
+
+/// Reads which input sizes of each cost function have results under
+/// `criterion_dir`, and when they were last measured.
+pub fn load_measurements(criterion_dir: &Path) -> Result<BTreeMap<String, Measurement>, Error> {
+    let mut measurements = BTreeMap::new();
+
+    for (function_name, size, base_dir) in result_dirs(criterion_dir)? {
+        let estimates_path = base_dir.join("estimates.json");
+        let modified = match fs::metadata(&estimates_path) {
+            Ok(metadata) => metadata
+                .modified()
+                .map_err(|e| Error::Io(estimates_path.clone(), e))?,
+            Err(_) => continue,
+        };
+        let measurement = measurements
+            .entry(function_name)
+            .or_insert_with(|| Measurement {
+                input_sizes: vec![],
+                last_measured: modified,
+            });
+        measurement.input_sizes.push(size);
+        measurement.last_measured = measurement.last_measured.max(modified);
+    }
+
+    for measurement in measurements.values_mut() {
+        measurement.input_sizes.sort_unstable();
+    }
+    Ok(measurements)
+}
+
+/// One row for every `ClarityCostFunction`, in `ClarityCostFunction::ALL` order,
+/// followed by every `AnalysisCostFunction`. Without `measurements` nothing is
+/// reported as measured, and without `fits` nothing is reported as fitted.
+pub fn coverage(
+    measurements: Option<&BTreeMap<String, Measurement>>,
+    fits: Option<&BTreeMap<String, Fit>>,
+) -> Vec<CoverageRow> {
+    ClarityCostFunction::ALL
+        .iter()
+        .map(|function| CostFunction::Clarity(*function))
+        .chain(
+            ANALYSIS_COST_FUNCTIONS
+                .iter()
+                .map(|function| CostFunction::Analysis(*function)),
+        )
+        .map(|function| {
+            let name = function.to_string();
+            CoverageRow {
+                function,
+                generator: generator_status(function),
+                spec: spec_for(function),
+                measurement: measurements.and_then(|m| m.get(&name).cloned()),
+                fit: fits.and_then(|f| f.get(&name).copied()),
+                fits_known: fits.is_some(),
+            }
+        })
+        .collect()
+}
+
+fn format_sizes(sizes: &[u64]) -> String {
+    sizes
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `time` as a UTC date, e.g. `2021-05-04`.
+fn format_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    // civil date from days since the epoch, as in Howard Hinnant's `civil_from_days`
+    let z = (secs / 86400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Writes the coverage rows as a markdown table, followed by a list of every
+/// cost function with a gap.
+pub fn write_coverage<W: Write>(out: &mut W, rows: &[CoverageRow]) -> io::Result<()> {
+    writeln!(
+        out,
+        "| Function | Generator | Benchmark | Input sizes | Last measured | Fitted model | Gaps |"
+    )?;
+    writeln!(
+        out,
+        "| ----------- | ----------- | ----------- | ----------- | ----------- | ----------- | ----------- |"
+    )?;
+    for row in rows {
+        let benchmark = match row.spec {
+            Some(spec) => format!("{} (scale {})", spec.category.name(), spec.scale),
+            None => "-".to_string(),
+        };
+        let input_sizes = match row.spec.map(|spec| spec.input_sizes) {
+            Some(InputSizes::Constant) => "constant".to_string(),
+            Some(InputSizes::Sizes(sizes)) => format_sizes(sizes),
+            None => "-".to_string(),
+        };
+        let last_measured = match &row.measurement {
+            Some(measurement) => format_date(measurement.last_measured),
+            None => "-".to_string(),
+        };
+        let fit = match row.fit {
+            Some(fit) => format!("{} a={:.3} b={:.3}", fit.model, fit.a, fit.b),
+            None => "-".to_string(),
+        };
+        let gaps = row
+            .gaps()
+            .iter()
+            .map(|gap| format!("**{}**", gap))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            row.function, row.generator, benchmark, input_sizes, last_measured, fit, gaps
+        )?;
+    }
+
+    let gaps: Vec<_> = rows.iter().filter(|row| !row.gaps().is_empty()).collect();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} of {} cost functions have gaps:",
+        gaps.len(),
+        rows.len()
+    )?;
+    writeln!(out)?;
+    for row in gaps {
+        let reasons = row
+            .gaps()
+            .iter()
+            .map(Gap::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(out, "- {}: {}", row.function, reasons)?;
+    }
+    Ok(())
+}
@@ -7,7 +7,8 @@ use blockstack_lib::vm::costs::cost_functions::{AnalysisCostFunction, ClarityCos
 use blockstack_lib::vm::database::ClaritySerializable;
 use rand::distributions::Uniform;
 use rand::prelude::SliceRandom;
-use rand::{Rng, RngCore};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
 
 use blockstack_lib::address::AddressHashMode;
 use blockstack_lib::chainstate::stacks::{StacksPublicKey, C32_ADDRESS_VERSION_TESTNET_SINGLESIG};
@@ -23,9 +24,16 @@ use blockstack_lib::vm::{execute, ClarityName, Value};
 use lazy_static::lazy_static;
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
+use std::fmt;
+
+/// Seed for the tuple field names shared by every generator. These names are
+/// baked into sized values and type signatures, so they are fixed independently
+/// of the per-run seed.
+const TUPLE_NAMES_SEED: u64 = 0;
 
 lazy_static! {
-    pub static ref TUPLE_NAMES: Vec<String> = create_tuple_names(16);
+    pub static ref TUPLE_NAMES: Vec<String> =
+        create_tuple_names(16, &mut StdRng::seed_from_u64(TUPLE_NAMES_SEED));
 }
 
 fn string_to_value(s: String) -> Value {
@@ -54,10 +62,29 @@ impl GenOutput {
     }
 }
 
-fn create_tuple_names(len: u16) -> Vec<String> {
+/// Why `try_gen` could not produce code for a cost function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoGenerator {
+    /// The cost is charged outside of evaluating contract code, so the function is
+    /// benchmarked by a dedicated harness in `benches/bench.rs`.
+    CustomHarness(&'static str),
+    /// The VM never charges this cost function, so there is nothing to measure.
+    NotCharged,
+}
+
+impl fmt::Display for NoGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoGenerator::CustomHarness(reason) => write!(f, "needs a custom harness ({})", reason),
+            NoGenerator::NotCharged => write!(f, "not charged by the VM"),
+        }
+    }
+}
+
+fn create_tuple_names(len: u16, rng: &mut StdRng) -> Vec<String> {
     let mut names = Vec::new();
     for _ in 0..len {
-        names.push(helper_generate_rand_char_string(5));
+        names.push(helper_generate_rand_char_string(5, rng));
     }
     names
 }
@@ -123,8 +150,7 @@ pub fn make_clarity_type_for_sized_value(input_size: u64) -> String {
 }
 
 // make contract for ast parse
-fn make_clarity_statement_for_sized_contract(mult: u64) -> (String, u64) {
-    let mut rng = rand::thread_rng();
+fn make_clarity_statement_for_sized_contract(mult: u64, rng: &mut StdRng) -> (String, u64) {
     let contract = (0..mult)
         .map(|_x| {
             format!(
@@ -138,7 +164,7 @@ fn make_clarity_statement_for_sized_contract(mult: u64) -> (String, u64) {
     (contract.clone(), contract.len() as u64)
 }
 
-fn make_sized_contract(input_size: u64) -> (String, u64) {
+pub fn make_sized_contract(input_size: u64, rng: &mut StdRng) -> (String, u64) {
     match input_size {
         1 => ("1".to_string(), 1),
         2 => ("u8".to_string(), 2),
@@ -146,38 +172,28 @@ fn make_sized_contract(input_size: u64) -> (String, u64) {
         n => {
             // assuming n is a multiple of 16
             let mult = n / 16;
-            let contract = make_clarity_statement_for_sized_contract(mult);
+            let contract = make_clarity_statement_for_sized_contract(mult, rng);
             (contract.0, contract.1)
         }
     }
 }
 
-pub fn make_sized_contracts_map(input_sizes: Vec<u64>) -> HashMap<u64, String> {
-    let mut ret_map = HashMap::new();
-    for i in input_sizes {
-        let val = make_sized_contract(i);
-        ret_map.insert(val.1, val.0);
-    }
-    ret_map
-}
-
 // make tuple type sigs for AnalysisCheckTupleGet
-fn make_tuple_sig(input_size: u64) -> TupleTypeSignature {
-    let mut rng = rand::thread_rng();
+fn make_tuple_sig(input_size: u64, rng: &mut StdRng) -> TupleTypeSignature {
     let type_list = [IntType, UIntType, BoolType, PrincipalType];
     let mut type_map = Vec::new();
     for i in 0..input_size {
         let name = ClarityName::try_from(format!("id{}", i)).unwrap();
-        let type_sig = type_list.choose(&mut rng).unwrap().clone();
+        let type_sig = type_list.choose(rng).unwrap().clone();
         type_map.push((name, type_sig));
     }
     TupleTypeSignature::try_from(type_map).unwrap()
 }
 
-pub fn make_sized_tuple_sigs_map(input_sizes: Vec<u64>) -> HashMap<u64, TupleTypeSignature> {
+pub fn make_sized_tuple_sigs_map(input_sizes: Vec<u64>, rng: &mut StdRng) -> HashMap<u64, TupleTypeSignature> {
     let mut ret_map = HashMap::new();
     for i in input_sizes {
-        let val = make_tuple_sig(i);
+        let val = make_tuple_sig(i, rng);
         ret_map.insert(i, val);
     }
     ret_map
@@ -208,8 +224,7 @@ fn helper_make_clarity_type_for_sized_type_sig(input_size: u64) -> String {
     }
 }
 
-fn helper_make_clarity_value_for_sized_type_sig(input_size: u64) -> String {
-    let mut rng = rand::thread_rng();
+fn helper_make_clarity_value_for_sized_type_sig(input_size: u64, rng: &mut StdRng) -> String {
     match input_size {
         1 => format!("{}", rng.gen::<bool>()),
         2 => format!("(some {})", rng.gen_bool(0.5)),
@@ -236,8 +251,7 @@ fn helper_make_clarity_value_for_sized_type_sig(input_size: u64) -> String {
     }
 }
 
-pub fn helper_make_value_for_sized_type_sig(input_size: u64) -> Value {
-    let mut rng = rand::thread_rng();
+pub fn helper_make_value_for_sized_type_sig(input_size: u64, rng: &mut StdRng) -> Value {
     match input_size {
         1 => Value::Bool(rng.gen()),
         2 => Value::Optional(OptionalData {
@@ -271,8 +285,7 @@ pub fn helper_make_value_for_sized_type_sig(input_size: u64) -> Value {
 }
 
 // make sized type sigs for AnalysisTypeCheck
-fn make_sized_type_sig(input_size: u64) -> TypeSignature {
-    let mut rng = rand::thread_rng();
+fn make_sized_type_sig(input_size: u64, rng: &mut StdRng) -> TypeSignature {
     match input_size {
         1 => TypeSignature::BoolType,
         2 => TypeSignature::OptionalType(Box::new(TypeSignature::BoolType)),
@@ -291,7 +304,7 @@ fn make_sized_type_sig(input_size: u64) -> TypeSignature {
                     "id"
                 };
                 let name = ClarityName::try_from(format!("{}{}", id_name, i)).unwrap();
-                let type_sig = type_list.choose(&mut rng).unwrap().clone();
+                let type_sig = type_list.choose(rng).unwrap().clone();
                 type_map.push((name, type_sig));
             }
             TupleType(TupleTypeSignature::try_from(type_map).unwrap())
@@ -299,17 +312,16 @@ fn make_sized_type_sig(input_size: u64) -> TypeSignature {
     }
 }
 
-pub fn make_sized_type_sig_map(input_sizes: Vec<u64>) -> HashMap<u64, TypeSignature> {
+pub fn make_sized_type_sig_map(input_sizes: Vec<u64>, rng: &mut StdRng) -> HashMap<u64, TypeSignature> {
     let mut ret_map = HashMap::new();
     for i in input_sizes {
-        let val = make_sized_type_sig(i);
+        let val = make_sized_type_sig(i, rng);
         ret_map.insert(i, val);
     }
     ret_map
 }
 
-pub fn helper_make_sized_clarity_value(input_size: u64) -> String {
-    let mut rng = rand::thread_rng();
+pub fn helper_make_sized_clarity_value(input_size: u64, rng: &mut StdRng) -> String {
 
     match input_size {
         1 => "true".to_string(),
@@ -319,7 +331,7 @@ pub fn helper_make_sized_clarity_value(input_size: u64) -> String {
             let mut val = String::new();
             let mult = n / 8;
             for _ in 0..mult {
-                let name = helper_generate_rand_char_string(5);
+                let name = helper_generate_rand_char_string(5, rng);
                 val.push_str(&*format!("({} {}) ", name, rng.gen::<u16>()));
             }
             format!("(tuple {}) ", val).to_string()
@@ -333,9 +345,9 @@ pub fn gen_arithmetic(
     function_name: &'static str,
     scale: u16,
     input_size: u64,
+    rng: &mut StdRng,
 ) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     let s = match function_name {
         "/" => 1,
@@ -355,9 +367,8 @@ pub fn gen_arithmetic(
 
 /// cost_function: Pow
 /// input_size: double arg function
-fn gen_pow(scale: u16) -> GenOutput {
+fn gen_pow(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     for _ in 0..scale {
         let n1: u16 = rng.gen();
@@ -370,9 +381,8 @@ fn gen_pow(scale: u16) -> GenOutput {
 
 /// cost_function: Le, Leq, Ge, Geq
 /// input_size: double arg function
-fn gen_cmp(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_cmp(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     for _ in 0..scale {
         let n1: u128 = rng.gen();
@@ -387,9 +397,8 @@ fn gen_cmp(function_name: &'static str, scale: u16) -> GenOutput {
 /// input_size: number of arguments
 /// input_size eq: sum of serialized_size of arguments. booleans are size 1, so input_size
 /// is fine here.
-fn gen_logic(function_name: &'static str, scale: u16, input_size: u64) -> GenOutput {
+fn gen_logic(function_name: &'static str, scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     for _ in 0..scale {
         let args = (0..input_size)
@@ -409,9 +418,8 @@ fn gen_logic(function_name: &'static str, scale: u16, input_size: u64) -> GenOut
 
 /// cost_function: Xor
 /// input_size: double arg function
-fn gen_xor(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_xor(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     for _ in 0..scale {
         let args = match rng.gen_range(0..=1) {
@@ -438,22 +446,20 @@ fn gen_xor(function_name: &'static str, scale: u16) -> GenOutput {
 }
 
 /// This function generates a random hex string of size n.
-fn helper_generate_rand_hex_string(n: usize) -> String {
+fn helper_generate_rand_hex_string(n: usize, rng: &mut StdRng) -> String {
     let hex_chars = [
         "a", "b", "c", "d", "e", "f", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
     ];
 
     let hex_range = Uniform::new_inclusive(0, 15);
-    rand::thread_rng()
-        .sample_iter(&hex_range)
+    rng.sample_iter(&hex_range)
         .take(n)
         .map(|x| hex_chars[x])
         .collect::<String>()
 }
 
 /// This function generates a random char string of size n.
-pub fn helper_generate_rand_char_string(n: usize) -> String {
-    let mut rng = rand::thread_rng();
+pub fn helper_generate_rand_char_string(n: usize, rng: &mut StdRng) -> String {
     (0..n)
         .map(|_| rng.gen_range(b'a'..b'z') as char)
         .collect::<String>()
@@ -463,9 +469,8 @@ pub fn helper_generate_rand_char_string(n: usize) -> String {
 ///
 /// cost_function: Hash160, Sha256, Sha512, Sha512t256, Keccak256
 /// input_size: single arg function
-fn gen_hash(function_name: &'static str, scale: u16, input_size: u64) -> GenOutput {
+fn gen_hash(function_name: &'static str, scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     let arg = match input_size {
         // size of (u)ints
@@ -482,7 +487,7 @@ fn gen_hash(function_name: &'static str, scale: u16, input_size: u64) -> GenOutp
                     format!("{}", x)
                 },
                 2 => {
-                    let buff = helper_gen_clarity_value("buff", 0, 128, None);
+                    let buff = helper_gen_clarity_value("buff", 0, 128, None, rng);
                     format!(r##"{}"##, buff.0)
                 },
                 _ => {
@@ -491,7 +496,7 @@ fn gen_hash(function_name: &'static str, scale: u16, input_size: u64) -> GenOutp
             }
         },
         _ => {
-            let buff = helper_gen_clarity_value("buff", 0, input_size, None);
+            let buff = helper_gen_clarity_value("buff", 0, input_size, None, rng);
             format!(r##"{}"##, buff.0)
         }
     };
@@ -511,15 +516,15 @@ fn gen_secp256k1(
     function_name: &'static str,
     scale: u16,
     verify: bool,
+    rng: &mut StdRng,
 ) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     for _ in 0..scale {
         let mut msg = [0u8; 32];
         rng.fill_bytes(&mut msg);
 
-        let privk = Secp256k1PrivateKey::new();
+        let privk = helper_gen_private_key(rng);
         let sig = privk.sign(&msg).unwrap();
         let secp256k1_sig = sig.to_secp256k1_recoverable().unwrap();
         let (rec_id, sig_bytes) = secp256k1_sig.serialize_compact();
@@ -549,9 +554,8 @@ fn gen_secp256k1(
 /// FUNGIBLE TOKEN GENERATOR FUNCTIONS
 /// ////////////////////////////////////
 
-fn helper_define_fungible_token_statement() -> (String, String) {
-    let mut rng = rand::thread_rng();
-    let token_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+fn helper_define_fungible_token_statement(rng: &mut StdRng) -> (String, String) {
+    let token_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
     let args = match rng.gen_range(0..=1) {
         0 => {
             // no supply arg
@@ -572,27 +576,41 @@ fn helper_define_fungible_token_statement() -> (String, String) {
 
 /// cost_function: CreateFt
 /// input_size: 0
-fn gen_create_ft(_function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_create_ft(_function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
 
     for _ in 0..scale {
-        let (statement, _) = helper_define_fungible_token_statement();
+        let (statement, _) = helper_define_fungible_token_statement(rng);
         body.push_str(&statement);
     }
 
     GenOutput::new(None, body, 1)
 }
 
-fn helper_create_principal_in_hex() -> String {
-    let privk = Secp256k1PrivateKey::new();
+/// Draws a private key from `rng` instead of the OS entropy source used by
+/// `Secp256k1PrivateKey::new()`, so keys are reproducible from the seed.
+fn helper_gen_private_key(rng: &mut StdRng) -> Secp256k1PrivateKey {
+    loop {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        // out-of-range scalars are astronomically rare, just draw again
+        if let Ok(mut privk) = Secp256k1PrivateKey::from_slice(&bytes) {
+            privk.set_compress_public(true);
+            return privk;
+        }
+    }
+}
+
+fn helper_create_principal_in_hex(rng: &mut StdRng) -> String {
+    let privk = helper_gen_private_key(rng);
     let pubk = Secp256k1PublicKey::from_private(&privk).to_hex();
 
     format!("0x{} ", pubk)
 }
 
 /// Creates a random principal to use in a clarity contract. The output includes the prefixing tick mark.
-fn helper_create_principal() -> String {
-    let privk = Secp256k1PrivateKey::new();
+fn helper_create_principal(rng: &mut StdRng) -> String {
+    let privk = helper_gen_private_key(rng);
     let addr = StacksAddress::from_public_keys(
         C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
         &AddressHashMode::SerializeP2PKH,
@@ -606,14 +624,13 @@ fn helper_create_principal() -> String {
 
 /// cost_function: FtMint
 /// input_size: 0
-fn gen_ft_mint(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_ft_mint(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
-    let (statement, token_name) = helper_define_fungible_token_statement();
+    let (statement, token_name) = helper_define_fungible_token_statement(rng);
 
     for _ in 0..scale {
         let amount: u128 = rng.gen_range(1..1000);
-        let principal_data = helper_create_principal();
+        let principal_data = helper_create_principal(rng);
         let args = format!("{} u{} {}", token_name, amount, principal_data);
         body.push_str(&*format!("({} {}) ", function_name, args));
     }
@@ -621,13 +638,12 @@ fn gen_ft_mint(function_name: &'static str, scale: u16) -> GenOutput {
     GenOutput::new(Some(statement), body, 1)
 }
 
-fn helper_create_ft_boilerplate(mint_amount: u16) -> (String, String, String) {
+fn helper_create_ft_boilerplate(mint_amount: u16, rng: &mut StdRng) -> (String, String, String) {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
-    let token_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+    let token_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
     body.push_str(&*format!("(define-fungible-token {}) ", token_name));
 
-    let principal_data = helper_create_principal();
+    let principal_data = helper_create_principal(rng);
     body.push_str(&*format!(
         "(ft-mint? {} u{} {}) ",
         token_name, mint_amount, principal_data
@@ -637,14 +653,13 @@ fn helper_create_ft_boilerplate(mint_amount: u16) -> (String, String, String) {
 
 /// cost_function: FtTransfer
 /// input_size: 0
-fn gen_ft_transfer(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_ft_transfer(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     let max_transfer = 100;
     let (token_name, sender_principal, template) =
-        helper_create_ft_boilerplate(scale * max_transfer);
+        helper_create_ft_boilerplate(scale * max_transfer, rng);
 
-    let recipient_principal = helper_create_principal();
+    let recipient_principal = helper_create_principal(rng);
     for _ in 0..scale {
         let transfer_amount = rng.gen_range(1..=max_transfer);
         let args = format!(
@@ -659,9 +674,9 @@ fn gen_ft_transfer(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: FtBalance
 /// input_size: 0
-fn gen_ft_balance(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_ft_balance(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let (token_name, principal_data, template) = helper_create_ft_boilerplate(100);
+    let (token_name, principal_data, template) = helper_create_ft_boilerplate(100, rng);
     let args = format!("{} {}", token_name, principal_data);
     for _ in 0..scale {
         body.push_str(&*format!("({} {}) ", function_name, args));
@@ -672,9 +687,9 @@ fn gen_ft_balance(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: FtSupply
 /// input_size: 0
-fn gen_ft_supply(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_ft_supply(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let (token_name, _, template) = helper_create_ft_boilerplate(100);
+    let (token_name, _, template) = helper_create_ft_boilerplate(100, rng);
     let args = format!("{}", token_name);
     for _ in 0..scale {
         body.push_str(&*format!("({} {}) ", function_name, args));
@@ -685,11 +700,10 @@ fn gen_ft_supply(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: FtBurn
 /// input_size: 0
-fn gen_ft_burn(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_ft_burn(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     let max_burn = 100;
-    let (token_name, principal_data, template) = helper_create_ft_boilerplate(scale * max_burn);
+    let (token_name, principal_data, template) = helper_create_ft_boilerplate(scale * max_burn, rng);
     for _ in 0..scale {
         let burn_amount = rng.gen_range(1..=max_burn);
         let args = format!("{} u{} {}", token_name, burn_amount, principal_data);
@@ -700,8 +714,7 @@ fn gen_ft_burn(function_name: &'static str, scale: u16) -> GenOutput {
 }
 
 // size of argument is in bytes
-fn helper_gen_clarity_list_size(approx_size: u64) -> String {
-    let mut rng = rand::thread_rng();
+fn helper_gen_clarity_list_size(approx_size: u64, rng: &mut StdRng) -> String {
 
     let uint_size = 17;
     let list_bytes = 5;
@@ -728,8 +741,8 @@ fn helper_gen_clarity_type(
     allow_bool_type: bool,
     only_sequence_types: bool,
     only_non_seqence_types: bool,
+    rng: &mut StdRng,
 ) -> (String, Option<u16>) {
-    let mut rng = rand::thread_rng();
     let type_no_len = ["int", "uint", "bool"];
     let type_with_len = ["buff", "string-ascii", "string-utf8"];
 
@@ -767,9 +780,9 @@ fn helper_gen_clarity_type(
 // Returns statement (that creates nft in clarity) and token_name
 fn helper_define_non_fungible_token_statement(
     input_size: u64,
+    rng: &mut StdRng,
 ) -> (String, String) {
-    let mut rng = rand::thread_rng();
-    let token_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+    let token_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
     let nft_type = helper_make_clarity_type_for_sized_type_sig(input_size);
 
     let statement = format!("(define-non-fungible-token {} {}) ", token_name, nft_type);
@@ -781,23 +794,23 @@ fn helper_gen_clarity_value(
     num: u16,
     value_len: u64,
     list_type: Option<&str>,
+    rng: &mut StdRng,
 ) -> (String, u64) {
-    let mut rng = rand::thread_rng();
     match value_type {
         "int" => (format!("{}", num), 17),
         "uint" => (format!("u{}", num), 17),
         "buff" => {
             let mut buff = "0x".to_string();
-            buff.push_str(&helper_generate_rand_hex_string(value_len as usize));
+            buff.push_str(&helper_generate_rand_hex_string(value_len as usize, rng));
             (buff.clone(), size_of_value(buff))
         }
         "string-ascii" => {
-            let ascii_string = helper_generate_rand_hex_string(value_len as usize);
+            let ascii_string = helper_generate_rand_hex_string(value_len as usize, rng);
             let val = format!(r##""{}""##, ascii_string);
             (val.clone(), size_of_value(val))
         }
         "string-utf8" => {
-            let utf8_string = helper_generate_rand_hex_string(value_len as usize);
+            let utf8_string = helper_generate_rand_hex_string(value_len as usize, rng);
             let val = format!(r##"u"{}""##, utf8_string);
             (val.clone(), size_of_value(val))
         }
@@ -809,7 +822,7 @@ fn helper_gen_clarity_value(
         "list" => {
             let list_type = list_type.unwrap();
             let args = (0..value_len)
-                .map(|_| helper_gen_clarity_value(&list_type, num, 0, None).0)
+                .map(|_| helper_gen_clarity_value(&list_type, num, 0, None, rng).0)
                 .collect::<Vec<String>>()
                 .join(" ");
 
@@ -822,33 +835,48 @@ fn helper_gen_clarity_value(
     }
 }
 
-fn helper_gen_random_clarity_value() -> (String, u64) {
-    let mut rng = rand::thread_rng();
+fn helper_gen_random_clarity_value(rng: &mut StdRng) -> (String, u64) {
     let num: u16 = rng.gen();
-    let (clarity_type, length) = helper_gen_clarity_type(true, false, false);
+    let (clarity_type, length) = helper_gen_clarity_type(true, false, false, rng);
     helper_gen_clarity_value(
         &clarity_type,
         num,
         length.map_or(0, |l| l.into()),
         None,
+        rng,
     )
 }
 
+/// cost_function: CreateNft
+/// input_size: size of asset type
+///     `asset_type.size()`
+fn gen_create_nft(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let mut body = String::new();
+    let asset_type_size = make_sized_type_sig(input_size, rng).size();
+
+    for _ in 0..scale {
+        let (statement, _) = helper_define_non_fungible_token_statement(input_size, rng);
+        body.push_str(&statement);
+    }
+
+    GenOutput::new(None, body, asset_type_size as u64)
+}
+
 /// cost_function: NftMint
 /// input_size: size of type signature of asset
 ///     `expected_asset_type.size()`
-fn gen_nft_mint(scale: u16, input_size: u64) -> GenOutput {
+fn gen_nft_mint(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     let (statement, token_name) =
-        helper_define_non_fungible_token_statement(input_size);
+        helper_define_non_fungible_token_statement(input_size, rng);
 
-    let nft_type = make_sized_type_sig(input_size);
+    let nft_type = make_sized_type_sig(input_size, rng);
     let nft_value_size = nft_type.size();
     assert!(nft_value_size <= u16::MAX as u32);
 
     for i in 0..scale {
-        let principal_data = helper_create_principal();
-        let nft_value = helper_make_value_for_sized_type_sig(input_size);
+        let principal_data = helper_create_principal(rng);
+        let nft_value = helper_make_value_for_sized_type_sig(input_size, rng);
         assert_eq!(nft_value_size, nft_value.size());
 
         let statement = format!(
@@ -864,19 +892,19 @@ fn gen_nft_mint(scale: u16, input_size: u64) -> GenOutput {
     GenOutput::new(Some(statement), body, nft_value_size as u64)
 }
 
-fn helper_create_nft_fn_boilerplate(input_size: u64) -> (String, String, String, String, u64) {
+fn helper_create_nft_fn_boilerplate(input_size: u64, rng: &mut StdRng) -> (String, String, String, String, u64) {
     let mut body = String::new();
     let (statement, token_name) =
-        helper_define_non_fungible_token_statement(input_size);
+        helper_define_non_fungible_token_statement(input_size, rng);
     body.push_str(&statement);
 
-    let nft_type = make_sized_type_sig(input_size);
+    let nft_type = make_sized_type_sig(input_size, rng);
     let nft_type_size = nft_type.size();
     assert!(nft_type_size <= u16::MAX as u32);
 
-    let nft_value = helper_make_value_for_sized_type_sig(input_size);
+    let nft_value = helper_make_value_for_sized_type_sig(input_size, rng);
     assert_eq!(nft_type_size, nft_value.size());
-    let mut owner_principal = helper_create_principal();
+    let mut owner_principal = helper_create_principal(rng);
     let mint_statement = format!(
         "(nft-mint? {} {} {}) ",
         token_name, nft_value, owner_principal
@@ -894,12 +922,12 @@ fn helper_create_nft_fn_boilerplate(input_size: u64) -> (String, String, String,
 /// cost_function: NftTransfer
 /// input_size: size of type signature of asset
 ///     `expected_asset_type.size()`
-fn gen_nft_transfer(function_name: &'static str, scale: u16, input_size: u64) -> GenOutput {
+fn gen_nft_transfer(function_name: &'static str, scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     let (mut setup, token_name, mut owner_principal, nft_value, nft_type_size) =
-        helper_create_nft_fn_boilerplate(input_size);
+        helper_create_nft_fn_boilerplate(input_size, rng);
     for _ in 0..scale {
-        let next_principal = helper_create_principal();
+        let next_principal = helper_create_principal(rng);
         let args = format!(
             "{} {} {} {}",
             token_name, nft_value, owner_principal, next_principal
@@ -916,12 +944,11 @@ fn gen_nft_transfer(function_name: &'static str, scale: u16, input_size: u64) ->
 /// cost_function: NftOwner
 /// input_size: size of type signature of asset
 ///     `expected_asset_type.size()`
-fn gen_nft_owner(function_name: &'static str, scale: u16, input_size: u64) -> GenOutput {
+fn gen_nft_owner(function_name: &'static str, scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     let (mut setup, token_name, _, nft_value, nft_type_size) =
-        helper_create_nft_fn_boilerplate(input_size);
-    let invalid_nft_value = helper_make_value_for_sized_type_sig(input_size);
+        helper_create_nft_fn_boilerplate(input_size, rng);
+    let invalid_nft_value = helper_make_value_for_sized_type_sig(input_size, rng);
     assert!(invalid_nft_value.size() <= u16::MAX as u32);
     assert_eq!(nft_type_size, invalid_nft_value.size() as u64);
     let invalid_nft_as_str = invalid_nft_value.to_string();
@@ -947,10 +974,10 @@ fn gen_nft_owner(function_name: &'static str, scale: u16, input_size: u64) -> Ge
 /// cost_function: NftBurn
 /// input_size: size of type signature of asset
 ///     `expected_asset_type.size()`
-fn gen_nft_burn(function_name: &'static str, scale: u16, input_size: u64) -> GenOutput {
+fn gen_nft_burn(function_name: &'static str, scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     let (mut setup, token_name, mut owner_principal, nft_value, nft_type_size) =
-        helper_create_nft_fn_boilerplate(input_size);
+        helper_create_nft_fn_boilerplate(input_size, rng);
     for _ in 0..scale {
         let args = format!("{} {} {}", token_name, nft_value, owner_principal);
         body.push_str(&*format!("({} {}) ", function_name, args));
@@ -964,8 +991,7 @@ fn gen_nft_burn(function_name: &'static str, scale: u16, input_size: u64) -> Gen
 /// TUPLE GENERATOR FUNCTIONS
 /// ////////////////////////////////////////
 
-fn helper_generate_tuple(input_size: u64) -> String {
-    let mut rng = rand::thread_rng();
+fn helper_generate_tuple(input_size: u64, rng: &mut StdRng) -> String {
     let tuple_vals = (0..input_size)
         .map(|i| format!("(id{} {})", i, rng.gen::<u32>()))
         .collect::<Vec<String>>()
@@ -976,11 +1002,10 @@ fn helper_generate_tuple(input_size: u64) -> String {
 
 /// cost_function: TupleGet
 /// input_size: length of tuple data == number of items
-fn gen_tuple_get(scale: u16, input_size: u64) -> GenOutput {
+fn gen_tuple_get(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
-    let tuple = helper_generate_tuple(input_size);
+    let tuple = helper_generate_tuple(input_size, rng);
 
     for _ in 0..scale {
         body.push_str(&*format!(
@@ -1051,13 +1076,12 @@ fn gen_tuple_cons(scale: u16, input_size: u64) -> GenOutput {
 /// OPTIONAL/ RESPONSE GENERATOR FUNCTIONS
 /// ////////////////////////////////////////
 
-fn helper_gen_random_optional_value(num: u16, only_some: bool) -> String {
-    let mut rng = rand::thread_rng();
+fn helper_gen_random_optional_value(num: u16, only_some: bool, rng: &mut StdRng) -> String {
     let p = if only_some { 0.0 } else { 0.5 };
     match rng.gen_bool(p) {
         true => "none".to_string(),
         false => {
-            let clarity_val = helper_gen_random_clarity_value();
+            let clarity_val = helper_gen_random_clarity_value(rng);
             format!("(some {})", clarity_val.0)
         }
     }
@@ -1065,19 +1089,18 @@ fn helper_gen_random_optional_value(num: u16, only_some: bool) -> String {
 
 /// cost_function: IsSome, IsNone
 /// input_size: single arg function
-fn gen_optional(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_optional(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for i in 0..scale {
-        let args = helper_gen_random_optional_value(i, false);
+        let args = helper_gen_random_optional_value(i, false, rng);
         body.push_str(&*format!("({} {}) ", function_name, args));
     }
 
     GenOutput::new(None, body, 1)
 }
 
-fn helper_gen_random_response_value(only_ok: bool, only_err: bool) -> String {
-    let mut rng = rand::thread_rng();
-    let clarity_val = helper_gen_random_clarity_value();
+fn helper_gen_random_response_value(only_ok: bool, only_err: bool, rng: &mut StdRng) -> String {
+    let clarity_val = helper_gen_random_clarity_value(rng);
     let p = if only_ok {
         0.0
     } else if only_err {
@@ -1097,10 +1120,10 @@ fn helper_gen_random_response_value(only_ok: bool, only_err: bool) -> String {
 
 /// cost_function: IsOkay, IsErr
 /// input_size: single arg function
-fn gen_response(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_response(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for i in 0..scale {
-        let args = helper_gen_random_response_value(false, false);
+        let args = helper_gen_random_response_value(false, false, rng);
         body.push_str(&*format!("({} {}) ", function_name, args));
     }
 
@@ -1115,25 +1138,26 @@ fn gen_unwrap(
     function_name: &'static str,
     scale: u16,
     ret_value: bool,
+    rng: &mut StdRng,
 ) -> GenOutput {
-    let mut rng = rand::thread_rng();
     let mut body = String::new();
     for i in 0..scale {
         let mut args = [
-            helper_gen_random_response_value(true, false),
-            helper_gen_random_optional_value(i, true),
+            helper_gen_random_response_value(true, false, rng),
+            helper_gen_random_optional_value(i, true, rng),
         ]
-        .choose(&mut rng)
+        .choose(rng)
         .unwrap()
         .clone();
 
         if ret_value {
-            let (clarity_type, length) = helper_gen_clarity_type(true, false, false);
+            let (clarity_type, length) = helper_gen_clarity_type(true, false, false, rng);
             let clarity_val = helper_gen_clarity_value(
                 &clarity_type,
                 i,
                 length.map_or(0, |len| len as u64),
                 None,
+                rng,
             );
             args = format!("{} {}", args, clarity_val.0)
         }
@@ -1152,13 +1176,14 @@ fn gen_unwrap_err(
     function_name: &'static str,
     scale: u16,
     ret_value: bool,
+    rng: &mut StdRng,
 ) -> GenOutput {
     let mut body = String::new();
     for i in 0..scale {
-        let mut args = helper_gen_random_response_value(false, true);
+        let mut args = helper_gen_random_response_value(false, true, rng);
 
         if ret_value {
-            let clarity_val = helper_gen_random_clarity_value();
+            let clarity_val = helper_gen_random_clarity_value(rng);
             args = format!("{} {}", args, clarity_val.0)
         }
         body.push_str(&*format!("({} {}) ", function_name, args));
@@ -1180,23 +1205,22 @@ struct DefineMap {
 
 // generate a define map statement
 // size = approximate size in bytes of key + value
-fn helper_create_map(size: u64) -> DefineMap {
+fn helper_create_map(size: u64, rng: &mut StdRng) -> DefineMap {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     // random map name
-    let map_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+    let map_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
 
     // create key name + type
-    let key_name = helper_generate_rand_char_string(rng.gen_range(10..20));
-    let (key_type, key_type_len) = helper_gen_clarity_type(false, false, false);
+    let key_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
+    let (key_type, key_type_len) = helper_gen_clarity_type(false, false, false, rng);
     let key_type_formatted = match key_type_len {
         Some(length) => format!("{{ {}: ({} {}) }}", key_name, key_type, length),
         None => format!("{{ {}: {} }}", key_name, key_type),
     };
 
     // create value name + type
-    let value_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+    let value_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
     let value_type = helper_gen_clarity_list_type(size);
     let value_type_formatted = format!("{{ {}: {} }}", value_name, value_type.0);
 
@@ -1215,11 +1239,27 @@ fn helper_create_map(size: u64) -> DefineMap {
     }
 }
 
+/// cost_function: CreateMap
+/// input_size: sum of key type size and value type size
+///     `u64::from(key_type.size()).cost_overflow_add(u64::from(value_type.size()))`
+fn gen_create_map(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let mut body = String::new();
+    let value_type = helper_make_clarity_type_for_sized_type_sig(input_size);
+    let total_size = BoolType.size() + make_sized_type_sig(input_size, rng).size();
+
+    for _ in 0..scale {
+        let map_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
+        body.push_str(&format!("(define-map {} bool {}) ", map_name, value_type));
+    }
+
+    GenOutput::new(None, body, total_size as u64)
+}
+
 // setEntry is the cost for map-delete, map-insert, & map-set
 // q: only ever deleting non-existent key; should we change that?
 /// cost_function: SetEntry
 /// input_size: sum of key type size and value type size
-fn gen_set_entry(scale: u16, input_size: u64) -> GenOutput {
+fn gen_set_entry(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let body = String::new();
 
     let DefineMap {
@@ -1229,7 +1269,7 @@ fn gen_set_entry(scale: u16, input_size: u64) -> GenOutput {
         key_type,
         value_name,
         value_type,
-    } = helper_create_map(input_size);
+    } = helper_create_map(input_size, rng);
 
     let output = format!(" (define-private (execute (input-value {})) (begin ", value_type.0);
     setup.push_str(&output);
@@ -1239,12 +1279,14 @@ fn gen_set_entry(scale: u16, input_size: u64) -> GenOutput {
         89,
         key_type.1.map_or(0, |len| len as u64),
         None,
+        rng,
     );
     let curr_value = helper_gen_clarity_value(
         "list",
         0,
         value_type.1,
         Some("uint"),
+        rng,
     );
 
     for i in 0..scale {
@@ -1279,7 +1321,7 @@ fn gen_set_entry(scale: u16, input_size: u64) -> GenOutput {
 
 /// cost_function: FetchEntry
 /// input_size: sum of key type size and value type size
-fn gen_fetch_entry(scale: u16, input_size: u64) -> GenOutput {
+fn gen_fetch_entry(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
 
     // define an arbitrary map
@@ -1290,7 +1332,7 @@ fn gen_fetch_entry(scale: u16, input_size: u64) -> GenOutput {
         key_type,
         value_name,
         value_type,
-    } = helper_create_map(input_size);
+    } = helper_create_map(input_size, rng);
 
     // construct a properly typed key for the map
     let curr_key = helper_gen_clarity_value(
@@ -1298,6 +1340,7 @@ fn gen_fetch_entry(scale: u16, input_size: u64) -> GenOutput {
         23,
         key_type.1.map_or(0, |len| len as u64),
         None,
+        rng,
     );
 
     // construct a properly typed value for the map
@@ -1306,6 +1349,7 @@ fn gen_fetch_entry(scale: u16, input_size: u64) -> GenOutput {
         89,
         value_type.1,
         Some("uint"),
+        rng,
     );
 
     // insert the key value pair into the map
@@ -1322,6 +1366,7 @@ fn gen_fetch_entry(scale: u16, input_size: u64) -> GenOutput {
                 i,
                 key_type.1.map_or(0, |len| len as u64),
                 None,
+                rng,
             )
         } else {
             curr_key.clone()
@@ -1357,17 +1402,33 @@ fn helper_gen_execute_fn(scale: u16, code_to_wrap: String, clarity_type: String)
 }
 
 
+/// cost_function: CreateVar
+/// input_size: value type size
+///     `value_type.size()`
+fn gen_create_var(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let mut body = String::new();
+    let value_type = helper_make_clarity_type_for_sized_type_sig(input_size);
+    let value_type_size = make_sized_type_sig(input_size, rng).size();
+
+    for _ in 0..scale {
+        let var_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
+        let value = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
+        body.push_str(&format!("(define-data-var {} {} {}) ", var_name, value_type, value));
+    }
+
+    GenOutput::new(None, body, value_type_size as u64)
+}
+
 /// cost_function: SetVar
 /// input_size: dynamic size of data being persisted
 /// generates setup code for var-set benchmarking, with the
 /// function calls inside a function body. this allows the
 /// benchmarking function generate the the input value as
 /// code instead of parsing a large Clarity string (takes too long)
-fn gen_var_set(scale: u16, input_size: u64) -> GenOutput {
+fn gen_var_set(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let body = String::new();
-    let mut rng = rand::thread_rng();
 
-    let var_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+    let var_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
 
     let (clarity_type, length) = helper_gen_clarity_list_type(input_size);
 
@@ -1376,6 +1437,7 @@ fn gen_var_set(scale: u16, input_size: u64) -> GenOutput {
         0,
         length,
         Some("uint"),
+        rng,
     );
 
     let mut setup = format!("(define-data-var {} {} {}) ", var_name, clarity_type, clarity_value.0);
@@ -1388,11 +1450,10 @@ fn gen_var_set(scale: u16, input_size: u64) -> GenOutput {
 
 /// cost_function: SetVar
 /// input_size: dynamic size of data being persisted
-fn gen_var_get(scale: u16, input_size: u64) -> GenOutput {
+fn gen_var_get(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
-    let var_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+    let var_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
 
     let (clarity_type, length) = helper_gen_clarity_list_type(input_size);
 
@@ -1401,6 +1462,7 @@ fn gen_var_get(scale: u16, input_size: u64) -> GenOutput {
         0,
         length,
         Some("uint"),
+        rng,
     );
 
     let setup = format!("(define-data-var {} {} {})", var_name, clarity_type, clarity_value.0);
@@ -1415,7 +1477,7 @@ fn gen_var_get(scale: u16, input_size: u64) -> GenOutput {
 
 /// cost_function: Print
 /// input_size: dynamic size of data being printed
-fn gen_print(scale: u16, input_size: u64) -> GenOutput {
+fn gen_print(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let body = String::new();
     let mut setup = String::new();
 
@@ -1426,6 +1488,7 @@ fn gen_print(scale: u16, input_size: u64) -> GenOutput {
         0,
         length,
         Some("uint"),
+        rng,
     );
 
     let print = format!("(print input-value) ");
@@ -1441,16 +1504,16 @@ fn gen_print(scale: u16, input_size: u64) -> GenOutput {
 /// print: size of given Value for print
 /// SomeCons/OkCons/ErrCons: single arg function
 /// begin: multi arg function
-fn gen_single_clar_value(function_name: &'static str, scale: u16, input_size: Option<u64>) -> GenOutput {
+fn gen_single_clar_value(function_name: &'static str, scale: u16, input_size: Option<u64>, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
 
-    let l = helper_gen_clarity_list_size(input_size.unwrap_or(20));
+    let l = helper_gen_clarity_list_size(input_size.unwrap_or(20), rng);
     let l_size = size_of_value(l.clone());
 
     for _ in 0..scale {
         let arg = match input_size {
             Some(_) => l.clone(),
-            None => helper_gen_random_clarity_value().0,
+            None => helper_gen_random_clarity_value(rng).0,
         };
         body.push_str(&*format!("({} {}) ", function_name, arg));
     }
@@ -1460,15 +1523,14 @@ fn gen_single_clar_value(function_name: &'static str, scale: u16, input_size: Op
 
 /// cost_function: If
 /// input_size: 0
-fn gen_if(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_if(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for i in 0..scale {
-        let (clarity_type, length) = helper_gen_clarity_type(true, false, false);
+        let (clarity_type, length) = helper_gen_clarity_type(true, false, false, rng);
         let if_case_value =
-            helper_gen_clarity_value(&clarity_type, i, length.map_or(0, |len| len as u64), None);
+            helper_gen_clarity_value(&clarity_type, i, length.map_or(0, |len| len as u64), None, rng);
         let else_case_value =
-            helper_gen_clarity_value(&clarity_type, i, length.map_or(0, |len| len as u64), None);
+            helper_gen_clarity_value(&clarity_type, i, length.map_or(0, |len| len as u64), None, rng);
         let curr_bool = rng.gen_bool(0.5);
 
         body.push_str(&*format!(
@@ -1482,10 +1544,10 @@ fn gen_if(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: Asserts
 /// input_size: 0
-fn gen_asserts(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_asserts(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for i in 0..scale {
-        let clarity_val = helper_gen_random_clarity_value();
+        let clarity_val = helper_gen_random_clarity_value(rng);
         body.push_str(&*format!("({} true {}) ", function_name, clarity_val.0));
     }
     
@@ -1493,12 +1555,11 @@ fn gen_asserts(function_name: &'static str, scale: u16) -> GenOutput {
     GenOutput::new(None, body, 1)
 }
 
-fn helper_generate_sequences(list_type: &str, output: u16) -> Vec<String> {
-    let mut rng = rand::thread_rng();
+fn helper_generate_sequences(list_type: &str, output: u16, rng: &mut StdRng) -> Vec<String> {
     match rng.gen_bool(0.75) {
         true => {
             // non-list case
-            let (clarity_type, _) = helper_gen_clarity_type(true, true, false);
+            let (clarity_type, _) = helper_gen_clarity_type(true, true, false, rng);
             (0..output)
                 .map(|_| {
                     helper_gen_clarity_value(
@@ -1506,6 +1567,7 @@ fn helper_generate_sequences(list_type: &str, output: u16) -> Vec<String> {
                         rng.gen_range(2..50),
                         rng.gen_range(2..50) * 2,
                         None,
+                        rng,
                     ).0
                 })
                 .collect()
@@ -1519,6 +1581,7 @@ fn helper_generate_sequences(list_type: &str, output: u16) -> Vec<String> {
                         rng.gen_range(2..50),
                         rng.gen_range(2..50) * 2,
                         Some(list_type),
+                        rng,
                     ).0
                 })
                 .collect()
@@ -1529,14 +1592,14 @@ fn helper_generate_sequences(list_type: &str, output: u16) -> Vec<String> {
 /// cost_function: Concat
 /// input_size: sum of Value size of input sequences
 ///     `u64::from(wrapped_seq.size()).cost_overflow_add(u64::from(other_wrapped_seq.size())`
-fn gen_concat(function_name: &'static str, scale: u16, input_size: u64) -> GenOutput {
+fn gen_concat(function_name: &'static str, scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
 
-    let value_size = make_sized_type_sig(input_size).size();
+    let value_size = make_sized_type_sig(input_size, rng).size();
     assert!(value_size < u16::MAX as u32);
     for _ in 0..scale {
-        let first_val = helper_make_clarity_value_for_sized_type_sig(input_size);
-        let second_val = helper_make_clarity_value_for_sized_type_sig(input_size);
+        let first_val = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
+        let second_val = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
         body.push_str(&*format!(
             "({} (list {}) (list {})) ",
             function_name, first_val, second_val
@@ -1549,13 +1612,12 @@ fn gen_concat(function_name: &'static str, scale: u16, input_size: u64) -> GenOu
 
 /// cost_function: AsMaxLen
 /// input_size: 0
-fn gen_as_max_len(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_as_max_len(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for _ in 0..scale {
-        let (list_type, _) = helper_gen_clarity_type(true, false, true);
-        let operand = helper_generate_sequences(&list_type, 1);
-        let len = helper_gen_clarity_value("uint", rng.gen_range(2..50), 0, None);
+        let (list_type, _) = helper_gen_clarity_type(true, false, true, rng);
+        let operand = helper_generate_sequences(&list_type, 1, rng);
+        let len = helper_gen_clarity_value("uint", rng.gen_range(2..50), 0, None, rng);
         body.push_str(&*format!("({} {} {}) ", function_name, operand[0], len.0));
     }
     
@@ -1565,12 +1627,11 @@ fn gen_as_max_len(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: BindName
 /// input_size: 0
-fn gen_define_constant(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_define_constant(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for i in 0..scale {
-        let name = helper_generate_rand_char_string(rng.gen_range(10..50));
-        let value = helper_gen_random_clarity_value();
+        let name = helper_generate_rand_char_string(rng.gen_range(10..50), rng);
+        let value = helper_gen_random_clarity_value(rng);
         body.push_str(&*format!("({} {} {}) ", function_name, name, value.0));
     }
     
@@ -1580,14 +1641,13 @@ fn gen_define_constant(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: DefaultTo
 /// input_size: double arg function
-fn gen_default_to(function_name: &'static str, scale: u16) -> GenOutput {
+fn gen_default_to(function_name: &'static str, scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     for i in 0..scale {
-        let (clarity_type, length) = helper_gen_clarity_type(true, false, false);
+        let (clarity_type, length) = helper_gen_clarity_type(true, false, false, rng);
         let default_val =
-            helper_gen_clarity_value(&clarity_type, i, length.map_or(0, |len| len as u64), None);
+            helper_gen_clarity_value(&clarity_type, i, length.map_or(0, |len| len as u64), None, rng);
         let opt_string = match rng.gen_bool(0.5) {
             true => "none".to_string(),
             false => {
@@ -1596,6 +1656,7 @@ fn gen_default_to(function_name: &'static str, scale: u16) -> GenOutput {
                     i,
                     length.map_or(0, |len| len as u64),
                     None,
+                    rng,
                 );
                 format!("(some {})", inner_val.0)
             }
@@ -1612,9 +1673,8 @@ fn gen_default_to(function_name: &'static str, scale: u16) -> GenOutput {
 
 /// cost_function: IntCast
 /// input_size: single arg function
-fn gen_int_cast(scale: u16) -> GenOutput {
+fn gen_int_cast(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for _ in 0..scale {
         let statement = match rng.gen_bool(0.5) {
             true => {
@@ -1637,23 +1697,22 @@ fn gen_int_cast(scale: u16) -> GenOutput {
 
 /// cost_function: Match
 /// input_size: 0
-fn gen_match(scale: u16) -> GenOutput {
+fn gen_match(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for i in 0..scale {
-        let first_branch_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+        let first_branch_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
 
         let statement = match rng.gen_bool(0.5) {
             true => {
-                let match_val = helper_gen_random_response_value(false, false);
-                let second_branch_name = helper_generate_rand_char_string(rng.gen_range(10..20));
+                let match_val = helper_gen_random_response_value(false, false, rng);
+                let second_branch_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
                 format!(
                     "(match {} {} (no-op) {} (no-op)) ",
                     match_val, first_branch_name, second_branch_name
                 )
             }
             false => {
-                let match_val = helper_gen_random_optional_value(i, false);
+                let match_val = helper_gen_random_optional_value(i, false, rng);
                 format!(
                     "(match {} {} (no-op) (no-op)) ",
                     match_val, first_branch_name
@@ -1667,17 +1726,64 @@ fn gen_match(scale: u16) -> GenOutput {
     GenOutput::new(None, body, 1)
 }
 
+/// Deepest context `gen_lookup_variable_depth` will nest to. Deeper nesting trips the
+/// AST stack depth check, so those depths can only be reached by the custom harness.
+const MAX_GENERATED_CONTEXT_DEPTH: u64 = 64;
+
+/// cost_function: LookupVariableDepth, AnalysisLookupVariableDepth
+/// input_size: depth of the context the variable is looked up in
+///     `context.depth()`
+fn gen_lookup_variable_depth(scale: u16, input_size: u64) -> Result<GenOutput, NoGenerator> {
+    if input_size > MAX_GENERATED_CONTEXT_DEPTH {
+        return Err(NoGenerator::CustomHarness(
+            "context depth exceeds the AST stack depth limit",
+        ));
+    }
+
+    // the looked up variable needs a `let` to bind it, so the context is at least one deep
+    let depth = input_size.max(1);
+
+    // each `let` extends the context by one level, lookups happen in the innermost one
+    let mut body = String::new();
+    for i in 0..depth {
+        body.push_str(&format!("(let ((var-{} {})) ", i, i));
+    }
+    for _ in 0..scale {
+        body.push_str("var-0 ");
+    }
+    for _ in 0..depth {
+        body.push_str(") ");
+    }
+
+    Ok(GenOutput::new(None, body, depth))
+}
+
+/// cost_function: LookupVariableSize
+/// input_size: size of the value bound to the variable
+///     `value.size()`
+fn gen_lookup_variable_size(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let value = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
+    let value_size = make_sized_type_sig(input_size, rng).size();
+
+    let mut body = format!("(let ((dummy {})) ", value);
+    for _ in 0..scale {
+        body.push_str("dummy ");
+    }
+    body.push_str(") ");
+
+    GenOutput::new(None, body, value_size as u64)
+}
+
 /// cost_function: Let
 /// input_size: number of bindings in the let statement
 ///     `bindings.len()`
-fn gen_let(scale: u16, input_size: u64) -> GenOutput {
+fn gen_let(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for i in 0..scale {
         let mut bindings = String::new();
         for _ in 0..input_size {
-            let var_name = helper_generate_rand_char_string(rng.gen_range(10..20));
-            let var_value = helper_gen_random_clarity_value();
+            let var_name = helper_generate_rand_char_string(rng.gen_range(10..20), rng);
+            let var_value = helper_gen_random_clarity_value(rng);
             bindings.push_str(&*format!("({} {}) ", var_name, var_value.0));
         }
         let statement = format!("(let ({}) (no-op)) ", bindings);
@@ -1688,22 +1794,21 @@ fn gen_let(scale: u16, input_size: u64) -> GenOutput {
     GenOutput::new(None, body, input_size)
 }
 
-fn helper_generate_random_sequence() -> (String, usize, String) {
-    let mut rng = rand::thread_rng();
+fn helper_generate_random_sequence(rng: &mut StdRng) -> (String, usize, String) {
     let value_len = rng.gen_range(2..50) * 2;
     match rng.gen_bool(0.75) {
         true => {
             // non-list case
-            let (clarity_type, _) = helper_gen_clarity_type(true, true, false);
+            let (clarity_type, _) = helper_gen_clarity_type(true, true, false, rng);
             let value =
-                helper_gen_clarity_value(&clarity_type, rng.gen_range(2..50), value_len, None);
+                helper_gen_clarity_value(&clarity_type, rng.gen_range(2..50), value_len, None, rng);
             (value.0, value_len as usize, clarity_type)
         }
         false => {
             // list case
-            let (list_type, _) = helper_gen_clarity_type(true, false, true);
+            let (list_type, _) = helper_gen_clarity_type(true, false, true, rng);
             let value =
-                helper_gen_clarity_value("list", rng.gen_range(2..50), value_len, Some(&list_type));
+                helper_gen_clarity_value("list", rng.gen_range(2..50), value_len, Some(&list_type), rng);
             (value.0, value_len as usize, list_type)
         }
     }
@@ -1711,12 +1816,11 @@ fn helper_generate_random_sequence() -> (String, usize, String) {
 
 /// cost_function: IndexOf
 /// input_size: the
-fn gen_index_of(scale: u16, input_size: u64) -> GenOutput {
+fn gen_index_of(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
-    let seq = helper_gen_clarity_value("list", 17, input_size, Some("uint"));
-    let item_val = helper_gen_clarity_value("uint", rng.gen_range(2..50), 0, None);
+    let seq = helper_gen_clarity_value("list", 17, input_size, Some("uint"), rng);
+    let item_val = helper_gen_clarity_value("uint", rng.gen_range(2..50), 0, None, rng);
 
     for _ in 0..scale {
         let statement = format!("(index-of {} {}) ", seq.0, item_val.0);
@@ -1728,11 +1832,10 @@ fn gen_index_of(scale: u16, input_size: u64) -> GenOutput {
 
 /// cost_function: ElementAt
 /// input_size: double arg function
-fn gen_element_at(scale: u16) -> GenOutput {
+fn gen_element_at(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for _ in 0..scale {
-        let (seq, seq_len, _) = helper_generate_random_sequence();
+        let (seq, seq_len, _) = helper_generate_random_sequence(rng);
         let index_to_query = rng.gen_range(0..seq_len * 2);
         let statement = format!("(element-at {} u{}) ", seq, index_to_query);
         body.push_str(&statement);
@@ -1744,10 +1847,10 @@ fn gen_element_at(scale: u16) -> GenOutput {
 
 /// cost_function: Len
 /// input_size: single arg function
-fn gen_len(scale: u16) -> GenOutput {
+fn gen_len(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for _ in 0..scale {
-        let (seq, _, _) = helper_generate_random_sequence();
+        let (seq, _, _) = helper_generate_random_sequence(rng);
         let statement = format!("(len {}) ", seq);
         body.push_str(&statement);
     }
@@ -1759,13 +1862,13 @@ fn gen_len(scale: u16) -> GenOutput {
 /// cost_function: Append
 /// input_size: max of value size (which is to be appended) and size of the type of the list
 ///     `u64::from(cmp::max(entry_type.size(), element_type.size()))`
-fn gen_append(scale: u16, input_size: u64) -> GenOutput {
+fn gen_append(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let value_size = make_sized_type_sig(input_size).size();
+    let value_size = make_sized_type_sig(input_size, rng).size();
     assert!(value_size < u16::MAX as u32);
     for _ in 0..scale {
-        let first_val = helper_make_clarity_value_for_sized_type_sig(input_size);
-        let second_val = helper_make_clarity_value_for_sized_type_sig(input_size);
+        let first_val = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
+        let second_val = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
 
         let statement = format!("(append (list {}) {}) ", first_val, second_val);
         body.push_str(&statement);
@@ -1799,16 +1902,16 @@ fn gen_list_cons(scale: u16, input_size: u64) -> GenOutput {
 
 /// cost_function: Filter
 /// input_size: 0
-fn gen_filter(scale: u16) -> GenOutput {
+fn gen_filter(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for _ in 0..scale {
-        let (list_type, _) = helper_gen_clarity_type(true, false, true);
+        let (list_type, _) = helper_gen_clarity_type(true, false, true, rng);
         let list_val = helper_gen_clarity_value(
             "list",
             rng.gen_range(2..50),
             rng.gen_range(1..5) * 2,
             Some(&list_type),
+            rng,
         );
         let statement = format!("(filter no-op {}) ", list_val.0);
         body.push_str(&statement);
@@ -1821,16 +1924,16 @@ fn gen_filter(scale: u16) -> GenOutput {
 // fixed type of B to be bool
 /// cost_function: Fold
 /// input_size: 0
-fn gen_fold(scale: u16) -> GenOutput {
+fn gen_fold(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for _ in 0..scale {
-        let (list_type, _) = helper_gen_clarity_type(true, false, true);
+        let (list_type, _) = helper_gen_clarity_type(true, false, true, rng);
         let list_val = helper_gen_clarity_value(
             "list",
             rng.gen_range(2..50),
             rng.gen_range(1..5) * 2,
             Some(&list_type),
+            rng,
         );
         let statement = format!("(fold no-op {} true) ", list_val.0);
         body.push_str(&statement);
@@ -1842,18 +1945,18 @@ fn gen_fold(scale: u16) -> GenOutput {
 
 /// cost_function: Map
 /// input_size: number of arguments
-fn gen_map(scale: u16, input_size: u64) -> GenOutput {
+fn gen_map(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     for _ in 0..scale {
         let mut lists = String::new();
         for _ in 0..input_size {
-            let (list_type, _) = helper_gen_clarity_type(true, false, true);
+            let (list_type, _) = helper_gen_clarity_type(true, false, true, rng);
             let list_val = helper_gen_clarity_value(
                 "list",
                 rng.gen_range(2..50),
                 rng.gen_range(2..50) * 2,
                 Some(&list_type),
+                rng,
             );
             lists.push_str(&list_val.0);
             lists.push_str(" ");
@@ -1869,9 +1972,8 @@ fn gen_map(scale: u16, input_size: u64) -> GenOutput {
 
 /// cost_function: BlockInfo
 /// input_size: 0
-fn gen_get_block_info(scale: u16) -> GenOutput {
+fn gen_get_block_info(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
 
     let props = [
         "time",
@@ -1885,7 +1987,7 @@ fn gen_get_block_info(scale: u16) -> GenOutput {
     // must use block 5 here b/c it has a hardcoded id_bhh
     // TODO: consider hardcoding more id_bhhs and making this random
     for _ in 0..scale {
-        body.push_str(format!("(get-block-info? {} u5) ", props.choose(&mut rng).unwrap()).as_str())
+        body.push_str(format!("(get-block-info? {} u5) ", props.choose(rng).unwrap()).as_str())
     }
 
     GenOutput::new(None, body, 1)
@@ -1904,9 +2006,9 @@ fn gen_at_block(scale: u16) -> GenOutput {
 }
 
 // helper function used in bench.rs
-pub fn gen_read_only_func(size: u16) -> GenOutput {
+pub fn gen_read_only_func(size: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let arith_string = gen_arithmetic("+", size, 2).body;
+    let arith_string = gen_arithmetic("+", size, 2, rng).body;
     body.push_str(arith_string.as_str());
 
     GenOutput::new(
@@ -1922,8 +2024,8 @@ pub fn gen_read_only_func(size: u16) -> GenOutput {
 /// cost_function: AnalysisBindName
 /// input_size: type size (could be value, constant, function, total map size, etc.)
 ///     `v_type.type_size()`
-fn gen_type_sig_size(input_size: u64) -> GenOutput {
-    let type_sig_map = make_sized_type_sig_map(vec![input_size]);
+fn gen_type_sig_size(input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let type_sig_map = make_sized_type_sig_map(vec![input_size], rng);
     let type_sig_size = type_sig_map.get(&input_size).unwrap().type_size().unwrap();
     assert!(type_sig_size < u16::MAX as u32);
 
@@ -1933,13 +2035,13 @@ fn gen_type_sig_size(input_size: u64) -> GenOutput {
 /// cost_function: AnalysisListItemsCheck
 /// input_size: type signature size of item
 ///     `type_arg.type_size()`
-fn gen_analysis_list_items_check(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_list_items_check(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for i in 0..scale {
-        let (base_type, _) = helper_gen_clarity_type(true, false, true);
+        let (base_type, _) = helper_gen_clarity_type(true, false, true, rng);
         body.push_str("(");
         for _ in 0..input_size {
-            let base_val = helper_gen_clarity_value(&base_type, i, 0, None);
+            let base_val = helper_gen_clarity_value(&base_type, i, 0, None, rng);
             body.push_str(&*format!("{} ", base_val.0));
         }
         body.push_str(") ");
@@ -1952,18 +2054,18 @@ fn gen_analysis_list_items_check(scale: u16, input_size: u64) -> GenOutput {
 /// cost_function: AnalysisCheckTupleGet
 /// input_size: length of tuple
 ///     `tuple_type_sig.len()`
-fn gen_analysis_tuple_get(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_tuple_get(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for _ in 0..scale {
-        body.push_str(&helper_generate_tuple(input_size));
+        body.push_str(&helper_generate_tuple(input_size, rng));
     }
     
 
     GenOutput::new(None, body, input_size)
 }
 
-fn gen_tuple_size(input_size: u64) -> GenOutput {
-    let tuple_map = make_sized_tuple_sigs_map(vec![input_size]);
+fn gen_tuple_size(input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let tuple_map = make_sized_tuple_sigs_map(vec![input_size], rng);
     let tuple_sig_size = tuple_map.get(&input_size).unwrap().len();
     assert!(tuple_sig_size < u16::MAX as u64);
 
@@ -1973,13 +2075,13 @@ fn gen_tuple_size(input_size: u64) -> GenOutput {
 /// cost_function: AnalysisCheckTupleCons
 /// input_size: number of arguments provided
 ///     `args.len()`
-fn gen_analysis_tuple_cons(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_tuple_cons(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for i in 0..scale {
         body.push_str("(");
         for _ in 0..input_size {
-            let var_val = helper_gen_random_clarity_value();
-            let var_name = helper_generate_rand_char_string(10);
+            let var_val = helper_gen_random_clarity_value(rng);
+            let var_name = helper_generate_rand_char_string(10, rng);
             body.push_str(&*format!("({} {}) ", var_name, var_val.0));
         }
         body.push_str(") ");
@@ -1992,12 +2094,12 @@ fn gen_analysis_tuple_cons(scale: u16, input_size: u64) -> GenOutput {
 /// cost_function: AnalysisCheckLet
 /// input_size: number of arguments total (the binding list counts as an arg)
 ///     `args.len()`
-fn gen_analysis_check_let(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_check_let(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for i in 0..(scale) {
         let no_ops = (0..input_size).map(|_x| "(no-op) ").collect::<String>();
-        let var_val = helper_gen_random_clarity_value();
-        let var_name = helper_generate_rand_char_string(10);
+        let var_val = helper_gen_random_clarity_value(rng);
+        let var_name = helper_generate_rand_char_string(10, rng);
         body.push_str(&*format!("((({} {})) {}) ", var_name, var_val.0, no_ops));
     }
     
@@ -2016,14 +2118,14 @@ fn gen_analysis_check_let(scale: u16, input_size: u64) -> GenOutput {
 ///            Err(e) => Err(e),
 ///        })?;
 ///    }```
-fn gen_analysis_storage(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_storage(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for _ in 0..scale {
         let mut defines = String::new();
         for j in 0..input_size {
-            let (base_type, _) = helper_gen_clarity_type(true, false, true);
-            let base_val = helper_gen_clarity_value(&base_type, j as u16, 0, None);
-            let constant_name = helper_generate_rand_char_string(10);
+            let (base_type, _) = helper_gen_clarity_type(true, false, true, rng);
+            let base_val = helper_gen_clarity_value(&base_type, j as u16, 0, None, rng);
+            let constant_name = helper_generate_rand_char_string(10, rng);
             defines.push_str(&*format!(
                 "(define-constant {} {}) ",
                 constant_name, base_val.0
@@ -2073,19 +2175,18 @@ fn gen_empty(input_size: u64) -> GenOutput {
 /// cost_function: ContractStorage
 /// input_size: length of contract string
 ///     `contract_string.len()`
-fn gen_contract_storage(input_size: u64) -> GenOutput {
-    let contract = make_sized_contract(input_size);
+fn gen_contract_storage(input_size: u64, rng: &mut StdRng) -> GenOutput {
+    let contract = make_sized_contract(input_size, rng);
     GenOutput::new(None, contract.0, contract.1)
 }
 
 /// cost_function: TypeParseStep
 /// input_size: 0
-fn gen_type_parse_step(scale: u16) -> GenOutput {
+fn gen_type_parse_step(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
-    let mut rng = rand::thread_rng();
     let type_list = ["bool ", "int ", "uint ", "principal ", "RANDOM "];
     for _ in 0..scale {
-        let curr_type = type_list.choose(&mut rng).unwrap();
+        let curr_type = type_list.choose(rng).unwrap();
         body.push_str(curr_type);
     }
     
@@ -2095,10 +2196,10 @@ fn gen_type_parse_step(scale: u16) -> GenOutput {
 
 /// cost_function: PrincipalOf
 /// input_size: 0
-fn gen_principal_of(scale: u16) -> GenOutput {
+fn gen_principal_of(scale: u16, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for _ in 0..scale {
-        body.push_str(&helper_create_principal_in_hex());
+        body.push_str(&helper_create_principal_in_hex(rng));
     }
     
 
@@ -2108,12 +2209,12 @@ fn gen_principal_of(scale: u16) -> GenOutput {
 /// cost_function: AnalysisTypeLookup
 /// input_size: type signature size of value being looked up
 ///     `expected_asset_type.type_size()`
-fn gen_analysis_type_lookup(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_type_lookup(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for _ in 0..scale {
-        let asset_name = helper_generate_rand_char_string(10);
-        let owner = helper_create_principal();
-        let tuple = helper_make_clarity_value_for_sized_type_sig(input_size);
+        let asset_name = helper_generate_rand_char_string(10, rng);
+        let owner = helper_create_principal(rng);
+        let tuple = helper_make_clarity_value_for_sized_type_sig(input_size, rng);
         body.push_str(&*format!("({} {} {}) ", asset_name, tuple, owner));
     }
     
@@ -2124,14 +2225,14 @@ fn gen_analysis_type_lookup(scale: u16, input_size: u64) -> GenOutput {
 /// cost_function: AnalysisTypeAnnotate, AnalysisLookupVariableConst
 /// input_size: type signature size of SymbolicExpression / 0
 ///     `type_sig.type_size()` / 0
-fn gen_analysis_type_annotate(scale: u16, input_size: u64) -> GenOutput {
+fn gen_analysis_type_annotate(scale: u16, input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut body = String::new();
     for _ in 0..scale {
-        body.push_str(&helper_generate_rand_char_string(10));
+        body.push_str(&helper_generate_rand_char_string(10, rng));
         body.push_str(" ");
     }
     
-    let type_sig_map = make_sized_type_sig_map(vec![input_size]);
+    let type_sig_map = make_sized_type_sig_map(vec![input_size], rng);
     let type_sig_size = type_sig_map.get(&input_size).unwrap().type_size().unwrap();
     assert!(type_sig_size < u16::MAX as u32);
 
@@ -2139,6 +2240,38 @@ fn gen_analysis_type_annotate(scale: u16, input_size: u64) -> GenOutput {
 }
 
 
+/// cost_function: AnalysisIterableFunc
+/// input_size: number of sequences passed to `map`, plus the function name
+///     `args.len()` in `check_special_map`
+fn gen_analysis_iterable_func(scale: u16, input_size: u64) -> GenOutput {
+    // `(map +)` doesn't type check, `map` needs at least one sequence
+    assert!(input_size > 0, "map needs at least one sequence");
+
+    let mut body = String::new();
+    let sequences = (0..input_size)
+        .map(|i| format!("(list {}) ", i))
+        .collect::<String>();
+
+    for _ in 0..scale {
+        body.push_str(&format!("(map + {}) ", sequences));
+    }
+
+    GenOutput::new(None, body, input_size + 1)
+}
+
+/// cost_function: AnalysisLookupFunction
+/// input_size: 0
+fn gen_analysis_lookup_function(scale: u16, rng: &mut StdRng) -> GenOutput {
+    let mut body = String::new();
+    let calls = ["(pow 2 3) ", "(mod 7 2) ", "(xor 1 2) ", "(if true 1 2) "];
+
+    for _ in 0..scale {
+        body.push_str(calls.choose(rng).unwrap());
+    }
+
+    GenOutput::new(None, body, 1)
+}
+
 /// cost_function: AnalysisLookupVariableConst
 /// input_size: 0
 fn gen_analysis_lookup_variable_const(scale: u16) -> GenOutput {
@@ -2273,11 +2406,11 @@ pub fn define_dummy_trait(i: u64, clarity_type: &str) -> String {
     format!("(define-trait dummy-trait-{} ({})) ", i, dummy_fn)
 }
 
-pub fn gen_analysis_pass_trait_checker(input_size: u64) -> GenOutput {
+pub fn gen_analysis_pass_trait_checker(input_size: u64, rng: &mut StdRng) -> GenOutput {
     let mut setup_body = String::new();
     let mut body = String::new();
     for i in 0..input_size {
-        let (clarity_type, length) = helper_gen_clarity_type(true, false, false);
+        let (clarity_type, length) = helper_gen_clarity_type(true, false, false, rng);
         let final_clarity_type = match length {
             Some(l) => format!("({} {})", clarity_type, l),
             None => clarity_type,
@@ -2346,42 +2479,52 @@ fn gen_contract_of(scale: u16) -> GenOutput {
 /// The `reviewed` comment above each cost function should list the GitHub usernames of those
 ///    who have verified that the benchmark for that cost function seems accurate (given the code
 ///    in `benches.rs`, the code in `generators.rs`, and the benchmark data.
-pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOutput {
-    match function {
+/// All randomness is drawn from an RNG seeded with `seed`, so the same
+/// (function, scale, input_size, seed) always produces byte-identical output.
+/// Every cost function is covered: those that can't be expressed as Clarity code
+/// return a `NoGenerator` describing why, so callers can iterate over the whole enum.
+pub fn try_gen(
+    function: ClarityCostFunction,
+    scale: u16,
+    input_size: u64,
+    seed: u64,
+) -> Result<GenOutput, NoGenerator> {
+    let rng = &mut StdRng::seed_from_u64(seed);
+    let output = match function {
         /// Arithmetic ///////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Add => gen_arithmetic("+", scale, input_size),
-        ClarityCostFunction::Sub => gen_arithmetic("-", scale, input_size),
-        ClarityCostFunction::Mul => gen_arithmetic("*", scale, input_size),
-        ClarityCostFunction::Div => gen_arithmetic("/", scale, input_size),
-        ClarityCostFunction::Sqrti => gen_arithmetic("sqrti", scale, 1),
-        ClarityCostFunction::Log2 => gen_arithmetic("log2", scale, 1),
-        ClarityCostFunction::Mod => gen_arithmetic("mod", scale, 2),
+        ClarityCostFunction::Add => gen_arithmetic("+", scale, input_size, rng),
+        ClarityCostFunction::Sub => gen_arithmetic("-", scale, input_size, rng),
+        ClarityCostFunction::Mul => gen_arithmetic("*", scale, input_size, rng),
+        ClarityCostFunction::Div => gen_arithmetic("/", scale, input_size, rng),
+        ClarityCostFunction::Sqrti => gen_arithmetic("sqrti", scale, 1, rng),
+        ClarityCostFunction::Log2 => gen_arithmetic("log2", scale, 1, rng),
+        ClarityCostFunction::Mod => gen_arithmetic("mod", scale, 2, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Pow => gen_pow(scale),
+        ClarityCostFunction::Pow => gen_pow(scale, rng),
 
         /// Logic /////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Le => gen_cmp("<", scale),
-        ClarityCostFunction::Leq => gen_cmp("<=", scale),
-        ClarityCostFunction::Ge => gen_cmp(">", scale),
-        ClarityCostFunction::Geq => gen_cmp(">=", scale),
+        ClarityCostFunction::Le => gen_cmp("<", scale, rng),
+        ClarityCostFunction::Leq => gen_cmp("<=", scale, rng),
+        ClarityCostFunction::Ge => gen_cmp(">", scale, rng),
+        ClarityCostFunction::Geq => gen_cmp(">=", scale, rng),
 
 
         /// Boolean ///////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::And => gen_logic("and", scale, input_size),
-        ClarityCostFunction::Or => gen_logic("or", scale, input_size),
-        ClarityCostFunction::Not => gen_logic("not", scale, input_size),
-        ClarityCostFunction::Eq => gen_logic("is-eq", scale, input_size),
+        ClarityCostFunction::And => gen_logic("and", scale, input_size, rng),
+        ClarityCostFunction::Or => gen_logic("or", scale, input_size, rng),
+        ClarityCostFunction::Not => gen_logic("not", scale, input_size, rng),
+        ClarityCostFunction::Eq => gen_logic("is-eq", scale, input_size, rng),
         /// reviewed: yes
-        ClarityCostFunction::Xor => gen_xor("xor", scale),
+        ClarityCostFunction::Xor => gen_xor("xor", scale, rng),
 
 
         /// Tuples ////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::TupleGet => gen_tuple_get(scale, input_size),
+        ClarityCostFunction::TupleGet => gen_tuple_get(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
         ClarityCostFunction::TupleMerge => gen_tuple_merge(scale, input_size),
@@ -2392,20 +2535,20 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
 
         /// Analysis //////////////////////////
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisTypeAnnotate => gen_analysis_type_annotate(scale, input_size),
+        ClarityCostFunction::AnalysisTypeAnnotate => gen_analysis_type_annotate(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisTypeCheck => gen_type_sig_size(input_size),
+        ClarityCostFunction::AnalysisTypeCheck => gen_type_sig_size(input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisTypeLookup => gen_analysis_type_lookup(scale, input_size),
+        ClarityCostFunction::AnalysisTypeLookup => gen_analysis_type_lookup(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
         ClarityCostFunction::AnalysisVisit => gen_no_op_with_scale_repetitions(scale),
 
         /// reviewed: @pavitthrap
         /// input_size: 0 in most cases, `args.len()` in `check_special_map`
-        ClarityCostFunction::AnalysisIterableFunc => unimplemented!(),
+        ClarityCostFunction::AnalysisIterableFunc => gen_analysis_iterable_func(scale, input_size),
 
         /// reviewed: @pavitthrap
         /// input_size: 0
@@ -2418,33 +2561,33 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         /// reviewed: @pavitthrap
         /// TODO: super slow, get second review
         /// input_size: type signature size of item
-        ClarityCostFunction::AnalysisBindName => gen_type_sig_size(input_size),
+        ClarityCostFunction::AnalysisBindName => gen_type_sig_size(input_size, rng),
 
         /// reviewed: @pavitthrap
         /// input_size: type signature size of item
-        ClarityCostFunction::AnalysisListItemsCheck => gen_type_sig_size(input_size),
+        ClarityCostFunction::AnalysisListItemsCheck => gen_type_sig_size(input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisCheckTupleGet => gen_analysis_tuple_get(scale, input_size),
+        ClarityCostFunction::AnalysisCheckTupleGet => gen_analysis_tuple_get(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
         /// input_size: length of second tuple
-        ClarityCostFunction::AnalysisCheckTupleMerge => gen_tuple_size(input_size),
+        ClarityCostFunction::AnalysisCheckTupleMerge => gen_tuple_size(input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisCheckTupleCons => gen_analysis_tuple_cons(scale, input_size),
+        ClarityCostFunction::AnalysisCheckTupleCons => gen_analysis_tuple_cons(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
         /// input_size: type signature size of value, `var_type.type_size()`
-        ClarityCostFunction::AnalysisTupleItemsCheck => gen_type_sig_size(input_size),
+        ClarityCostFunction::AnalysisTupleItemsCheck => gen_type_sig_size(input_size, rng),
 
         /// reviewed: @pavitthrap
         /// TODO: size is args.len() not binding_list.len()
-        ClarityCostFunction::AnalysisCheckLet => gen_analysis_check_let(scale, input_size),
+        ClarityCostFunction::AnalysisCheckLet => gen_analysis_check_let(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
         /// input_size: 0
-        ClarityCostFunction::AnalysisLookupFunction => unimplemented!(),
+        ClarityCostFunction::AnalysisLookupFunction => gen_analysis_lookup_function(scale, rng),
 
         /// reviewed: @pavitthrap
         ClarityCostFunction::AnalysisLookupFunctionTypes => {
@@ -2457,10 +2600,10 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         }
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisLookupVariableDepth => unimplemented!(), // no gen function needed
+        ClarityCostFunction::AnalysisLookupVariableDepth => gen_lookup_variable_depth(scale, input_size)?,
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::AnalysisStorage => gen_analysis_storage(scale, input_size),
+        ClarityCostFunction::AnalysisStorage => gen_analysis_storage(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
         ClarityCostFunction::AnalysisUseTraitEntry => {
@@ -2474,7 +2617,7 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
 
         /// reviewed: @pavitthrap
         /// This cost function is not used anywhere.
-        ClarityCostFunction::AnalysisFetchContractEntry => unimplemented!(), // not used anywhere
+        ClarityCostFunction::AnalysisFetchContractEntry => return Err(NoGenerator::NotCharged),
 
 
         /// Ast ////////////////////////////////
@@ -2485,15 +2628,15 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         ClarityCostFunction::AstCycleDetection => gen_ast_cycle_detection(input_size),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::ContractStorage => gen_contract_storage(input_size),
+        ClarityCostFunction::ContractStorage => gen_contract_storage(input_size, rng),
 
 
         /// Lookup ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::LookupVariableDepth => unimplemented!(), // no gen function needed
+        ClarityCostFunction::LookupVariableDepth => gen_lookup_variable_depth(scale, input_size)?,
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::LookupVariableSize => unimplemented!(),  // no gen function needed
+        ClarityCostFunction::LookupVariableSize => gen_lookup_variable_size(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
         ClarityCostFunction::LookupFunction => gen_ast_cycle_detection(input_size),
@@ -2501,70 +2644,70 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
 
         /// List ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Map => gen_map(scale, input_size), // includes LookupFunction cost
+        ClarityCostFunction::Map => gen_map(scale, input_size, rng), // includes LookupFunction cost
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Filter => gen_filter(scale),       // includes LookupFunction cost
+        ClarityCostFunction::Filter => gen_filter(scale, rng),       // includes LookupFunction cost
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Fold => gen_fold(scale),           // includes LookupFunction cost
+        ClarityCostFunction::Fold => gen_fold(scale, rng),           // includes LookupFunction cost
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Len => gen_len(scale),
+        ClarityCostFunction::Len => gen_len(scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::ElementAt => gen_element_at(scale),
+        ClarityCostFunction::ElementAt => gen_element_at(scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::IndexOf => gen_index_of(scale, input_size),
+        ClarityCostFunction::IndexOf => gen_index_of(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
         ClarityCostFunction::ListCons => gen_list_cons(scale, input_size),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::Append => gen_append(scale, input_size),
+        ClarityCostFunction::Append => gen_append(scale, input_size, rng),
 
 
         /// Hash ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Hash160 => gen_hash("hash160", scale, input_size),
+        ClarityCostFunction::Hash160 => gen_hash("hash160", scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Sha256 => gen_hash("sha256", scale, input_size),
+        ClarityCostFunction::Sha256 => gen_hash("sha256", scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Sha512 => gen_hash("sha512", scale, input_size),
+        ClarityCostFunction::Sha512 => gen_hash("sha512", scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Sha512t256 => gen_hash("sha512/256", scale, input_size),
+        ClarityCostFunction::Sha512t256 => gen_hash("sha512/256", scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Keccak256 => gen_hash("keccak256", scale, input_size),
+        ClarityCostFunction::Keccak256 => gen_hash("keccak256", scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Secp256k1recover => gen_secp256k1("secp256k1-recover?", scale, false),
+        ClarityCostFunction::Secp256k1recover => gen_secp256k1("secp256k1-recover?", scale, false, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Secp256k1verify => gen_secp256k1("secp256k1-verify", scale, true),
+        ClarityCostFunction::Secp256k1verify => gen_secp256k1("secp256k1-verify", scale, true, rng),
 
         /// FT ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::CreateFt => gen_create_ft("define-fungible-token", scale),
+        ClarityCostFunction::CreateFt => gen_create_ft("define-fungible-token", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::FtMint => gen_ft_mint("ft-mint?", scale),
+        ClarityCostFunction::FtMint => gen_ft_mint("ft-mint?", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::FtTransfer => gen_ft_transfer("ft-transfer?", scale),
+        ClarityCostFunction::FtTransfer => gen_ft_transfer("ft-transfer?", scale, rng),
 
         /// reviewed: @reedrosenbluth   
-        ClarityCostFunction::FtBalance => gen_ft_balance("ft-get-balance", scale),
+        ClarityCostFunction::FtBalance => gen_ft_balance("ft-get-balance", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::FtSupply => gen_ft_supply("ft-get-supply", scale),
+        ClarityCostFunction::FtSupply => gen_ft_supply("ft-get-supply", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::FtBurn => gen_ft_burn("ft-burn?", scale),
+        ClarityCostFunction::FtBurn => gen_ft_burn("ft-burn?", scale, rng),
 
 
         /// NFT ////////////////////////////////
@@ -2572,26 +2715,30 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         /// cost_function: CreateNft
         /// input_size: size of asset type
         ///     `asset_type.size()`
-        ClarityCostFunction::CreateNft => unimplemented!(),
+        ClarityCostFunction::CreateNft => gen_create_nft(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::NftMint => gen_nft_mint(scale, input_size),
+        ClarityCostFunction::NftMint => gen_nft_mint(scale, input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::NftTransfer => gen_nft_transfer("nft-transfer?", scale, input_size),
+        ClarityCostFunction::NftTransfer => gen_nft_transfer("nft-transfer?", scale, input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::NftOwner => gen_nft_owner("nft-get-owner?", scale, input_size),
+        ClarityCostFunction::NftOwner => gen_nft_owner("nft-get-owner?", scale, input_size, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::NftBurn => gen_nft_burn("nft-burn?", scale, input_size),
+        ClarityCostFunction::NftBurn => gen_nft_burn("nft-burn?", scale, input_size, rng),
 
         /// Stacks ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::PoisonMicroblock => unimplemented!(), // don't need a gen for this
+        ClarityCostFunction::PoisonMicroblock => {
+            return Err(NoGenerator::CustomHarness(
+                "needs a microblock public key hash registered in the clarity db",
+            ))
+        }
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::BlockInfo => gen_get_block_info(scale),
+        ClarityCostFunction::BlockInfo => gen_get_block_info(scale, rng),
 
         /// reviewed: @reedrosenbluth
         ClarityCostFunction::StxBalance => gen_stx_get_balance(scale),
@@ -2602,36 +2749,36 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
 
         /// Option & result checks ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::IsSome => gen_optional("is-some", scale),
+        ClarityCostFunction::IsSome => gen_optional("is-some", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::IsNone => gen_optional("is-none", scale),
+        ClarityCostFunction::IsNone => gen_optional("is-none", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::IsOkay => gen_response("is-ok", scale),
+        ClarityCostFunction::IsOkay => gen_response("is-ok", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::IsErr => gen_response("is-err", scale),
+        ClarityCostFunction::IsErr => gen_response("is-err", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::DefaultTo => gen_default_to("default-to", scale),
+        ClarityCostFunction::DefaultTo => gen_default_to("default-to", scale, rng),
 
 
         /// Unwrap functions ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Unwrap => gen_unwrap("unwrap-panic", scale, false),
+        ClarityCostFunction::Unwrap => gen_unwrap("unwrap-panic", scale, false, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::UnwrapRet => gen_unwrap("unwrap!", scale, true),
+        ClarityCostFunction::UnwrapRet => gen_unwrap("unwrap!", scale, true, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::UnwrapErr => gen_unwrap_err("unwrap-err-panic", scale, false),
+        ClarityCostFunction::UnwrapErr => gen_unwrap_err("unwrap-err-panic", scale, false, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::UnwrapErrOrRet => gen_unwrap_err("unwrap-err!", scale, true),
+        ClarityCostFunction::UnwrapErrOrRet => gen_unwrap_err("unwrap-err!", scale, true, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::TryRet => gen_unwrap("try!", scale, false),
+        ClarityCostFunction::TryRet => gen_unwrap("try!", scale, false, rng),
 
 
         /// Map ////////////////////////////////
@@ -2639,13 +2786,13 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         /// cost_function: CreateMap
         /// input_size: sum of key type size and value type size
         ///     `u64::from(key_type.size()).cost_overflow_add(u64::from(value_type.size()))`
-        ClarityCostFunction::CreateMap => unimplemented!(),
+        ClarityCostFunction::CreateMap => gen_create_map(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::FetchEntry => gen_fetch_entry(scale, input_size), // map-get?
+        ClarityCostFunction::FetchEntry => gen_fetch_entry(scale, input_size, rng), // map-get?
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::SetEntry => gen_set_entry(scale, input_size),     // map-set
+        ClarityCostFunction::SetEntry => gen_set_entry(scale, input_size, rng),     // map-set
 
 
         /// Var ////////////////////////////////
@@ -2653,33 +2800,33 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         /// cost_function: CreateVar
         /// input_size: value type size
         ///     `value_type.size()`
-        ClarityCostFunction::CreateVar => unimplemented!(),
+        ClarityCostFunction::CreateVar => gen_create_var(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::FetchVar => gen_var_get(scale, input_size),
+        ClarityCostFunction::FetchVar => gen_var_get(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::SetVar => gen_var_set(scale, input_size),
+        ClarityCostFunction::SetVar => gen_var_set(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::BindName => gen_define_constant("define-constant-bench", scale), // used for define var and define function
+        ClarityCostFunction::BindName => gen_define_constant("define-constant-bench", scale, rng), // used for define var and define function
 
 
         /// Functions with single clarity value input ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Print => gen_print(scale, input_size),
+        ClarityCostFunction::Print => gen_print(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::SomeCons => gen_single_clar_value("some", scale, None),
+        ClarityCostFunction::SomeCons => gen_single_clar_value("some", scale, None, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::OkCons => gen_single_clar_value("ok", scale, None),
+        ClarityCostFunction::OkCons => gen_single_clar_value("ok", scale, None, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::ErrCons => gen_single_clar_value("err", scale, None),
+        ClarityCostFunction::ErrCons => gen_single_clar_value("err", scale, None, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Begin => gen_single_clar_value("begin", scale, None),
+        ClarityCostFunction::Begin => gen_single_clar_value("begin", scale, None, rng),
 
 
         /// Type Checking ////////////////////////////////
@@ -2687,30 +2834,30 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         ClarityCostFunction::InnerTypeCheckCost => gen_inner_type_check_cost(input_size),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::TypeParseStep => gen_type_parse_step(scale), // called by `parse_type_repr` in `signatures.rs` (takes in symbolic expression)
+        ClarityCostFunction::TypeParseStep => gen_type_parse_step(scale, rng), // called by `parse_type_repr` in `signatures.rs` (takes in symbolic expression)
 
 
         /// Uncategorized ////////////////////////////////
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::If => gen_if("if", scale),
+        ClarityCostFunction::If => gen_if("if", scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Asserts => gen_asserts("asserts!", scale),
+        ClarityCostFunction::Asserts => gen_asserts("asserts!", scale, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::Concat => gen_concat("concat", scale, input_size),
+        ClarityCostFunction::Concat => gen_concat("concat", scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::IntCast => gen_int_cast(scale),
+        ClarityCostFunction::IntCast => gen_int_cast(scale, rng),
 
         /// reviewed: @pavitthrap
-        ClarityCostFunction::Let => gen_let(scale, input_size),
+        ClarityCostFunction::Let => gen_let(scale, input_size, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::Match => gen_match(scale),
+        ClarityCostFunction::Match => gen_match(scale, rng),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::AsMaxLen => gen_as_max_len("as-max-len?", scale),
+        ClarityCostFunction::AsMaxLen => gen_as_max_len("as-max-len?", scale, rng),
 
         /// reviewed:
         ClarityCostFunction::UserFunctionApplication => gen_analysis_get_function_entry(input_size),
@@ -2722,23 +2869,39 @@ pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64) -> GenOut
         ClarityCostFunction::ContractOf => gen_contract_of(scale),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::PrincipalOf => gen_principal_of(scale),
+        ClarityCostFunction::PrincipalOf => gen_principal_of(scale, rng),
 
         /// reviewed: @reedrosenbluth
         ClarityCostFunction::AtBlock => gen_at_block(scale),
 
         /// reviewed: @reedrosenbluth
-        ClarityCostFunction::LoadContract => unimplemented!(), // called at start of execute_contract
-    }
+        ClarityCostFunction::LoadContract => {
+            // called at start of execute_contract
+            return Err(NoGenerator::CustomHarness(
+                "needs a contract already stored in the clarity db",
+            ))
+        }
+    };
+
+    Ok(output)
+}
+
+/// Like `try_gen`, but panics for cost functions that have no generator.
+pub fn gen(function: ClarityCostFunction, scale: u16, input_size: u64, seed: u64) -> GenOutput {
+    try_gen(function, scale, input_size, seed)
+        .unwrap_or_else(|reason| panic!("No generator for {}: {}", function, reason))
 }
 
 
 /// Returns tuple of optional setup clarity code, and "main" clarity code
+/// Seeded the same way as `gen`.
 pub fn gen_analysis_pass(
     function: AnalysisCostFunction,
     _scale: u16,
     input_size: u64,
+    seed: u64,
 ) -> GenOutput {
+    let rng = &mut StdRng::seed_from_u64(seed);
     match function {
         /// reviewed:
         AnalysisCostFunction::ReadOnly => gen_analysis_pass_read_only(input_size),
@@ -2747,7 +2910,7 @@ pub fn gen_analysis_pass(
         AnalysisCostFunction::TypeChecker => gen_analysis_pass_type_checker(input_size),
 
         /// reviewed:
-        AnalysisCostFunction::TraitChecker => gen_analysis_pass_trait_checker(input_size),
+        AnalysisCostFunction::TraitChecker => gen_analysis_pass_trait_checker(input_size, rng),
 
         /// reviewed:
         AnalysisCostFunction::ArithmeticOnlyChecker => {
@@ -1,16 +1,29 @@
-use std::{fs, io, path::PathBuf};
+use std::{
+    cell::Cell,
+    env, fmt, fs, hint, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 use blockstack_lib::{
-    chainstate::stacks::db::{MinerPaymentSchedule, StacksHeaderInfo},
+    chainstate::stacks::{
+        db::{MinerPaymentSchedule, StacksHeaderInfo},
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+    },
     types::{
         chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksBlockId, VRFSeed},
         proof::ClarityMarfTrieId,
     },
-    util::{db::FromRow, hash::Hash160},
+    util::{
+        db::FromRow,
+        hash::{to_hex, Hash160, Sha512Trunc256Sum},
+        vrf::{VRFPrivateKey, VRF},
+    },
     vm::database::HeadersDB,
 };
 
-use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, NO_PARAMS};
 
 pub struct TestHeadersDB;
 
@@ -53,38 +66,244 @@ impl HeadersDB for TestHeadersDB {
     }
 }
 
+/// Chainstate database `SimHeadersDB::new` opens.
+pub const DEFAULT_CHAINSTATE_PATH: &str = "./chainstate.sqlite";
+
+/// Blocks a chainstate needs for every benchmark, since `gen_get_block_info` reads
+/// block 5.
+pub const MIN_BLOCK_COUNT: u32 = 6;
+
+/// Tables `SimHeadersDB` reads, and the columns it needs from each.
+const REQUIRED_COLUMNS: [(&str, &[&str]); 2] = [
+    (
+        "block_headers",
+        &[
+            "version",
+            "total_burn",
+            "total_work",
+            "proof",
+            "parent_block",
+            "parent_microblock",
+            "parent_microblock_sequence",
+            "tx_merkle_root",
+            "state_index_root",
+            "microblock_pubkey_hash",
+            "block_hash",
+            "index_block_hash",
+            "block_height",
+            "index_root",
+            "consensus_hash",
+            "burn_header_hash",
+            "burn_header_height",
+            "burn_header_timestamp",
+            "parent_block_id",
+            "cost",
+            "block_size",
+        ],
+    ),
+    (
+        "payments",
+        &[
+            "address",
+            "block_hash",
+            "consensus_hash",
+            "parent_block_hash",
+            "parent_consensus_hash",
+            "coinbase",
+            "tx_fees_anchored",
+            "tx_fees_streamed",
+            "stx_burns",
+            "burnchain_commit_burn",
+            "burnchain_sortition_burn",
+            "miner",
+            "stacks_block_height",
+            "index_block_hash",
+            "vtxindex",
+        ],
+    ),
+];
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound(PathBuf),
+    Io(PathBuf, io::Error),
+    Sqlite(PathBuf, rusqlite::Error),
+    MissingTable(PathBuf, &'static str),
+    MissingColumn {
+        path: PathBuf,
+        table: &'static str,
+        column: &'static str,
+    },
+    /// a row that `StacksHeaderInfo` or `MinerPaymentSchedule` can't decode
+    BadRow {
+        path: PathBuf,
+        table: &'static str,
+        error: String,
+    },
+    TooFewBlocks {
+        path: PathBuf,
+        found: u32,
+        required: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotFound(path) => write!(
+                f,
+                "no chainstate at {}, run from the repository root or create one with `cost-bench make-chainstate`",
+                path.display()
+            ),
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Sqlite(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::MissingTable(path, table) => {
+                write!(f, "{}: no {} table", path.display(), table)
+            }
+            Error::MissingColumn {
+                path,
+                table,
+                column,
+            } => write!(f, "{}: {} has no {} column", path.display(), table, column),
+            Error::BadRow { path, table, error } => {
+                write!(f, "{}: bad row in {}: {}", path.display(), table, error)
+            }
+            Error::TooFewBlocks {
+                path,
+                found,
+                required,
+            } => write!(
+                f,
+                "{}: {} blocks, but benchmarks need at least {}",
+                path.display(),
+                found,
+                required
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Headers of a chainstate database, opened read-only so benchmarks can't change it.
 pub struct SimHeadersDB {
+    path: PathBuf,
     conn: Connection,
 }
 
 impl SimHeadersDB {
+    /// Opens `DEFAULT_CHAINSTATE_PATH`, panicking with the reason if that fails. Run
+    /// `validate` on it before benchmarking, so that a bad chainstate is reported up
+    /// front rather than as a panic in the middle of a benchmark.
     pub fn new() -> Self {
-        let db_path = "./chainstate.sqlite";
-
-        Self::new_with_path(db_path)
-    }
-
-    fn new_with_path(db_path: &str) -> Self {
-        let open_flags = match fs::metadata(&db_path) {
-            Err(e) => {
-                if e.kind() == io::ErrorKind::NotFound {
-                    // need to create
-                    if let Some(dirp) = PathBuf::from(db_path).parent() {
-                        fs::create_dir_all(dirp).unwrap_or_else(|e| {
-                            panic!("Failed to create {:?}: {:?}", dirp, &e);
-                        });
-                    }
-                    OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE
-                } else {
-                    panic!("FATAL: could not stat {}", db_path);
-                }
+        Self::open(DEFAULT_CHAINSTATE_PATH).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Opens the chainstate database at `path` read-only, and checks it has the
+    /// tables and columns the `HeadersDB` queries need.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SimHeadersDB, Error> {
+        let path = path.as_ref().to_path_buf();
+        if !path.is_file() {
+            return Err(Error::NotFound(path));
+        }
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| Error::Sqlite(path.clone(), e))?;
+        let headers_db = SimHeadersDB { path, conn };
+        headers_db.check_schema()?;
+        Ok(headers_db)
+    }
+
+    fn check_schema(&self) -> Result<(), Error> {
+        for (table, columns) in REQUIRED_COLUMNS.iter() {
+            let mut stmt = self
+                .conn
+                .prepare(&format!("PRAGMA table_info({})", table))
+                .map_err(|e| self.sqlite_error(e))?;
+            let found = stmt
+                .query_map(NO_PARAMS, |row| row.get::<_, String>("name"))
+                .and_then(|names| names.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| self.sqlite_error(e))?;
+            if found.is_empty() {
+                return Err(Error::MissingTable(self.path.clone(), table));
             }
-            Ok(_md) => OpenFlags::SQLITE_OPEN_READ_WRITE,
-        };
+            if let Some(column) = columns.iter().find(|c| !found.iter().any(|f| f == *c)) {
+                return Err(Error::MissingColumn {
+                    path: self.path.clone(),
+                    table,
+                    column,
+                });
+            }
+        }
+        Ok(())
+    }
 
-        let conn = Connection::open_with_flags(db_path, open_flags).unwrap();
+    /// Checks there are at least `min_blocks` blocks, and that every block header
+    /// and miner payment decodes. Returns the number of blocks.
+    pub fn validate(&self, min_blocks: u32) -> Result<u32, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT * FROM block_headers")
+            .map_err(|e| self.sqlite_error(e))?;
+        let mut rows = stmt.query(NO_PARAMS).map_err(|e| self.sqlite_error(e))?;
+        let mut found = 0;
+        while let Some(row) = rows.next().map_err(|e| self.sqlite_error(e))? {
+            StacksHeaderInfo::from_row(row).map_err(|e| self.bad_row("block_headers", e))?;
+            found += 1;
+        }
 
-        SimHeadersDB { conn }
+        let mut stmt = self
+            .conn
+            .prepare("SELECT * FROM payments WHERE miner = 1")
+            .map_err(|e| self.sqlite_error(e))?;
+        let mut rows = stmt.query(NO_PARAMS).map_err(|e| self.sqlite_error(e))?;
+        while let Some(row) = rows.next().map_err(|e| self.sqlite_error(e))? {
+            MinerPaymentSchedule::from_row(row).map_err(|e| self.bad_row("payments", e))?;
+        }
+
+        if found < min_blocks {
+            return Err(Error::TooFewBlocks {
+                path: self.path.clone(),
+                found,
+                required: min_blocks,
+            });
+        }
+        Ok(found)
+    }
+
+    /// Burn block height and header hash of every Stacks block, by burn height.
+    pub fn burn_blocks(&self) -> Result<Vec<(u32, BurnchainHeaderHash)>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT burn_header_height, burn_header_hash FROM block_headers
+                 ORDER BY burn_header_height",
+            )
+            .map_err(|e| self.sqlite_error(e))?;
+        let rows = stmt
+            .query_map(NO_PARAMS, |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| self.sqlite_error(e))?;
+        rows.into_iter()
+            .map(|(height, hash)| {
+                BurnchainHeaderHash::from_hex(&hash)
+                    .map(|hash| (height, hash))
+                    .map_err(|e| self.bad_row("block_headers", e))
+            })
+            .collect()
+    }
+
+    fn sqlite_error(&self, e: rusqlite::Error) -> Error {
+        Error::Sqlite(self.path.clone(), e)
+    }
+
+    fn bad_row<E: fmt::Debug>(&self, table: &'static str, e: E) -> Error {
+        Error::BadRow {
+            path: self.path.clone(),
+            table,
+            error: format!("{:?}", e),
+        }
     }
 }
 
@@ -121,6 +340,154 @@ impl HeadersDB for SimHeadersDB {
     }
 }
 
+/// Environment variable holding a delay, in microseconds, that benchmarks add to
+/// every header lookup. See `InstrumentedHeadersDB`.
+pub const HEADERS_LATENCY_ENV_VAR: &str = "COST_BENCH_HEADERS_LATENCY_US";
+
+/// The delay given by `COST_BENCH_HEADERS_LATENCY_US`, or none if it is unset.
+pub fn latency_from_env() -> Duration {
+    match env::var(HEADERS_LATENCY_ENV_VAR) {
+        Ok(micros) => Duration::from_micros(micros.parse().unwrap_or_else(|_| {
+            panic!(
+                "Invalid {}: '{}' is not a number of microseconds",
+                HEADERS_LATENCY_ENV_VAR, micros
+            )
+        })),
+        Err(_) => Duration::from_micros(0),
+    }
+}
+
+/// The lookups of `HeadersDB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadersDBMethod {
+    StacksBlockHeaderHash,
+    BurnHeaderHash,
+    BurnBlockTime,
+    BurnBlockHeight,
+    VrfSeed,
+    MinerAddress,
+}
+
+impl HeadersDBMethod {
+    pub const ALL: [HeadersDBMethod; 6] = [
+        HeadersDBMethod::StacksBlockHeaderHash,
+        HeadersDBMethod::BurnHeaderHash,
+        HeadersDBMethod::BurnBlockTime,
+        HeadersDBMethod::BurnBlockHeight,
+        HeadersDBMethod::VrfSeed,
+        HeadersDBMethod::MinerAddress,
+    ];
+
+    /// Name of the `HeadersDB` method.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeadersDBMethod::StacksBlockHeaderHash => "get_stacks_block_header_hash_for_block",
+            HeadersDBMethod::BurnHeaderHash => "get_burn_header_hash_for_block",
+            HeadersDBMethod::BurnBlockTime => "get_burn_block_time_for_block",
+            HeadersDBMethod::BurnBlockHeight => "get_burn_block_height_for_block",
+            HeadersDBMethod::VrfSeed => "get_vrf_seed_for_block",
+            HeadersDBMethod::MinerAddress => "get_miner_address",
+        }
+    }
+}
+
+impl fmt::Display for HeadersDBMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Wraps a `HeadersDB`, counting the calls to each method and delaying every call
+/// by a fixed latency. The delay is a busy wait, since sleeping can't resolve
+/// microseconds, so it models a headers DB that has to go to disk.
+pub struct InstrumentedHeadersDB<H> {
+    inner: H,
+    latency: Duration,
+    counts: [Cell<u64>; 6],
+}
+
+impl<H: HeadersDB> InstrumentedHeadersDB<H> {
+    pub fn new(inner: H, latency: Duration) -> InstrumentedHeadersDB<H> {
+        InstrumentedHeadersDB {
+            inner,
+            latency,
+            counts: Default::default(),
+        }
+    }
+
+    pub fn count(&self, method: HeadersDBMethod) -> u64 {
+        self.counts[method as usize].get()
+    }
+
+    pub fn total_count(&self) -> u64 {
+        self.counts.iter().map(Cell::get).sum()
+    }
+
+    /// Methods called since the last reset, with their call counts.
+    pub fn counts(&self) -> Vec<(HeadersDBMethod, u64)> {
+        HeadersDBMethod::ALL
+            .iter()
+            .map(|method| (*method, self.count(*method)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    pub fn reset_counts(&self) {
+        for count in self.counts.iter() {
+            count.set(0);
+        }
+    }
+
+    fn record(&self, method: HeadersDBMethod) {
+        let count = &self.counts[method as usize];
+        count.set(count.get() + 1);
+        if self.latency > Duration::from_micros(0) {
+            let start = Instant::now();
+            while start.elapsed() < self.latency {
+                hint::spin_loop();
+            }
+        }
+    }
+}
+
+impl<H: HeadersDB> HeadersDB for InstrumentedHeadersDB<H> {
+    fn get_stacks_block_header_hash_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<BlockHeaderHash> {
+        self.record(HeadersDBMethod::StacksBlockHeaderHash);
+        self.inner.get_stacks_block_header_hash_for_block(id_bhh)
+    }
+
+    fn get_burn_header_hash_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<BurnchainHeaderHash> {
+        self.record(HeadersDBMethod::BurnHeaderHash);
+        self.inner.get_burn_header_hash_for_block(id_bhh)
+    }
+
+    fn get_burn_block_time_for_block(&self, id_bhh: &StacksBlockId) -> Option<u64> {
+        self.record(HeadersDBMethod::BurnBlockTime);
+        self.inner.get_burn_block_time_for_block(id_bhh)
+    }
+
+    fn get_burn_block_height_for_block(&self, id_bhh: &StacksBlockId) -> Option<u32> {
+        self.record(HeadersDBMethod::BurnBlockHeight);
+        self.inner.get_burn_block_height_for_block(id_bhh)
+    }
+
+    fn get_vrf_seed_for_block(&self, id_bhh: &StacksBlockId) -> Option<VRFSeed> {
+        self.record(HeadersDBMethod::VrfSeed);
+        self.inner.get_vrf_seed_for_block(id_bhh)
+    }
+
+    fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress> {
+        self.record(HeadersDBMethod::MinerAddress);
+        self.inner.get_miner_address(id_bhh)
+    }
+}
+
 fn get_stacks_header_info(conn: &Connection, id_bhh: &StacksBlockId) -> Option<StacksHeaderInfo> {
     conn.query_row(
         "SELECT * FROM block_headers WHERE index_block_hash = ?",
@@ -140,3 +507,175 @@ fn get_miner_info(conn: &Connection, id_bhh: &StacksBlockId) -> Option<MinerPaym
     .optional()
     .expect("Unexpected SQL failure querying payment table")
 }
+
+/// The columns of a chainstate's `block_headers` and `payments` tables that
+/// `StacksHeaderInfo::from_row` and `MinerPaymentSchedule::from_row` read.
+const CHAINSTATE_SCHEMA: &str = "
+    CREATE TABLE block_headers(
+        version INTEGER NOT NULL,
+        total_burn TEXT NOT NULL,
+        total_work TEXT NOT NULL,
+        proof TEXT NOT NULL,
+        parent_block TEXT NOT NULL,
+        parent_microblock TEXT NOT NULL,
+        parent_microblock_sequence INTEGER NOT NULL,
+        tx_merkle_root TEXT NOT NULL,
+        state_index_root TEXT NOT NULL,
+        microblock_pubkey_hash TEXT NOT NULL,
+        block_hash TEXT NOT NULL,
+        index_block_hash TEXT UNIQUE NOT NULL,
+        block_height INTEGER NOT NULL,
+        index_root TEXT NOT NULL,
+        consensus_hash TEXT UNIQUE NOT NULL,
+        burn_header_hash TEXT NOT NULL,
+        burn_header_height INT NOT NULL,
+        burn_header_timestamp INT NOT NULL,
+        parent_block_id TEXT NOT NULL,
+        cost TEXT NOT NULL,
+        block_size TEXT NOT NULL,
+        PRIMARY KEY(consensus_hash,block_hash)
+    );
+    CREATE TABLE payments(
+        address TEXT NOT NULL,
+        block_hash TEXT NOT NULL,
+        consensus_hash TEXT NOT NULL,
+        parent_block_hash TEXT NOT NULL,
+        parent_consensus_hash TEXT NOT NULL,
+        coinbase TEXT NOT NULL,
+        tx_fees_anchored TEXT NOT NULL,
+        tx_fees_streamed TEXT NOT NULL,
+        stx_burns TEXT NOT NULL,
+        burnchain_commit_burn INT NOT NULL,
+        burnchain_sortition_burn INT NOT NULL,
+        miner INT NOT NULL,
+        stacks_block_height INTEGER NOT NULL,
+        index_block_hash TEXT NOT NULL,
+        vtxindex INT NOT NULL
+    );";
+
+/// Cost of an empty block, as the node stores it.
+const ZERO_COST_JSON: &str =
+    r#"{"write_length":0,"write_count":0,"read_length":0,"read_count":0,"runtime":0}"#;
+
+const COINBASE: u64 = 1_020_400_000;
+const BURN_PER_BLOCK: u64 = 20_000;
+
+/// Builds a chainstate database of synthetic blocks, for `SimHeadersDB` to read
+/// in place of `./chainstate.sqlite`.
+///
+/// Blocks form one chain from a genesis block at height 0, each one burn block
+/// after its parent. Hashes, VRF proofs and miner addresses are drawn from an RNG
+/// seeded with `seed`, so the same builder always writes the same database.
+#[derive(Debug, Clone)]
+pub struct ChainstateBuilder {
+    pub block_count: u32,
+    pub first_burn_height: u32,
+    pub first_burn_timestamp: u64,
+    /// seconds between consecutive burn blocks
+    pub burn_block_interval: u64,
+    pub seed: u64,
+}
+
+impl ChainstateBuilder {
+    pub fn new(block_count: u32) -> ChainstateBuilder {
+        ChainstateBuilder {
+            block_count,
+            first_burn_height: 200,
+            first_burn_timestamp: 1_624_308_165,
+            burn_block_interval: 600,
+            seed: 0,
+        }
+    }
+
+    /// Writes the blocks to a new database at `db_path`, and opens it read-only.
+    pub fn build<P: AsRef<Path>>(&self, db_path: P) -> Result<SimHeadersDB, Error> {
+        let path = db_path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::Io(path.clone(), e))?;
+        }
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+        let mut conn = Connection::open_with_flags(&path, flags)
+            .map_err(|e| Error::Sqlite(path.clone(), e))?;
+        self.write_blocks(&mut conn)
+            .map_err(|e| Error::Sqlite(path.clone(), e))?;
+        drop(conn);
+        SimHeadersDB::open(path)
+    }
+
+    fn write_blocks(&self, conn: &mut Connection) -> rusqlite::Result<()> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let tx = conn.transaction()?;
+        tx.execute_batch(CHAINSTATE_SCHEMA)?;
+
+        let vrf_key = VRFPrivateKey::from_bytes(&rng.gen::<[u8; 32]>()).unwrap();
+        let mut parent_block = BlockHeaderHash([0xff; 32]);
+        let mut parent_consensus_hash = [0u8; 20];
+        let mut parent_block_id = StacksBlockId::sentinel();
+
+        for height in 0..self.block_count {
+            let block_hash = BlockHeaderHash(rng.gen());
+            let consensus_hash: [u8; 20] = rng.gen();
+            // as `StacksBlockHeader::make_index_block_hash`
+            let index_block_hash = StacksBlockId(
+                Sha512Trunc256Sum::from_data(&[&block_hash.0[..], &consensus_hash[..]].concat()).0,
+            );
+            let proof = VRF::prove(&vrf_key, &block_hash.0.to_vec());
+            let miner =
+                StacksAddress::new(C32_ADDRESS_VERSION_TESTNET_SINGLESIG, Hash160(rng.gen()));
+            let burn_header_hash = BurnchainHeaderHash(rng.gen());
+
+            tx.execute(
+                "INSERT INTO block_headers VALUES
+                     (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+                rusqlite::params![
+                    0,
+                    (BURN_PER_BLOCK * height as u64).to_string(),
+                    height.to_string(),
+                    proof.to_hex(),
+                    parent_block.to_hex(),
+                    to_hex(&[0; 32]),
+                    0,
+                    to_hex(&rng.gen::<[u8; 32]>()),
+                    to_hex(&rng.gen::<[u8; 32]>()),
+                    to_hex(&rng.gen::<[u8; 20]>()),
+                    block_hash.to_hex(),
+                    index_block_hash.to_hex(),
+                    height,
+                    to_hex(&rng.gen::<[u8; 32]>()),
+                    to_hex(&consensus_hash),
+                    burn_header_hash.to_hex(),
+                    self.first_burn_height + height,
+                    (self.first_burn_timestamp + self.burn_block_interval * height as u64) as i64,
+                    parent_block_id.to_hex(),
+                    ZERO_COST_JSON,
+                    "0",
+                ],
+            )?;
+
+            // like a real chainstate, the genesis block has no miner
+            if height > 0 {
+                tx.execute(
+                    "INSERT INTO payments VALUES
+                         (?1, ?2, ?3, ?4, ?5, ?6, '0', '0', '0', ?7, ?7, 1, ?8, ?9, 0)",
+                    rusqlite::params![
+                        miner.to_string(),
+                        block_hash.to_hex(),
+                        to_hex(&consensus_hash),
+                        parent_block.to_hex(),
+                        to_hex(&parent_consensus_hash),
+                        COINBASE.to_string(),
+                        BURN_PER_BLOCK as i64,
+                        height,
+                        index_block_hash.to_hex(),
+                    ],
+                )?;
+            }
+
+            parent_block = block_hash;
+            parent_consensus_hash = consensus_hash;
+            parent_block_id = index_block_hash;
+        }
+
+        tx.commit()
+    }
+}
@@ -1,5 +1,21 @@
+pub mod allocations;
+pub mod audit;
+pub mod bootstrap;
+pub mod burn_state_db;
+pub mod compare;
+pub mod corpus;
+pub mod cost_probe;
+pub mod costs_check;
+pub mod coverage;
 pub mod generators;
 pub mod headers_db;
+pub mod proposal;
+pub mod registry;
+pub mod regression;
+pub mod run_metadata;
+pub mod search;
+pub mod storage;
+pub mod storage_dimensions;
 
 #[macro_use]
 extern crate lazy_static;
@@ -0,0 +1,624 @@
+//! Turns fitted cost models into a costs boot contract and a markdown table of the
+//! changes, in the format `proposal/make_cost_functions.py` produces.
+
+use crate::bootstrap::{FitInterval, CONFIDENCE_LEVEL};
+use crate::registry;
+use crate::regression::{Fit, Model};
+use crate::run_metadata::{self, RunMetadata};
+use blockstack_lib::core::BLOCK_LIMIT_MAINNET;
+use blockstack_lib::vm::costs::cost_functions::ClarityCostFunction;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use self::Dim::{Const, Linear};
+
+/// Wall time the runtime block limit should correspond to, by default.
+pub const DEFAULT_TARGET_WALL_TIME_SECS: f64 = 30.0;
+
+/// Constants of the placeholder costs that new costs are compared against, which
+/// functions without a fit keep.
+pub const PLACEHOLDER_COST: u64 = 1000;
+
+/// Cost functions that are assigned the most expensive fit within their group, so
+/// that equivalent operations cost the same.
+pub const MATCHED_FUNCTIONS: &[&[&str]] = &[
+    &["cost_ft_burn", "cost_ft_transfer"],
+    &["cost_nft_transfer", "cost_nft_burn"],
+    &["cost_le", "cost_ge", "cost_geq", "cost_leq"],
+    &["cost_add", "cost_sub"],
+    &["cost_mul", "cost_div"],
+    &["cost_ok_cons", "cost_err_cons", "cost_some_cons"],
+    &["cost_or", "cost_and"],
+];
+
+/// How fitted nanoseconds are converted into runtime cost units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scaling {
+    /// runtime block limit, in cost units
+    pub runtime_limit: u64,
+    pub target_wall_time_ns: f64,
+    /// times a benchmark iteration runs the measured operation, by cost function
+    pub executions_per_sample: BTreeMap<String, u64>,
+}
+
+impl Scaling {
+    /// Scaling with no known executions per sample, which only converts the time of
+    /// single executions, see `runtime_per_ns`.
+    pub fn with_target_wall_time(seconds: f64) -> Scaling {
+        Scaling {
+            runtime_limit: BLOCK_LIMIT_MAINNET.runtime,
+            target_wall_time_ns: seconds * 1e9,
+            executions_per_sample: BTreeMap::new(),
+        }
+    }
+
+    /// This scaling for fits of benchmarks that ran each function
+    /// `executions_per_sample` times per iteration.
+    pub fn with_executions_per_sample(
+        self,
+        executions_per_sample: BTreeMap<String, u64>,
+    ) -> Scaling {
+        Scaling {
+            executions_per_sample,
+            ..self
+        }
+    }
+
+    /// Cost units per nanosecond of a single execution.
+    pub fn runtime_per_ns(&self) -> f64 {
+        self.runtime_limit as f64 / self.target_wall_time_ns
+    }
+
+    /// Cost units per nanosecond of a benchmark iteration of `function`.
+    pub fn factor(&self, function: &str) -> f64 {
+        self.runtime_per_ns() / self.executions(function) as f64
+    }
+
+    /// Panics if `function`'s executions per sample aren't known, rather than
+    /// pricing it at the wrong scale.
+    fn executions(&self, function: &str) -> u64 {
+        *self
+            .executions_per_sample
+            .get(function)
+            .unwrap_or_else(|| panic!("executions per sample of {} aren't known", function))
+    }
+
+    fn scale(&self, function: &str, nanoseconds: f64) -> u64 {
+        (nanoseconds * self.factor(function)) as u64
+    }
+}
+
+/// Executions of the measured operation per benchmark iteration for each of
+/// `functions`: the scale recorded by the run its results belong to, or else the
+/// scale it is registered with. Fails naming the functions with neither.
+pub fn executions_per_sample<'a, I>(
+    functions: I,
+    runs: &[RunMetadata],
+) -> Result<BTreeMap<String, u64>, String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let current = run_metadata::current_runs(runs);
+    let mut executions = BTreeMap::new();
+    let mut unknown = vec![];
+    for function in functions {
+        let recorded = current
+            .iter()
+            .rev()
+            .find_map(|run| run.benchmarks.get(function))
+            .map(|record| record.scale);
+        match recorded.or_else(|| registry::spec_named(function).map(|spec| spec.scale)) {
+            Some(scale) => {
+                executions.insert(function.clone(), scale as u64);
+            }
+            None => unknown.push(function.as_str()),
+        }
+    }
+    match unknown.is_empty() {
+        true => Ok(executions),
+        false => Err(format!("no known scale for {}", unknown.join(", "))),
+    }
+}
+
+/// A read or write dimension of a special cost function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dim {
+    Const(u64),
+    /// one unit per unit of input size, plus one
+    Linear,
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dim::Const(x) => write!(f, "u{}", x),
+            Dim::Linear => write!(f, "(linear n u1 u1)"),
+        }
+    }
+}
+
+/// A cost function that reads or writes storage, which has fixed read and write
+/// dimensions alongside its fitted runtime.
+struct Special {
+    function: &'static str,
+    /// runtime is linear in the input size, otherwise constant
+    linear: bool,
+    /// write_length, write_count, read_count and read_length
+    dims: [Dim; 4],
+    /// comment on read_count
+    note: Option<&'static str>,
+}
+
+const fn special(function: &'static str, linear: bool, dims: [Dim; 4]) -> Special {
+    Special {
+        function,
+        linear,
+        dims,
+        note: None,
+    }
+}
+
+const SPECIAL_FUNCTIONS: &[Special] = &[
+    special(
+        "cost_at_block",
+        false,
+        [Const(0), Const(0), Const(1), Const(1)],
+    ),
+    special(
+        "cost_create_ft",
+        false,
+        [Const(1), Const(2), Const(0), Const(0)],
+    ),
+    special(
+        "cost_block_info",
+        false,
+        [Const(0), Const(0), Const(1), Const(1)],
+    ),
+    special(
+        "cost_stx_balance",
+        false,
+        [Const(0), Const(0), Const(1), Const(1)],
+    ),
+    special(
+        "cost_stx_transfer",
+        false,
+        [Const(1), Const(1), Const(1), Const(1)],
+    ),
+    special(
+        "cost_ft_mint",
+        false,
+        [Const(1), Const(2), Const(2), Const(1)],
+    ),
+    special(
+        "cost_ft_transfer",
+        false,
+        [Const(1), Const(2), Const(2), Const(1)],
+    ),
+    special(
+        "cost_ft_balance",
+        false,
+        [Const(0), Const(0), Const(1), Const(1)],
+    ),
+    special(
+        "cost_ft_get_supply",
+        false,
+        [Const(0), Const(0), Const(1), Const(1)],
+    ),
+    special(
+        "cost_ft_burn",
+        false,
+        [Const(1), Const(2), Const(2), Const(1)],
+    ),
+    special(
+        "poison_microblock",
+        false,
+        [Const(1), Const(1), Const(1), Const(1)],
+    ),
+    special(
+        "cost_analysis_storage",
+        true,
+        [Linear, Const(1), Const(1), Const(1)],
+    ),
+    special(
+        "cost_analysis_use_trait_entry",
+        true,
+        [Linear, Const(0), Const(1), Linear],
+    ),
+    special(
+        "cost_analysis_get_function_entry",
+        true,
+        [Const(0), Const(0), Const(1), Linear],
+    ),
+    Special {
+        note: Some("set to 3 because of the associated metadata loads"),
+        ..special(
+            "cost_load_contract",
+            true,
+            [Const(0), Const(0), Const(3), Linear],
+        )
+    },
+    special(
+        "cost_create_map",
+        true,
+        [Linear, Const(1), Const(0), Const(0)],
+    ),
+    special(
+        "cost_create_var",
+        true,
+        [Linear, Const(2), Const(0), Const(0)],
+    ),
+    special(
+        "cost_create_nft",
+        true,
+        [Linear, Const(1), Const(0), Const(0)],
+    ),
+    special(
+        "cost_fetch_entry",
+        true,
+        [Const(0), Const(0), Const(1), Linear],
+    ),
+    special(
+        "cost_set_entry",
+        true,
+        [Linear, Const(1), Const(1), Const(0)],
+    ),
+    special(
+        "cost_fetch_var",
+        true,
+        [Const(0), Const(0), Const(1), Linear],
+    ),
+    special("cost_set_var", true, [Linear, Const(1), Const(1), Const(0)]),
+    special(
+        "cost_contract_storage",
+        true,
+        [Linear, Const(1), Const(0), Const(0)],
+    ),
+    special(
+        "cost_nft_mint",
+        true,
+        [Const(1), Const(1), Const(1), Const(1)],
+    ),
+    special(
+        "cost_nft_transfer",
+        true,
+        [Const(1), Const(1), Const(1), Const(1)],
+    ),
+    special(
+        "cost_nft_owner",
+        true,
+        [Const(0), Const(0), Const(1), Const(1)],
+    ),
+    special(
+        "cost_nft_burn",
+        true,
+        [Const(1), Const(1), Const(1), Const(1)],
+    ),
+];
+
+fn lookup_special(function: &str) -> Option<&'static Special> {
+    SPECIAL_FUNCTIONS.iter().find(|s| s.function == function)
+}
+
+/// Runtime cost function in cost units, before it is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScaledCost {
+    pub model: Model,
+    pub a: u64,
+    pub b: u64,
+}
+
+impl ScaledCost {
+    /// Scales `fit`. Slopes are at least 1 so no non-constant cost is free, and
+    /// constant costs are at least 1. Storage functions keep a fixed shape: constant
+    /// or linear, whatever family the runtime fit used.
+    pub fn new(function: &str, fit: &Fit, scaling: &Scaling) -> ScaledCost {
+        let a = scaling.scale(function, fit.a).max(1);
+        let b = scaling.scale(function, fit.b);
+        let model = match lookup_special(function) {
+            Some(special) if special.linear => Model::Linear,
+            Some(_) => {
+                return ScaledCost {
+                    model: Model::Constant,
+                    a: 0,
+                    b,
+                }
+            }
+            None => fit.model,
+        };
+        match model {
+            Model::Constant => ScaledCost {
+                model,
+                a: 0,
+                b: b.max(1),
+            },
+            _ => ScaledCost { model, a, b },
+        }
+    }
+
+    /// The placeholder cost of a function without a fit: linear, unless its storage
+    /// dimensions fix it as constant.
+    pub fn placeholder(function: &str) -> ScaledCost {
+        match lookup_special(function) {
+            Some(special) if !special.linear => ScaledCost {
+                model: Model::Constant,
+                a: 0,
+                b: PLACEHOLDER_COST,
+            },
+            _ => ScaledCost {
+                model: Model::Linear,
+                a: PLACEHOLDER_COST,
+                b: PLACEHOLDER_COST,
+            },
+        }
+    }
+
+    /// The runtime expression used in the contract.
+    pub fn clarity_expr(&self) -> String {
+        match self.model {
+            Model::Constant => format!("u{}", self.b),
+            Model::Linear => format!("(linear n u{} u{})", self.a, self.b),
+            Model::LogN => format!("(logn n u{} u{})", self.a, self.b),
+            Model::NLogN => format!("(nlogn n u{} u{})", self.a, self.b),
+        }
+    }
+
+    /// Evaluates the cost function the way the contract does, with integer `log2`.
+    pub fn evaluate(&self, n: u64) -> u64 {
+        let log2 = |n: u64| {
+            if n == 0 {
+                0
+            } else {
+                63 - n.leading_zeros() as u64
+            }
+        };
+        match self.model {
+            Model::Constant => self.b,
+            Model::Linear => self.a * n + self.b,
+            Model::LogN => self.a * log2(n) + self.b,
+            Model::NLogN => self.a * n * log2(n) + self.b,
+        }
+    }
+}
+
+/// Replaces the fit of every function in a `MATCHED_FUNCTIONS` group with the
+/// largest `a` and `b` found in the group. They are compared per execution, since
+/// the functions may have been benchmarked at different scales.
+pub fn apply_matched_functions(
+    fits: &BTreeMap<String, Fit>,
+    scaling: &Scaling,
+) -> BTreeMap<String, Fit> {
+    let mut matched = fits.clone();
+    for group in MATCHED_FUNCTIONS {
+        let per_execution: Vec<(f64, f64)> = group
+            .iter()
+            .filter_map(|f| {
+                let fit = fits.get(*f)?;
+                let executions = scaling.executions(f) as f64;
+                Some((fit.a / executions, fit.b / executions))
+            })
+            .collect();
+        let a = per_execution.iter().map(|(a, _)| *a).fold(0.0, f64::max);
+        let b = per_execution.iter().map(|(_, b)| *b).fold(0.0, f64::max);
+        for function in group.iter() {
+            if let Some(fit) = matched.get_mut(*function) {
+                let executions = scaling.executions(function) as f64;
+                fit.a = a * executions;
+                fit.b = b * executions;
+            }
+        }
+    }
+    matched
+}
+
+/// The `define-read-only` for one cost function.
+pub fn make_clarity_cost_function(function: &str, fit: &Fit, scaling: &Scaling) -> String {
+    define_cost_function(function, &ScaledCost::new(function, fit, scaling))
+}
+
+fn define_cost_function(function: &str, cost: &ScaledCost) -> String {
+    match lookup_special(function) {
+        Some(special) => {
+            let read_count_note = match special.note {
+                Some(note) => format!("        ;; {}\n", note),
+                None => String::new(),
+            };
+            format!(
+                "(define-read-only ({} (n uint))
+    {{
+        runtime: {},
+        write_length: {},
+        write_count: {},
+{}        read_count: {},
+        read_length: {}
+    }})
+",
+                function,
+                cost.clarity_expr(),
+                special.dims[0],
+                special.dims[1],
+                read_count_note,
+                special.dims[2],
+                special.dims[3]
+            )
+        }
+        None => format!(
+            "(define-read-only ({} (n uint))
+    (runtime {}))
+",
+            function,
+            cost.clarity_expr()
+        ),
+    }
+}
+
+/// The markdown row comparing one cost function against the `PLACEHOLDER_COST`
+/// costs it replaces.
+pub fn make_clarity_cost_table_row(function: &str, fit: &Fit, scaling: &Scaling) -> String {
+    let cost = ScaledCost::new(function, fit, scaling);
+    let format = |a: u64, b: u64| match cost.model {
+        Model::Constant => format!("f(x) := {}", b),
+        Model::Linear => format!("f(x) := {}*x + {}", a, b),
+        Model::LogN => format!("f(x) := {}*log(x) + {}", a, b),
+        Model::NLogN => format!("f(x) := {}*x*log(x) + {}", a, b),
+    };
+    format!(
+        "| {} | {} | {} |",
+        function,
+        format(cost.a, cost.b),
+        format(PLACEHOLDER_COST, PLACEHOLDER_COST)
+    )
+}
+
+/// The bootstrapped interval of a cost function in cost units, e.g.
+/// `a 3..5, b 100..140`. Slopes that can't be told from zero are flagged, since
+/// the runtime they add is noise.
+pub fn make_interval_cell(
+    function: &str,
+    fit: &Fit,
+    interval: &FitInterval,
+    scaling: &Scaling,
+) -> String {
+    let cost = ScaledCost::new(function, fit, scaling);
+    let b = format!(
+        "b {}..{}",
+        scaling.scale(function, interval.b.lower),
+        scaling.scale(function, interval.b.upper)
+    );
+    match cost.model {
+        Model::Constant => b,
+        _ if interval.slope_undetermined(fit.model) => format!(
+            "a {}..{} (may be 0), {}",
+            scaling.scale(function, interval.a.lower),
+            scaling.scale(function, interval.a.upper),
+            b
+        ),
+        _ => format!(
+            "a {}..{}, {}",
+            scaling.scale(function, interval.a.lower),
+            scaling.scale(function, interval.a.upper),
+            b
+        ),
+    }
+}
+
+const CLAR_HEADER: &str = "
+;; the .costs-2 contract
+
+;; Helper Functions
+
+;; Return a Cost Specification with just a runtime cost
+(define-private (runtime (r uint))
+    {
+        runtime: r,
+        write_length: u0,
+        write_count: u0,
+        read_count: u0,
+        read_length: u0,
+    })
+
+;; Linear cost-assessment function
+(define-private (linear (n uint) (a uint) (b uint))
+    (+ (* a n) b))
+
+;; LogN cost-assessment function
+(define-private (logn (n uint) (a uint) (b uint))
+    (+ (* a (log2 n)) b))
+
+;; NLogN cost-assessment function
+(define-private (nlogn (n uint) (a uint) (b uint))
+    (+ (* a (* n (log2 n))) b))
+
+
+;; Cost Functions
+";
+
+/// Every `ClarityCostFunction` without a fit in `fits`, which the contract still
+/// has to define.
+pub fn unfitted_functions(fits: &BTreeMap<String, Fit>) -> Vec<String> {
+    ClarityCostFunction::ALL
+        .iter()
+        .map(|function| function.get_name())
+        .filter(|function| !fits.contains_key(function))
+        .collect()
+}
+
+/// Writes the costs contract for `fits`, after applying `MATCHED_FUNCTIONS`. Every
+/// `ClarityCostFunction` is defined, those without a fit at their placeholder cost.
+pub fn write_costs_contract<W: Write>(
+    out: &mut W,
+    fits: &BTreeMap<String, Fit>,
+    scaling: &Scaling,
+) -> io::Result<()> {
+    out.write_all(CLAR_HEADER.as_bytes())?;
+    let matched = apply_matched_functions(fits, scaling);
+    let functions: BTreeSet<String> = matched
+        .keys()
+        .cloned()
+        .chain(unfitted_functions(fits))
+        .collect();
+    for function in &functions {
+        let cost = match matched.get(function) {
+            Some(fit) => ScaledCost::new(function, fit, scaling),
+            None => ScaledCost::placeholder(function),
+        };
+        writeln!(out, "{}", define_cost_function(function, &cost))?;
+    }
+    Ok(())
+}
+
+/// Writes the markdown update table for `fits`, after applying `MATCHED_FUNCTIONS`.
+/// If there are `intervals`, each row also has the interval of the function's own
+/// fit, before matching, or `-` if it has none. The functions without a fit are
+/// listed after the table.
+pub fn write_updates_table<W: Write>(
+    out: &mut W,
+    fits: &BTreeMap<String, Fit>,
+    intervals: &BTreeMap<String, FitInterval>,
+    scaling: &Scaling,
+) -> io::Result<()> {
+    if intervals.is_empty() {
+        writeln!(out, "| Runtime cost | New function | Old function |")?;
+        writeln!(out, "| ----------- | ----------- | ----------- |")?;
+    } else {
+        writeln!(
+            out,
+            "| Runtime cost | New function | Old function | {:.0}% interval |",
+            CONFIDENCE_LEVEL * 100.0
+        )?;
+        writeln!(
+            out,
+            "| ----------- | ----------- | ----------- | ----------- |"
+        )?;
+    }
+    for (function, fit) in apply_matched_functions(fits, scaling).iter() {
+        let row = make_clarity_cost_table_row(function, fit, scaling);
+        if intervals.is_empty() {
+            writeln!(out, "{}", row)?;
+            continue;
+        }
+        let cell = match (fits.get(function), intervals.get(function)) {
+            (Some(own_fit), Some(interval)) => {
+                make_interval_cell(function, own_fit, interval, scaling)
+            }
+            _ => "-".to_string(),
+        };
+        writeln!(out, "{} {} |", row, cell)?;
+    }
+
+    let unfitted = unfitted_functions(fits);
+    if !unfitted.is_empty() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Not fitted, so left at the placeholder cost: {}",
+            unfitted
+                .iter()
+                .map(|function| format!("`{}`", function))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,528 @@
+use self::Category::*;
+use self::InputSizes::{Constant, Sizes};
+use blockstack_lib::vm::costs::cost_functions::ClarityCostFunction as F;
+use blockstack_lib::vm::costs::cost_functions::{AnalysisCostFunction, ClarityCostFunction};
+use std::env;
+use std::fmt;
+
+// for when input size is the number of elements
+pub const INPUT_SIZES: [u64; 8] = [1, 2, 8, 16, 32, 64, 128, 256];
+pub const MORE_INPUT_SIZES: [u64; 12] = [1, 2, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+// for when input size is the size of the data
+pub const INPUT_SIZES_DATA: [u64; 8] = [22, 1000, 40000, 160000, 360000, 640000, 1000000, 1100000];
+
+// for when input size is the size of the data, but with a smaller max value
+pub const INPUT_SIZES_DATA_SMALL: [u64; 8] = [17, 100, 500, 1000, 5000, 10000, 50000, 500000];
+
+// input sizes for arithmetic functions
+pub const INPUT_SIZES_ARITHMETIC: [u64; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+pub const INPUT_SIZES_ANALYSIS_PASS: [u64; 6] = [1, 2, 8, 16, 32, 64];
+
+// for when input size is the depth of a context, deeper `let` nesting trips the AST
+// stack depth check
+pub const INPUT_SIZES_CONTEXT_DEPTH: [u64; 6] = [1, 2, 8, 16, 32, 64];
+
+// scaling factor for code generators
+pub const SCALE: u16 = 75;
+
+/// Environment variable holding the benchmark selection, see `parse_selection`.
+pub const SELECT_ENV_VAR: &str = "COST_BENCH_SELECT";
+
+/// Environment variable holding comma separated input sizes that replace those of
+/// every selected benchmark that isn't constant.
+pub const INPUT_SIZES_ENV_VAR: &str = "COST_BENCH_INPUT_SIZES";
+
+/// Environment variable holding a scale that replaces that of every selected benchmark.
+pub const SCALE_ENV_VAR: &str = "COST_BENCH_SCALE";
+
+/// A cost function that has a benchmark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostFunction {
+    Clarity(ClarityCostFunction),
+    Analysis(AnalysisCostFunction),
+}
+
+impl fmt::Display for CostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CostFunction::Clarity(function) => write!(f, "{}", function),
+            CostFunction::Analysis(function) => write!(f, "{}", function),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Arithmetic,
+    /// tuples, sequences, maps, vars, optionals/responses and hashing
+    Data,
+    /// control flow, bindings and lookups
+    Control,
+    /// fungible, non-fungible and stx assets
+    Assets,
+    /// contracts, principals and block info
+    Chain,
+    /// ast, type checking and analysis passes
+    Analysis,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Arithmetic,
+        Category::Data,
+        Category::Control,
+        Category::Assets,
+        Category::Chain,
+        Category::Analysis,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Arithmetic => "arithmetic",
+            Category::Data => "data",
+            Category::Control => "control",
+            Category::Assets => "assets",
+            Category::Chain => "chain",
+            Category::Analysis => "analysis",
+        }
+    }
+
+    pub fn lookup_by_name(name: &str) -> Option<Category> {
+        Category::ALL.iter().find(|c| c.name() == name).copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputSizes {
+    /// Benchmarked once, with input size 1 passed to the generator.
+    Constant,
+    Sizes(&'static [u64]),
+}
+
+/// A registered benchmark: which cost function it measures, which input sizes it is
+/// run over, and the scale handed to the code generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchSpec {
+    pub function: CostFunction,
+    pub category: Category,
+    pub input_sizes: InputSizes,
+    pub scale: u16,
+}
+
+impl BenchSpec {
+    /// Name of the cost function, which is also the criterion group name.
+    pub fn name(&self) -> String {
+        self.function.to_string()
+    }
+
+    /// This spec with its input sizes and scale replaced, where given. Constant
+    /// benchmarks keep running once.
+    pub fn with_overrides(
+        self,
+        input_sizes: Option<&'static [u64]>,
+        scale: Option<u16>,
+    ) -> BenchSpec {
+        BenchSpec {
+            input_sizes: match (self.input_sizes, input_sizes) {
+                (InputSizes::Sizes(_), Some(sizes)) => InputSizes::Sizes(sizes),
+                (input_sizes, _) => input_sizes,
+            },
+            scale: scale.unwrap_or(self.scale),
+            ..self
+        }
+    }
+
+    /// Input sizes to pass to the generator. Constant benchmarks are run once with 1.
+    pub fn input_sizes(&self) -> Vec<u64> {
+        match self.input_sizes {
+            InputSizes::Constant => vec![1],
+            InputSizes::Sizes(sizes) => sizes.to_vec(),
+        }
+    }
+}
+
+const fn clarity(
+    function: ClarityCostFunction,
+    category: Category,
+    input_sizes: InputSizes,
+) -> BenchSpec {
+    BenchSpec {
+        function: CostFunction::Clarity(function),
+        category,
+        input_sizes,
+        scale: SCALE,
+    }
+}
+
+const fn analysis_pass(function: AnalysisCostFunction) -> BenchSpec {
+    BenchSpec {
+        function: CostFunction::Analysis(function),
+        category: Category::Analysis,
+        input_sizes: InputSizes::Sizes(&INPUT_SIZES_ANALYSIS_PASS),
+        scale: SCALE,
+    }
+}
+
+/// Every benchmark in `benches/bench.rs`, in the order they are run.
+pub const REGISTRY: &[BenchSpec] = &[
+    clarity(F::Add, Arithmetic, Sizes(&INPUT_SIZES_ARITHMETIC)),
+    clarity(F::Sub, Arithmetic, Sizes(&INPUT_SIZES_ARITHMETIC)),
+    clarity(F::Mul, Arithmetic, Sizes(&INPUT_SIZES_ARITHMETIC)),
+    clarity(F::Div, Arithmetic, Sizes(&INPUT_SIZES_ARITHMETIC)),
+    clarity(F::Le, Arithmetic, Constant),
+    clarity(F::Leq, Arithmetic, Constant),
+    clarity(F::Ge, Arithmetic, Constant),
+    clarity(F::Geq, Arithmetic, Constant),
+    clarity(F::And, Arithmetic, Sizes(&INPUT_SIZES)),
+    clarity(F::Or, Arithmetic, Sizes(&INPUT_SIZES)),
+    clarity(F::Xor, Arithmetic, Constant),
+    clarity(F::Not, Arithmetic, Constant),
+    clarity(F::Eq, Arithmetic, Sizes(&INPUT_SIZES)),
+    clarity(F::Mod, Arithmetic, Constant),
+    clarity(F::Pow, Arithmetic, Constant),
+    clarity(F::Sqrti, Arithmetic, Constant),
+    clarity(F::Log2, Arithmetic, Constant),
+    clarity(F::IntCast, Arithmetic, Constant),
+    clarity(F::TupleGet, Data, Sizes(&MORE_INPUT_SIZES)),
+    clarity(F::TupleMerge, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::TupleCons, Data, Sizes(&MORE_INPUT_SIZES)),
+    clarity(F::Hash160, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::Sha256, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::Sha512, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::Sha512t256, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::Keccak256, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::Secp256k1recover, Data, Constant),
+    clarity(F::Secp256k1verify, Data, Constant),
+    clarity(F::CreateFt, Assets, Constant),
+    clarity(F::FtMint, Assets, Constant),
+    clarity(F::FtTransfer, Assets, Constant),
+    clarity(F::FtBalance, Assets, Constant),
+    clarity(F::FtSupply, Assets, Constant),
+    clarity(F::FtBurn, Assets, Constant),
+    clarity(F::CreateNft, Assets, Sizes(&INPUT_SIZES)),
+    clarity(F::NftMint, Assets, Sizes(&INPUT_SIZES)),
+    clarity(F::NftTransfer, Assets, Sizes(&INPUT_SIZES)),
+    clarity(F::NftOwner, Assets, Sizes(&INPUT_SIZES)),
+    clarity(F::NftBurn, Assets, Sizes(&INPUT_SIZES)),
+    clarity(F::IsNone, Data, Constant),
+    clarity(F::IsSome, Data, Constant),
+    clarity(F::IsOkay, Data, Constant),
+    clarity(F::IsErr, Data, Constant),
+    clarity(F::Unwrap, Data, Constant),
+    clarity(F::UnwrapRet, Data, Constant),
+    clarity(F::UnwrapErr, Data, Constant),
+    clarity(F::UnwrapErrOrRet, Data, Constant),
+    clarity(F::CreateMap, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::CreateVar, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::SetVar, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::FetchVar, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::SetEntry, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::FetchEntry, Data, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::Print, Control, Sizes(&INPUT_SIZES_DATA)),
+    clarity(F::If, Control, Constant),
+    clarity(F::Asserts, Control, Constant),
+    clarity(F::OkCons, Data, Constant),
+    clarity(F::SomeCons, Data, Constant),
+    clarity(F::ErrCons, Data, Constant),
+    clarity(F::Concat, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::AsMaxLen, Data, Constant),
+    clarity(F::Begin, Control, Constant),
+    clarity(F::BindName, Control, Constant),
+    clarity(F::DefaultTo, Data, Constant),
+    clarity(F::TryRet, Data, Constant),
+    clarity(F::Match, Control, Constant),
+    clarity(F::Let, Control, Sizes(&INPUT_SIZES)),
+    clarity(F::IndexOf, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::ElementAt, Data, Constant),
+    clarity(F::Len, Data, Constant),
+    clarity(F::ListCons, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::Append, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::Filter, Data, Constant),
+    clarity(F::Map, Data, Sizes(&INPUT_SIZES)),
+    clarity(F::Fold, Data, Constant),
+    clarity(F::BlockInfo, Chain, Constant),
+    clarity(F::AtBlock, Chain, Constant),
+    clarity(F::LoadContract, Chain, Sizes(&INPUT_SIZES)),
+    clarity(
+        F::LookupVariableDepth,
+        Control,
+        Sizes(&INPUT_SIZES_CONTEXT_DEPTH),
+    ),
+    clarity(F::LookupVariableSize, Control, Sizes(&INPUT_SIZES)),
+    clarity(F::LookupFunction, Control, Constant),
+    clarity(F::TypeParseStep, Analysis, Constant),
+    clarity(F::AnalysisOptionCons, Analysis, Constant),
+    clarity(F::AnalysisOptionCheck, Analysis, Constant),
+    clarity(F::AnalysisVisit, Analysis, Constant),
+    clarity(F::AnalysisBindName, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisListItemsCheck, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisCheckTupleGet, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisCheckTupleMerge, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisCheckTupleCons, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisTupleItemsCheck, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisCheckLet, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisLookupFunction, Analysis, Constant),
+    clarity(
+        F::AnalysisLookupFunctionTypes,
+        Analysis,
+        Sizes(&INPUT_SIZES),
+    ),
+    clarity(F::AnalysisTypeAnnotate, Analysis, Sizes(&INPUT_SIZES)),
+    BenchSpec {
+        scale: 100,
+        ..clarity(F::AnalysisTypeCheck, Analysis, Sizes(&INPUT_SIZES))
+    },
+    clarity(F::AnalysisIterableFunc, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisStorage, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(
+        F::AnalysisLookupVariableDepth,
+        Analysis,
+        Sizes(&INPUT_SIZES_CONTEXT_DEPTH),
+    ),
+    clarity(F::AnalysisTypeLookup, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisLookupVariableConst, Analysis, Constant),
+    clarity(F::AnalysisUseTraitEntry, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::AnalysisGetFunctionEntry, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::InnerTypeCheckCost, Analysis, Sizes(&INPUT_SIZES)),
+    clarity(F::UserFunctionApplication, Control, Sizes(&INPUT_SIZES)),
+    clarity(F::AstCycleDetection, Analysis, Sizes(&INPUT_SIZES)),
+    BenchSpec {
+        scale: 1,
+        ..clarity(F::AstParse, Analysis, Sizes(&INPUT_SIZES))
+    },
+    clarity(F::ContractStorage, Chain, Sizes(&INPUT_SIZES)),
+    clarity(F::PrincipalOf, Chain, Constant),
+    clarity(F::StxTransfer, Assets, Constant),
+    clarity(F::StxBalance, Assets, Constant),
+    analysis_pass(AnalysisCostFunction::ReadOnly),
+    analysis_pass(AnalysisCostFunction::ArithmeticOnlyChecker),
+    analysis_pass(AnalysisCostFunction::TraitChecker),
+    analysis_pass(AnalysisCostFunction::TypeChecker),
+    clarity(F::PoisonMicroblock, Chain, Constant),
+    clarity(F::ContractCall, Chain, Constant),
+    clarity(F::ContractOf, Chain, Constant),
+];
+
+/// Looks up the registry entry for a cost function.
+pub fn spec_for(function: CostFunction) -> Option<&'static BenchSpec> {
+    REGISTRY.iter().find(|spec| spec.function == function)
+}
+
+/// Looks up the registry entry named `name`, such as a criterion group name.
+pub fn spec_named(name: &str) -> Option<&'static BenchSpec> {
+    REGISTRY.iter().find(|spec| spec.name() == name)
+}
+
+/// One term of a benchmark selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    Category(Category),
+    /// an exact cost function name, or a glob over names using `*` and `?`
+    Pattern(String),
+}
+
+impl Selector {
+    pub fn matches(&self, spec: &BenchSpec) -> bool {
+        match self {
+            Selector::Category(category) => spec.category == *category,
+            Selector::Pattern(pattern) => glob_match(pattern, &spec.name()),
+        }
+    }
+}
+
+/// Parses a comma separated selection such as `cost_add,cost_sha*,assets`.
+/// A term is a category name, an exact cost function name, or a glob over names.
+/// Errors on terms that match no registered benchmark, to catch typos.
+pub fn parse_selection(selection: &str) -> Result<Vec<Selector>, String> {
+    let mut selectors = vec![];
+    for term in selection
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        let selector = match Category::lookup_by_name(term) {
+            Some(category) => Selector::Category(category),
+            None => Selector::Pattern(term.to_string()),
+        };
+        if !REGISTRY.iter().any(|spec| selector.matches(spec)) {
+            return Err(format!(
+                "'{}' does not match any registered benchmark",
+                term
+            ));
+        }
+        selectors.push(selector);
+    }
+    Ok(selectors)
+}
+
+/// Registry entries matching any of `selectors`, or every entry if there are none.
+pub fn select(selectors: &[Selector]) -> Vec<&'static BenchSpec> {
+    REGISTRY
+        .iter()
+        .filter(|spec| selectors.is_empty() || selectors.iter().any(|s| s.matches(spec)))
+        .collect()
+}
+
+/// Parses comma separated input sizes such as `1,8,64`.
+pub fn parse_input_sizes(sizes: &str) -> Result<Vec<u64>, String> {
+    let sizes = sizes
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<u64>()
+                .map_err(|_| format!("'{}' is not an input size", s))
+        })
+        .collect::<Result<Vec<u64>, String>>()?;
+    if sizes.is_empty() {
+        return Err("no input sizes given".to_string());
+    }
+    Ok(sizes)
+}
+
+/// Registry entries selected by the `COST_BENCH_SELECT` environment variable, with
+/// the overrides in `COST_BENCH_INPUT_SIZES` and `COST_BENCH_SCALE` applied.
+/// Panics if any of them are malformed, since silently running the wrong thing is worse.
+pub fn select_from_env() -> Vec<BenchSpec> {
+    let selectors = match env::var(SELECT_ENV_VAR) {
+        Ok(selection) => parse_selection(&selection)
+            .unwrap_or_else(|e| panic!("Invalid {}: {}", SELECT_ENV_VAR, e)),
+        Err(_) => vec![],
+    };
+    // leaked once per run, so overridden specs stay `Copy` like the registry's
+    let input_sizes: Option<&'static [u64]> = env::var(INPUT_SIZES_ENV_VAR).ok().map(|sizes| {
+        let sizes = parse_input_sizes(&sizes)
+            .unwrap_or_else(|e| panic!("Invalid {}: {}", INPUT_SIZES_ENV_VAR, e));
+        &*Box::leak(sizes.into_boxed_slice())
+    });
+    let scale = env::var(SCALE_ENV_VAR).ok().map(|scale| {
+        scale
+            .parse::<u16>()
+            .unwrap_or_else(|_| panic!("Invalid {}: '{}' is not a scale", SCALE_ENV_VAR, scale))
+    });
+
+    select(&selectors)
+        .into_iter()
+        .map(|spec| spec.with_overrides(input_sizes, scale))
+        .collect()
+}
+
+/// Glob match supporting `*` (any run of characters) and `?` (any one character).
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` seen in the pattern, and of the name when it was seen
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected_names(selection: &str) -> Vec<String> {
+        let mut names: Vec<String> = select(&parse_selection(selection).unwrap())
+            .iter()
+            .map(|spec| spec.name())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn glob_matches_stars_and_question_marks() {
+        assert!(glob_match("cost_add", "cost_add"));
+        assert!(glob_match("cost_*", "cost_add"));
+        assert!(glob_match("*_add", "cost_add"));
+        assert!(glob_match("cost_a?d", "cost_add"));
+        assert!(glob_match("c*t*d", "cost_add"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("cost_add", "cost_adds"));
+        assert!(!glob_match("cost_a?", "cost_add"));
+        assert!(!glob_match("*_sub", "cost_add"));
+    }
+
+    #[test]
+    fn selects_by_exact_name_and_glob() {
+        assert_eq!(selected_names("cost_add"), vec!["cost_add"]);
+        assert_eq!(
+            selected_names("cost_sha*"),
+            vec!["cost_sha256", "cost_sha512", "cost_sha512t256"]
+        );
+        assert_eq!(
+            selected_names(" cost_sub, cost_add ,"),
+            vec!["cost_add", "cost_sub"]
+        );
+    }
+
+    #[test]
+    fn selects_by_category() {
+        assert_eq!(
+            parse_selection("assets").unwrap(),
+            vec![Selector::Category(Category::Assets)]
+        );
+        let selected = select(&parse_selection("arithmetic").unwrap());
+        assert!(selected.iter().any(|spec| spec.name() == "cost_add"));
+        assert!(selected
+            .iter()
+            .all(|spec| spec.category == Category::Arithmetic));
+    }
+
+    #[test]
+    fn empty_selection_selects_everything() {
+        assert_eq!(parse_selection("").unwrap(), vec![]);
+        assert_eq!(select(&[]).len(), REGISTRY.len());
+    }
+
+    #[test]
+    fn rejects_terms_matching_no_benchmark() {
+        let error = parse_selection("cost_add,cost_nope*").unwrap_err();
+        assert!(error.contains("cost_nope*"), "{}", error);
+        assert!(parse_selection("arithmetics").is_err());
+    }
+
+    #[test]
+    fn parses_input_sizes() {
+        assert_eq!(parse_input_sizes("1, 8,64,").unwrap(), vec![1, 8, 64]);
+        assert!(parse_input_sizes("1,eight").is_err());
+        assert!(parse_input_sizes("-1").is_err());
+        assert!(parse_input_sizes(" , ").is_err());
+    }
+
+    #[test]
+    fn overrides_replace_sizes_of_sized_benchmarks_only() {
+        static SIZES: [u64; 2] = [3, 5];
+
+        let add = spec_for(CostFunction::Clarity(F::Add)).unwrap();
+        let overridden = add.with_overrides(Some(&SIZES), Some(2));
+        assert_eq!(overridden.input_sizes(), vec![3, 5]);
+        assert_eq!(overridden.scale, 2);
+        assert_eq!(add.with_overrides(None, None), *add);
+
+        let le = spec_for(CostFunction::Clarity(F::Le)).unwrap();
+        let overridden = le.with_overrides(Some(&SIZES), None);
+        assert_eq!(overridden.input_sizes, InputSizes::Constant);
+        assert_eq!(overridden.input_sizes(), vec![1]);
+        assert_eq!(overridden.scale, le.scale);
+    }
+}
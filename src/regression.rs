@@ -0,0 +1,599 @@
+//! Fits cost models to criterion results and writes `cost_constants.csv`.
+//!
+//! This is the Rust port of `analysis/cost_estimator.py`: it reads the same
+//! `<function>/<size>/base/estimates.json` layout and produces the same
+//! `function,a,b` output, so no SciPy environment is needed.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Median runtime in nanoseconds, keyed by input size, for each cost function.
+pub type Report = BTreeMap<String, BTreeMap<u64, f64>>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    /// estimates.json did not contain `median.point_estimate`
+    MissingEstimate(PathBuf),
+    /// malformed line in an overrides file
+    Parse(PathBuf, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Json(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::MissingEstimate(path) => {
+                write!(f, "{}: no median point estimate", path.display())
+            }
+            Error::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The shape of a cost function, `f(n) = a * transform(n) + b`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Constant,
+    Linear,
+    LogN,
+    NLogN,
+}
+
+impl Model {
+    pub const ALL: [Model; 4] = [Model::Constant, Model::Linear, Model::LogN, Model::NLogN];
+
+    /// Name as used in `proposal/function_name_to_type.csv`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Model::Constant => "constant",
+            Model::Linear => "linear",
+            Model::LogN => "logn",
+            Model::NLogN => "nlogn",
+        }
+    }
+
+    pub fn lookup_by_name(name: &str) -> Option<Model> {
+        Model::ALL.iter().find(|m| m.name() == name).copied()
+    }
+
+    pub fn transform(&self, n: f64) -> f64 {
+        match self {
+            Model::Constant => 0.0,
+            Model::Linear => n,
+            Model::LogN => n.log2(),
+            Model::NLogN => n * n.log2(),
+        }
+    }
+
+    /// Number of fitted parameters, used to penalize the more flexible families.
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            Model::Constant => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Fitted constants of a cost function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    pub model: Model,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Fit {
+    pub fn predict(&self, n: u64) -> f64 {
+        self.a * self.model.transform(n as f64) + self.b
+    }
+}
+
+/// Reads every `<function>/<size>/base/estimates.json` under `criterion_dir`.
+pub fn load_reports(criterion_dir: &Path) -> Result<Report, Error> {
+    let mut report = Report::new();
+    for (function_name, size, base_dir) in result_dirs(criterion_dir)? {
+        let estimate = read_median_estimate(&base_dir.join("estimates.json"))?;
+        report
+            .entry(function_name)
+            .or_insert_with(BTreeMap::new)
+            .insert(size, estimate);
+    }
+    Ok(report)
+}
+
+/// The `<function>/<size>/base` directory of every result under `criterion_dir`,
+/// with its function name and input size. criterion's own `report` directories
+/// are skipped, as are size directories that aren't integers.
+pub(crate) fn result_dirs(criterion_dir: &Path) -> Result<Vec<(String, u64, PathBuf)>, Error> {
+    let mut dirs = vec![];
+
+    for function_dir in read_subdirs(criterion_dir)? {
+        let function_name = match function_dir.file_name().and_then(|n| n.to_str()) {
+            Some("report") | None => continue,
+            Some(name) => name.split(' ').next().unwrap_or(name).to_string(),
+        };
+
+        for size_dir in read_subdirs(&function_dir)? {
+            let size = match size_dir
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.parse::<u64>().ok())
+            {
+                Some(size) => size,
+                None => continue,
+            };
+            dirs.push((function_name.clone(), size, size_dir.join("base")));
+        }
+    }
+
+    Ok(dirs)
+}
+
+fn read_subdirs(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut subdirs = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| Error::Io(dir.to_path_buf(), e))? {
+        let path = entry.map_err(|e| Error::Io(dir.to_path_buf(), e))?.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        }
+    }
+    subdirs.sort();
+    Ok(subdirs)
+}
+
+fn read_median_estimate(path: &Path) -> Result<f64, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let estimates: serde_json::Value =
+        serde_json::from_str(&contents).map_err(|e| Error::Json(path.to_path_buf(), e))?;
+    estimates["median"]["point_estimate"]
+        .as_f64()
+        .ok_or_else(|| Error::MissingEstimate(path.to_path_buf()))
+}
+
+/// Least squares fit of `model` to `(size, nanoseconds)` points.
+///
+/// A negative intercept is replaced the same way `cost_estimator.py` does it,
+/// with `max(y_0 - a, 0)` where `y_0` is the measurement at the smallest size.
+/// With fewer than two distinct transformed sizes there is no slope to fit, so
+/// `a` is 0 and `b` is the mean.
+pub fn fit(model: Model, points: &BTreeMap<u64, f64>) -> Fit {
+    let n = points.len() as f64;
+    if points.is_empty() {
+        return Fit {
+            model,
+            a: 0.0,
+            b: 0.0,
+        };
+    }
+
+    let xs: Vec<f64> = points.keys().map(|x| model.transform(*x as f64)).collect();
+    let ys: Vec<f64> = points.values().copied().collect();
+    let x_mean = xs.iter().sum::<f64>() / n;
+    let y_mean = ys.iter().sum::<f64>() / n;
+
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        sxx += (x - x_mean) * (x - x_mean);
+        sxy += (x - x_mean) * (y - y_mean);
+    }
+
+    if sxx == 0.0 {
+        return Fit {
+            model,
+            a: 0.0,
+            b: y_mean,
+        };
+    }
+
+    let a = sxy / sxx;
+    let mut b = y_mean - a * x_mean;
+    if b < 0.0 {
+        b = (ys[0] - a).max(0.0);
+    }
+
+    Fit { model, a, b }
+}
+
+/// How well a fit explains the measurements it was fitted to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitQuality {
+    pub r_squared: f64,
+    /// measured minus predicted nanoseconds, by input size
+    pub residuals: Vec<(u64, f64)>,
+    /// Akaike information criterion with the small sample correction. Lower is
+    /// better; only comparable between fits of the same points.
+    pub aic: f64,
+}
+
+impl FitQuality {
+    pub fn of(fit: &Fit, points: &BTreeMap<u64, f64>) -> FitQuality {
+        let n = points.len() as f64;
+        let y_mean = points.values().sum::<f64>() / n;
+
+        let residuals: Vec<(u64, f64)> = points
+            .iter()
+            .map(|(x, y)| (*x, y - fit.predict(*x)))
+            .collect();
+        let rss: f64 = residuals.iter().map(|(_, r)| r * r).sum();
+        let tss: f64 = points.values().map(|y| (y - y_mean) * (y - y_mean)).sum();
+
+        let r_squared = if tss == 0.0 { 1.0 } else { 1.0 - rss / tss };
+
+        // AICc = n ln(RSS / n) + 2k + 2k(k + 1) / (n - k - 1). The correction term
+        // matters here: most benchmarks only have 6 to 12 input sizes. It is
+        // undefined when there are too few points to tell the families apart.
+        let k = fit.model.parameter_count() as f64;
+        let aic = if n - k - 1.0 <= 0.0 {
+            f64::INFINITY
+        } else {
+            // floor the RSS so exact fits compare as very good rather than -inf
+            let rss = rss.max(f64::MIN_POSITIVE);
+            n * (rss / n).ln() + 2.0 * k + 2.0 * k * (k + 1.0) / (n - k - 1.0)
+        };
+
+        FitQuality {
+            r_squared,
+            residuals,
+            aic,
+        }
+    }
+}
+
+/// A model family fitted to one cost function's measurements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub fit: Fit,
+    pub quality: FitQuality,
+}
+
+/// Fits every model family to `points`, in `Model::ALL` order.
+pub fn evaluate(points: &BTreeMap<u64, f64>) -> Vec<Candidate> {
+    Model::ALL
+        .iter()
+        .map(|model| {
+            let fit = fit(*model, points);
+            let quality = FitQuality::of(&fit, points);
+            Candidate { fit, quality }
+        })
+        .collect()
+}
+
+/// The family with the lowest AICc. Cost functions measured at a single input
+/// size, or whose candidates can't be told apart, are constant.
+pub fn recommend(candidates: &[Candidate]) -> Model {
+    candidates
+        .iter()
+        .filter(|c| c.quality.aic.is_finite())
+        .min_by(|a, b| a.quality.aic.partial_cmp(&b.quality.aic).unwrap())
+        .map(|c| c.fit.model)
+        .unwrap_or(Model::Constant)
+}
+
+/// Model family chosen for a cost function, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub candidates: Vec<Candidate>,
+    pub recommended: Model,
+    /// family given for this function in the overrides file, if any
+    pub override_model: Option<Model>,
+}
+
+impl Selection {
+    /// The override if there is one, otherwise the recommendation.
+    pub fn model(&self) -> Model {
+        self.override_model.unwrap_or(self.recommended)
+    }
+
+    pub fn chosen(&self) -> &Candidate {
+        let model = self.model();
+        self.candidates
+            .iter()
+            .find(|c| c.fit.model == model)
+            .expect("every model family is evaluated")
+    }
+
+    /// True when an override picks a different family than the data does.
+    pub fn disagrees(&self) -> bool {
+        match self.override_model {
+            Some(model) => model != self.recommended,
+            None => false,
+        }
+    }
+}
+
+/// Evaluates every cost function in `report` and picks a family for each,
+/// preferring `overrides` where given.
+pub fn select_models(
+    report: &Report,
+    overrides: &BTreeMap<String, Model>,
+) -> BTreeMap<String, Selection> {
+    report
+        .iter()
+        .map(|(function, points)| {
+            let candidates = evaluate(points);
+            let recommended = recommend(&candidates);
+            let selection = Selection {
+                candidates,
+                recommended,
+                override_model: overrides.get(function).copied(),
+            };
+            (function.clone(), selection)
+        })
+        .collect()
+}
+
+/// The chosen fit of every selection.
+pub fn chosen_fits(selections: &BTreeMap<String, Selection>) -> BTreeMap<String, Fit> {
+    selections
+        .iter()
+        .map(|(function, selection)| (function.clone(), selection.chosen().fit))
+        .collect()
+}
+
+/// Reads a `function_name,type_name` file such as `proposal/function_name_to_type.csv`.
+pub fn load_overrides(path: &Path) -> Result<BTreeMap<String, Model>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let mut overrides = BTreeMap::new();
+
+    for (line_number, line) in contents.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parse_error = |msg: String| Error::Parse(path.to_path_buf(), msg);
+        let mut fields = line.split(',').map(str::trim);
+        let (function, type_name) = match (fields.next(), fields.next()) {
+            (Some(function), Some(type_name)) => (function, type_name),
+            _ => {
+                return Err(parse_error(format!(
+                    "line {}: expected function_name,type_name",
+                    line_number + 1
+                )))
+            }
+        };
+        let model = Model::lookup_by_name(type_name).ok_or_else(|| {
+            parse_error(format!(
+                "line {}: unknown type '{}'",
+                line_number + 1,
+                type_name
+            ))
+        })?;
+        overrides.insert(function.to_string(), model);
+    }
+
+    Ok(overrides)
+}
+
+/// Writes goodness-of-fit for every family, with the recommended and chosen family
+/// of each cost function.
+pub fn write_model_selection<W: Write>(
+    out: &mut W,
+    selections: &BTreeMap<String, Selection>,
+) -> io::Result<()> {
+    write!(out, "function,chosen,recommended,override,disagrees")?;
+    for model in Model::ALL.iter() {
+        write!(out, ",{}_r2,{}_aic", model, model)?;
+    }
+    writeln!(out)?;
+
+    for (function, selection) in selections {
+        write!(
+            out,
+            "{},{},{},{},{}",
+            function,
+            selection.model(),
+            selection.recommended,
+            selection.override_model.map(|m| m.name()).unwrap_or(""),
+            selection.disagrees()
+        )?;
+        for candidate in &selection.candidates {
+            write!(
+                out,
+                ",{},{}",
+                candidate.quality.r_squared, candidate.quality.aic
+            )?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes fitted constants in the `function,a,b` format of `cost_constants.csv`.
+pub fn write_cost_constants<W: Write>(out: &mut W, fits: &BTreeMap<String, Fit>) -> io::Result<()> {
+    writeln!(out, "function,a,b")?;
+    for (function, fit) in fits {
+        writeln!(out, "{},{},{}", function, fit.a, fit.b)?;
+    }
+    Ok(())
+}
+
+/// Reads back the fits written by `write_cost_constants` and `write_model_selection`.
+pub fn load_fits(
+    constants_path: &Path,
+    selection_path: &Path,
+) -> Result<BTreeMap<String, Fit>, Error> {
+    let mut models = BTreeMap::new();
+    for (function, fields) in read_csv_rows(selection_path)? {
+        let model = fields
+            .get(0)
+            .and_then(|name| Model::lookup_by_name(name))
+            .ok_or_else(|| {
+                Error::Parse(
+                    selection_path.to_path_buf(),
+                    format!("no chosen model for {}", function),
+                )
+            })?;
+        models.insert(function, model);
+    }
+
+    let mut fits = BTreeMap::new();
+    for (function, fields) in read_csv_rows(constants_path)? {
+        let parse_error = |msg: &str| {
+            Error::Parse(
+                constants_path.to_path_buf(),
+                format!("{}: {}", function, msg),
+            )
+        };
+        let model = *models
+            .get(&function)
+            .ok_or_else(|| parse_error("missing from model selection"))?;
+        let (a, b) = match (fields.get(0), fields.get(1)) {
+            (Some(a), Some(b)) => (
+                a.parse().map_err(|_| parse_error("invalid a"))?,
+                b.parse().map_err(|_| parse_error("invalid b"))?,
+            ),
+            _ => return Err(parse_error("expected function,a,b")),
+        };
+        fits.insert(function, Fit { model, a, b });
+    }
+    Ok(fits)
+}
+
+/// Rows of a simple CSV file after the header, keyed by their first field.
+fn read_csv_rows(path: &Path) -> Result<Vec<(String, Vec<String>)>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    Ok(contents
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split(',').map(|f| f.trim().to_string());
+            let key = fields.next().unwrap_or_default();
+            (key, fields.collect())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(sizes: &[u64], f: impl Fn(f64) -> f64) -> BTreeMap<u64, f64> {
+        sizes.iter().map(|n| (*n, f(*n as f64))).collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    const SIZES: [u64; 6] = [1, 2, 8, 16, 32, 64];
+
+    #[test]
+    fn fits_exact_linear_data() {
+        let points = points(&SIZES, |n| 3.0 * n + 10.0);
+        let fitted = fit(Model::Linear, &points);
+        assert_close(fitted.a, 3.0);
+        assert_close(fitted.b, 10.0);
+        assert_close(FitQuality::of(&fitted, &points).r_squared, 1.0);
+    }
+
+    #[test]
+    fn fits_exact_logn_and_nlogn_data() {
+        let logn = fit(Model::LogN, &points(&SIZES, |n| 7.0 * n.log2() + 3.0));
+        assert_close(logn.a, 7.0);
+        assert_close(logn.b, 3.0);
+
+        let nlogn = fit(Model::NLogN, &points(&SIZES, |n| 2.0 * n * n.log2() + 5.0));
+        assert_close(nlogn.a, 2.0);
+        assert_close(nlogn.b, 5.0);
+        assert_close(nlogn.predict(64), 2.0 * 64.0 * 6.0 + 5.0);
+    }
+
+    #[test]
+    fn replaces_a_negative_intercept_like_cost_estimator() {
+        // y = 10n - 50: the intercept becomes y_0 - a = 30 - 10
+        let fitted = fit(Model::Linear, &points(&[8, 16, 32], |n| 10.0 * n - 50.0));
+        assert_close(fitted.a, 10.0);
+        assert_close(fitted.b, 20.0);
+
+        // y = 100n - 1000: y_0 - a is negative, so the intercept is 0
+        let fitted = fit(Model::Linear, &points(&[1, 10, 20], |n| 100.0 * n - 1000.0));
+        assert_close(fitted.a, 100.0);
+        assert_close(fitted.b, 0.0);
+    }
+
+    #[test]
+    fn fits_the_mean_without_a_slope() {
+        let fitted = fit(Model::Linear, &points(&[16], |_| 42.0));
+        assert_close(fitted.a, 0.0);
+        assert_close(fitted.b, 42.0);
+
+        let fitted = fit(Model::Constant, &points(&SIZES, |n| n));
+        assert_close(fitted.a, 0.0);
+        assert_close(fitted.b, 20.5);
+    }
+
+    #[test]
+    fn aicc_penalizes_the_extra_parameter() {
+        // every family fits constant data exactly, so the one with fewer
+        // parameters wins
+        let candidates = evaluate(&points(&SIZES, |_| 100.0));
+        assert_eq!(recommend(&candidates), Model::Constant);
+        assert!(candidates[0].quality.aic < candidates[1].quality.aic);
+
+        // a slope within the noise isn't worth its parameter
+        let noise = [1.0, -1.0, 1.0, -1.0, 1.0, -1.0];
+        let noisy: BTreeMap<u64, f64> = SIZES
+            .iter()
+            .zip(noise.iter())
+            .map(|(n, e)| (*n, 1000.0 + e))
+            .collect();
+        assert_eq!(recommend(&evaluate(&noisy)), Model::Constant);
+    }
+
+    #[test]
+    fn aicc_picks_the_family_that_generated_the_data() {
+        let linear = points(&SIZES, |n| 3.0 * n + 10.0);
+        assert_eq!(recommend(&evaluate(&linear)), Model::Linear);
+
+        let nlogn = points(&SIZES, |n| 2.0 * n * n.log2() + 5.0);
+        assert_eq!(recommend(&evaluate(&nlogn)), Model::NLogN);
+
+        let logn = points(&SIZES, |n| 7.0 * n.log2() + 3.0);
+        assert_eq!(recommend(&evaluate(&logn)), Model::LogN);
+    }
+
+    #[test]
+    fn too_few_sizes_are_constant() {
+        for sizes in [&[8][..], &[8, 16][..]].iter() {
+            let candidates = evaluate(&points(sizes, |n| 3.0 * n + 10.0));
+            assert!(candidates.iter().all(|c| c.quality.aic.is_infinite()));
+            assert_eq!(recommend(&candidates), Model::Constant);
+        }
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_the_recommendation() {
+        let mut report = Report::new();
+        report.insert("cost_add".to_string(), points(&SIZES, |n| 3.0 * n + 10.0));
+        let mut overrides = BTreeMap::new();
+        overrides.insert("cost_add".to_string(), Model::Constant);
+
+        let selections = select_models(&report, &overrides);
+        let selection = &selections["cost_add"];
+        assert_eq!(selection.recommended, Model::Linear);
+        assert_eq!(selection.model(), Model::Constant);
+        assert!(selection.disagrees());
+        assert_eq!(chosen_fits(&selections)["cost_add"].model, Model::Constant);
+    }
+}
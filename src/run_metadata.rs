@@ -0,0 +1,391 @@
+//! Records what produced a set of benchmark results, so results from different
+//! machines, toolchains or settings aren't mixed without noticing.
+//!
+//! Every timed run of the bench target appends a `RunMetadata` to
+//! `run_metadata.json` in its criterion directory. Runs with different
+//! selections can write to the same directory, so each record lists the
+//! benchmarks it ran, and a benchmark's results belong to the last run that
+//! lists it.
+
+use crate::allocations::Metric;
+use crate::regression::Error;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Name of the metadata file in a criterion or analysis directory.
+pub const RUN_METADATA_FILE: &str = "run_metadata.json";
+
+/// The machine and build a run used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    pub cpu: String,
+    pub kernel: String,
+    pub rustc: String,
+    /// cargo profile the bench target was built with
+    pub profile: String,
+    /// commit of the blockstack-core checkout, see `build.rs`
+    pub blockstack_core: String,
+}
+
+impl Environment {
+    /// The environment of this process. Build details are captured by `build.rs`.
+    pub fn current() -> Environment {
+        Environment {
+            cpu: cpu_model().unwrap_or_else(|| "unknown".to_string()),
+            kernel: fs::read_to_string("/proc/sys/kernel/osrelease")
+                .map(|release| release.trim().to_string())
+                .unwrap_or_else(|_| "unknown".to_string()),
+            rustc: env!("COST_BENCH_RUSTC_VERSION").to_string(),
+            profile: env!("COST_BENCH_PROFILE").to_string(),
+            blockstack_core: env!("COST_BENCH_BLOCKSTACK_CORE_REV").to_string(),
+        }
+    }
+}
+
+fn cpu_model() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo
+        .lines()
+        .find(|line| line.starts_with("model name"))
+        .and_then(|line| line.splitn(2, ':').nth(1))
+        .map(|model| model.trim().to_string())
+}
+
+/// How one benchmark was run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkRecord {
+    pub scale: u16,
+    pub input_sizes: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunMetadata {
+    pub environment: Environment,
+    pub generator_seed: u64,
+    pub storage_backend: String,
+    pub headers_latency_us: u64,
+    /// `allocations::Metric` the results are in
+    pub measurement: String,
+    /// every benchmark the run selected, by name
+    pub benchmarks: BTreeMap<String, BenchmarkRecord>,
+}
+
+impl RunMetadata {
+    pub fn to_json(&self) -> Value {
+        let benchmarks: serde_json::Map<String, Value> = self
+            .benchmarks
+            .iter()
+            .map(|(name, record)| {
+                let record = json!({
+                    "scale": record.scale,
+                    "input_sizes": record.input_sizes,
+                });
+                (name.clone(), record)
+            })
+            .collect();
+        json!({
+            "cpu": self.environment.cpu,
+            "kernel": self.environment.kernel,
+            "rustc": self.environment.rustc,
+            "profile": self.environment.profile,
+            "blockstack_core": self.environment.blockstack_core,
+            "generator_seed": self.generator_seed,
+            "storage_backend": self.storage_backend,
+            "headers_latency_us": self.headers_latency_us,
+            "measurement": self.measurement,
+            "benchmarks": benchmarks,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<RunMetadata, String> {
+        let string = |field: &str| {
+            value[field]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing {}", field))
+        };
+        let number = |value: &Value, field: &str| {
+            value[field]
+                .as_u64()
+                .ok_or_else(|| format!("missing {}", field))
+        };
+
+        let mut benchmarks = BTreeMap::new();
+        let records = value["benchmarks"]
+            .as_object()
+            .ok_or_else(|| "missing benchmarks".to_string())?;
+        for (name, record) in records {
+            let scale = number(record, "scale")?;
+            let input_sizes = record["input_sizes"]
+                .as_array()
+                .and_then(|sizes| sizes.iter().map(Value::as_u64).collect())
+                .ok_or_else(|| format!("{}: missing input_sizes", name))?;
+            benchmarks.insert(
+                name.clone(),
+                BenchmarkRecord {
+                    scale: scale as u16,
+                    input_sizes,
+                },
+            );
+        }
+
+        Ok(RunMetadata {
+            environment: Environment {
+                cpu: string("cpu")?,
+                kernel: string("kernel")?,
+                rustc: string("rustc")?,
+                profile: string("profile")?,
+                blockstack_core: string("blockstack_core")?,
+            },
+            generator_seed: number(value, "generator_seed")?,
+            storage_backend: string("storage_backend")?,
+            headers_latency_us: number(value, "headers_latency_us")?,
+            // runs recorded before allocations could be measured timed everything
+            measurement: value["measurement"]
+                .as_str()
+                .unwrap_or(Metric::WallTime.name())
+                .to_string(),
+            benchmarks,
+        })
+    }
+}
+
+/// Every run recorded in `dir`, oldest first. A directory without a metadata file,
+/// such as results from before runs were recorded, has none.
+pub fn load_runs(dir: &Path) -> Result<Vec<RunMetadata>, Error> {
+    let path = dir.join(RUN_METADATA_FILE);
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| Error::Io(path.clone(), e))?;
+    let runs: Value = serde_json::from_str(&contents).map_err(|e| Error::Json(path.clone(), e))?;
+    runs.as_array()
+        .ok_or_else(|| Error::Parse(path.clone(), "expected an array of runs".to_string()))?
+        .iter()
+        .map(|run| RunMetadata::from_json(run).map_err(|e| Error::Parse(path.clone(), e)))
+        .collect()
+}
+
+/// Writes `runs` to `dir`, replacing any recorded there.
+pub fn write_runs(dir: &Path, runs: &[RunMetadata]) -> Result<(), Error> {
+    let path = dir.join(RUN_METADATA_FILE);
+    let runs = Value::Array(runs.iter().map(RunMetadata::to_json).collect());
+    let contents = serde_json::to_string_pretty(&runs).expect("run metadata is valid JSON");
+    fs::write(&path, contents + "\n").map_err(|e| Error::Io(path, e))
+}
+
+/// Adds `run` to the runs recorded in `dir`.
+pub fn append_run(dir: &Path, run: &RunMetadata) -> Result<(), Error> {
+    let mut runs = load_runs(dir)?;
+    runs.push(run.clone());
+    write_runs(dir, &runs)
+}
+
+/// The runs whose results are still in a directory: the last run of each
+/// benchmark, oldest first.
+pub fn current_runs(runs: &[RunMetadata]) -> Vec<&RunMetadata> {
+    runs.iter()
+        .enumerate()
+        .filter(|(i, run)| {
+            run.benchmarks.keys().any(|name| {
+                !runs[i + 1..]
+                    .iter()
+                    .any(|later| later.benchmarks.contains_key(name))
+            })
+        })
+        .map(|(_, run)| run)
+        .collect()
+}
+
+/// A setting that differs between two runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// the setting, or `scale of <benchmark>`
+    pub field: String,
+    pub first: String,
+    pub second: String,
+}
+
+impl Difference {
+    /// Differences that make results incommensurable: a different build profile
+    /// or scale changes what one sample measures, not how fast it is, and a
+    /// different measurement isn't in the same unit.
+    pub fn incommensurable(&self) -> bool {
+        self.field == "profile"
+            || self.field == "measurement"
+            || self.field.starts_with("scale of ")
+    }
+}
+
+/// Every setting of `first` and `second` that differs, including the scale of
+/// benchmarks both ran. Input sizes aren't compared, since results are compared
+/// per size.
+pub fn differences(first: &RunMetadata, second: &RunMetadata) -> Vec<Difference> {
+    let mut differences = vec![];
+    let mut check = |field: &str, a: String, b: String| {
+        if a != b {
+            differences.push(Difference {
+                field: field.to_string(),
+                first: a,
+                second: b,
+            });
+        }
+    };
+    let (a, b) = (&first.environment, &second.environment);
+    check("cpu", a.cpu.clone(), b.cpu.clone());
+    check("kernel", a.kernel.clone(), b.kernel.clone());
+    check("rustc", a.rustc.clone(), b.rustc.clone());
+    check("profile", a.profile.clone(), b.profile.clone());
+    check(
+        "blockstack_core",
+        a.blockstack_core.clone(),
+        b.blockstack_core.clone(),
+    );
+    check(
+        "generator_seed",
+        first.generator_seed.to_string(),
+        second.generator_seed.to_string(),
+    );
+    check(
+        "storage_backend",
+        first.storage_backend.clone(),
+        second.storage_backend.clone(),
+    );
+    check(
+        "headers_latency_us",
+        first.headers_latency_us.to_string(),
+        second.headers_latency_us.to_string(),
+    );
+    check(
+        "measurement",
+        first.measurement.clone(),
+        second.measurement.clone(),
+    );
+    for (name, record) in &first.benchmarks {
+        if let Some(other) = second.benchmarks.get(name) {
+            check(
+                &format!("scale of {}", name),
+                record.scale.to_string(),
+                other.scale.to_string(),
+            );
+        }
+    }
+    differences
+}
+
+/// Differences between the runs whose results are mixed in one directory, each
+/// against the oldest of them. Any difference but the generator seed means the
+/// results shouldn't be fitted together.
+pub fn mixed_run_differences(runs: &[RunMetadata]) -> Vec<Difference> {
+    let current = current_runs(runs);
+    let mut all = vec![];
+    if let Some((first, rest)) = current.split_first() {
+        for run in rest {
+            for difference in differences(first, run) {
+                if !all.contains(&difference) {
+                    all.push(difference);
+                }
+            }
+        }
+    }
+    all
+}
+
+/// Differences between the results in two directories, comparing the latest runs
+/// of each and the scale of every benchmark both ran.
+pub fn cross_run_differences(first: &[RunMetadata], second: &[RunMetadata]) -> Vec<Difference> {
+    let mut all = vec![];
+    for a in current_runs(first) {
+        for b in current_runs(second) {
+            for difference in differences(a, b) {
+                if !all.contains(&difference) {
+                    all.push(difference);
+                }
+            }
+        }
+    }
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(scales: &[(&str, u16)]) -> RunMetadata {
+        RunMetadata {
+            environment: Environment {
+                cpu: "cpu".to_string(),
+                kernel: "kernel".to_string(),
+                rustc: "rustc 1.0.0".to_string(),
+                profile: "release".to_string(),
+                blockstack_core: "abc123".to_string(),
+            },
+            generator_seed: 1,
+            storage_backend: "memory".to_string(),
+            headers_latency_us: 0,
+            measurement: Metric::WallTime.name().to_string(),
+            benchmarks: scales
+                .iter()
+                .map(|(name, scale)| {
+                    let record = BenchmarkRecord {
+                        scale: *scale,
+                        input_sizes: vec![1, 8],
+                    };
+                    (name.to_string(), record)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let run = run(&[("cost_add", 75), ("cost_sub", 10)]);
+        assert_eq!(RunMetadata::from_json(&run.to_json()), Ok(run));
+    }
+
+    #[test]
+    fn runs_without_a_measurement_timed_wall_time() {
+        let mut json = run(&[("cost_add", 75)]).to_json();
+        json.as_object_mut().unwrap().remove("measurement");
+        let parsed = RunMetadata::from_json(&json).unwrap();
+        assert_eq!(parsed.measurement, Metric::WallTime.name());
+    }
+
+    #[test]
+    fn later_runs_replace_earlier_runs_benchmarks() {
+        let first = run(&[("cost_add", 75)]);
+        let second = run(&[("cost_sub", 75)]);
+        let third = run(&[("cost_add", 75)]);
+        let runs = vec![first, second.clone(), third.clone()];
+        assert_eq!(current_runs(&runs), vec![&second, &third]);
+    }
+
+    #[test]
+    fn a_mixed_scale_is_incommensurable() {
+        let mut second = run(&[("cost_add", 10)]);
+        second.generator_seed = 2;
+        let runs = vec![run(&[("cost_add", 75), ("cost_sub", 75)]), second];
+
+        let differences = mixed_run_differences(&runs);
+        assert_eq!(differences.len(), 2);
+        let seed = differences
+            .iter()
+            .find(|d| d.field == "generator_seed")
+            .unwrap();
+        assert!(!seed.incommensurable());
+        let scale = differences
+            .iter()
+            .find(|d| d.field == "scale of cost_add")
+            .unwrap();
+        assert_eq!((scale.first.as_str(), scale.second.as_str()), ("75", "10"));
+        assert!(scale.incommensurable());
+    }
+
+    #[test]
+    fn replaced_runs_are_not_mixed() {
+        let runs = vec![run(&[("cost_add", 75)]), run(&[("cost_add", 10)])];
+        assert_eq!(mixed_run_differences(&runs), vec![]);
+    }
+}
@@ -0,0 +1,672 @@
+//! Searches for inputs that are expensive to evaluate relative to what the VM
+//! charges for them.
+//!
+//! Generators produce one fairly benign input shape per size. Starting from a
+//! generator's output at scale 1, so that each mutation changes the only copy of
+//! the measured expression, the search repeatedly mutates literal values and the
+//! order of expression arguments, times each variant and keeps those with the
+//! most nanoseconds per charged cost unit. Charges are observed with a probe
+//! tracker (see `cost_probe`), and priced with the proposed cost function when
+//! there is a fit for it, so the worst inputs found show where the proposal
+//! undercharges. The time is of the whole body, so it includes the work of any
+//! other cost functions the body calls.
+//!
+//! A single timing of a variant can be lucky, so the variants kept are timed
+//! again before they are ranked.
+
+use crate::cost_probe::{self, Charges};
+use crate::generators::GenOutput;
+use crate::proposal::{ScaledCost, Scaling};
+use crate::regression::Fit;
+use blockstack_lib::clarity_vm::database::MemoryBackingStore;
+use blockstack_lib::vm::ast::{self, ContractAST};
+use blockstack_lib::vm::contexts::{ContractContext, GlobalContext};
+use blockstack_lib::vm::costs::cost_functions::ClarityCostFunction;
+use blockstack_lib::vm::costs::{CostTracker, LimitedCostTracker};
+use blockstack_lib::vm::database::{ClarityDatabase, NULL_BURN_STATE_DB, NULL_HEADER_DB};
+use blockstack_lib::vm::eval_all;
+use blockstack_lib::vm::types::QualifiedContractIdentifier;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::time::Instant;
+
+/// Timed evaluations of each variant; the median is kept.
+pub const DEFAULT_REPETITIONS: usize = 15;
+
+/// Scale the searched code is generated at: with more copies of the measured
+/// expression, a mutation would only change one of them.
+pub const SEARCH_SCALE: u16 = 1;
+
+pub const DEFAULT_ITERATIONS: usize = 200;
+
+/// Number of worst variants kept and reported.
+pub const POOL_SIZE: usize = 5;
+
+/// Longest excerpt of source shown when describing a mutation.
+const EXCERPT_LEN: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LiteralKind {
+    Uint,
+    Int,
+    Buffer,
+    /// an ASCII string, including its quotes
+    Ascii,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+/// Where mutations can be applied in a piece of Clarity source.
+#[derive(Debug, Default)]
+struct Sites {
+    literals: Vec<(Span, LiteralKind)>,
+    /// the elements of every parenthesized list with at least two arguments
+    argument_lists: Vec<Vec<Span>>,
+}
+
+fn classify(atom: &str) -> Option<LiteralKind> {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if atom.starts_with('u') && is_digits(&atom[1..]) {
+        Some(LiteralKind::Uint)
+    } else if is_digits(atom.strip_prefix('-').unwrap_or(atom)) {
+        Some(LiteralKind::Int)
+    } else if atom.starts_with("0x") && atom[2..].bytes().all(|b| b.is_ascii_hexdigit()) {
+        Some(LiteralKind::Buffer)
+    } else {
+        None
+    }
+}
+
+/// Index just past the string literal whose opening quote is at `quote`.
+fn string_end(bytes: &[u8], quote: usize) -> usize {
+    let mut i = quote + 1;
+    while i < bytes.len() && bytes[i] != b'"' {
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    (i + 1).min(bytes.len())
+}
+
+/// Finds the mutation sites of `source`. This only tokenizes as much Clarity as
+/// mutating needs: comments, strings, lists, tuples and atoms.
+fn find_sites(source: &str) -> Sites {
+    let bytes = source.as_bytes();
+    let mut sites = Sites::default();
+    // start of each open list, whether it is parenthesized, and its elements so far
+    let mut stack: Vec<(usize, bool, Vec<Span>)> = vec![];
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let element = match bytes[i] {
+            b';' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                None
+            }
+            b'"' => {
+                i = string_end(bytes, i);
+                let span = Span { start, end: i };
+                sites.literals.push((span, LiteralKind::Ascii));
+                Some(span)
+            }
+            // utf8 strings are left alone, but can still be swapped
+            b'u' if bytes.get(i + 1) == Some(&b'"') => {
+                i = string_end(bytes, i + 1);
+                Some(Span { start, end: i })
+            }
+            b'(' | b'{' => {
+                stack.push((i, bytes[i] == b'(', vec![]));
+                i += 1;
+                None
+            }
+            b')' | b'}' => {
+                i += 1;
+                stack.pop().map(|(start, parenthesized, elements)| {
+                    if parenthesized && elements.len() >= 3 {
+                        sites.argument_lists.push(elements);
+                    }
+                    Span { start, end: i }
+                })
+            }
+            b if b.is_ascii_whitespace() || b == b',' => {
+                i += 1;
+                None
+            }
+            _ => {
+                while i < bytes.len()
+                    && !bytes[i].is_ascii_whitespace()
+                    && !b"(){},;\"".contains(&bytes[i])
+                {
+                    i += 1;
+                }
+                let span = Span { start, end: i };
+                if let Some(kind) = classify(&source[start..i]) {
+                    sites.literals.push((span, kind));
+                }
+                Some(span)
+            }
+        };
+        if let (Some(span), Some((_, _, elements))) = (element, stack.last_mut()) {
+            elements.push(span);
+        }
+    }
+    sites
+}
+
+/// A change made to a variant's body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+    ReplaceLiteral { from: String, to: String },
+    SwapArguments { first: String, second: String },
+}
+
+fn excerpt(source: &str) -> String {
+    let source = source.split_whitespace().collect::<Vec<_>>().join(" ");
+    match source.char_indices().nth(EXCERPT_LEN) {
+        Some((end, _)) => format!("{}...", &source[..end]),
+        None => source,
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mutation::ReplaceLiteral { from, to } => {
+                write!(f, "`{}` -> `{}`", excerpt(from), excerpt(to))
+            }
+            Mutation::SwapArguments { first, second } => {
+                write!(f, "swap `{}` and `{}`", excerpt(first), excerpt(second))
+            }
+        }
+    }
+}
+
+/// A replacement for a literal of `kind`. Buffers and strings keep their length,
+/// so the input sizes the VM charges for them don't change.
+fn mutate_literal(literal: &str, kind: LiteralKind, rng: &mut StdRng) -> String {
+    match kind {
+        LiteralKind::Uint => {
+            let extremes = [0, 1, u64::MAX as u128, 1 << 127, u128::MAX, rng.gen()];
+            format!("u{}", extremes.choose(rng).unwrap())
+        }
+        LiteralKind::Int => {
+            let extremes = [0, -1, 1, i128::MAX, i128::MIN + 1, rng.gen()];
+            format!("{}", extremes.choose(rng).unwrap())
+        }
+        LiteralKind::Buffer => {
+            let len = literal.len() - 2;
+            let hex: String = match rng.gen_range(0..3) {
+                0 => "0".repeat(len),
+                1 => "f".repeat(len),
+                _ => (0..len)
+                    .map(|_| std::char::from_digit(rng.gen_range(0..16), 16).unwrap())
+                    .collect(),
+            };
+            format!("0x{}", hex)
+        }
+        LiteralKind::Ascii => {
+            let len = literal.len().saturating_sub(2);
+            let contents: String = match rng.gen_range(0..3) {
+                0 => "a".repeat(len),
+                1 => "~".repeat(len),
+                _ => (0..len)
+                    .map(|_| rng.gen_range(b'a'..=b'z') as char)
+                    .collect(),
+            };
+            format!("\"{}\"", contents)
+        }
+    }
+}
+
+/// Applies one random mutation to `source`, or returns None if it has no sites.
+fn mutate(source: &str, rng: &mut StdRng) -> Option<(String, Mutation)> {
+    let sites = find_sites(source);
+    let swap = !sites.argument_lists.is_empty() && (sites.literals.is_empty() || rng.gen_bool(0.3));
+
+    if swap {
+        let elements = sites.argument_lists.choose(rng).unwrap();
+        let mut indices: Vec<usize> = (1..elements.len()).collect();
+        indices.shuffle(rng);
+        let (a, b) = (
+            elements[indices[0].min(indices[1])],
+            elements[indices[0].max(indices[1])],
+        );
+        let (first, second) = (&source[a.start..a.end], &source[b.start..b.end]);
+        let mutated = format!(
+            "{}{}{}{}{}",
+            &source[..a.start],
+            second,
+            &source[a.end..b.start],
+            first,
+            &source[b.end..]
+        );
+        let mutation = Mutation::SwapArguments {
+            first: first.to_string(),
+            second: second.to_string(),
+        };
+        return Some((mutated, mutation));
+    }
+
+    let (span, kind) = *sites.literals.choose(rng)?;
+    let from = &source[span.start..span.end];
+    let to = mutate_literal(from, kind, rng);
+    let mutated = format!("{}{}{}", &source[..span.start], to, &source[span.end..]);
+    let mutation = Mutation::ReplaceLiteral {
+        from: from.to_string(),
+        to,
+    };
+    Some((mutated, mutation))
+}
+
+/// Evaluates `setup`, then calls `f` with the parsed body in a fresh in-memory
+/// store, with setup committed like `prepare_bench` does.
+fn with_context<F, R>(
+    setup: Option<&str>,
+    body: &str,
+    probed: Option<ClarityCostFunction>,
+    f: F,
+) -> Result<R, String>
+where
+    F: FnOnce(&ContractAST, &mut GlobalContext, &mut ContractContext) -> Result<R, String>,
+{
+    let mut store = MemoryBackingStore::new();
+    if let Some(probed) = probed {
+        cost_probe::install_probe_contracts(&mut store, probed);
+    }
+    let mut clarity_db = ClarityDatabase::new(&mut store, &NULL_HEADER_DB, &NULL_BURN_STATE_DB);
+    let cost_tracker = match probed {
+        Some(_) => cost_probe::unlimited_tracker(&mut clarity_db)?,
+        None => LimitedCostTracker::new_free(),
+    };
+    let mut global_context = GlobalContext::new(false, clarity_db, cost_tracker);
+    global_context.begin();
+
+    let contract_identifier = QualifiedContractIdentifier::local("search").unwrap();
+    let mut contract_context = ContractContext::new(contract_identifier.clone());
+    let parse = |source: &str| {
+        ast::build_ast(&contract_identifier, source, &mut ())
+            .map_err(|e| format!("parse error: {}", e.diagnostic.message))
+    };
+
+    if let Some(setup) = setup {
+        let setup_ast = parse(setup)?;
+        global_context
+            .execute(|g| eval_all(&setup_ast.expressions, &mut contract_context, g))
+            .map_err(|e| format!("setup failed: {}", e))?;
+    }
+    global_context.commit().map_err(|e| e.to_string())?;
+    global_context.begin();
+
+    let body_ast = parse(body)?;
+    f(&body_ast, &mut global_context, &mut contract_context)
+}
+
+fn eval(
+    body_ast: &ContractAST,
+    global_context: &mut GlobalContext,
+    contract_context: &mut ContractContext,
+) -> Result<(), String> {
+    global_context
+        .execute(|g| eval_all(&body_ast.expressions, contract_context, g))
+        .map(|_| ())
+        .map_err(|e| format!("evaluation failed: {}", e))
+}
+
+/// Prices charges: with the proposed cost function if there is a fit for it,
+/// otherwise one unit per charge.
+#[derive(Debug, Clone, Copy)]
+pub struct Pricing {
+    pub cost: Option<ScaledCost>,
+}
+
+impl Pricing {
+    pub fn new(function: ClarityCostFunction, fit: Option<&Fit>, scaling: &Scaling) -> Pricing {
+        Pricing {
+            cost: fit.map(|fit| ScaledCost::new(&function.to_string(), fit, scaling)),
+        }
+    }
+
+    /// Cost units of `charges`, priced at their mean input.
+    pub fn cost_units(&self, charges: &Charges) -> f64 {
+        let mean_input = charges.mean_input().unwrap_or(0.0).round() as u64;
+        match self.cost {
+            Some(cost) => cost.evaluate(mean_input) as f64 * charges.count as f64,
+            None => charges.count as f64,
+        }
+    }
+}
+
+/// A variant of a generator's output and how expensive it is.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub body: String,
+    /// mutations applied to the generator's body, in order
+    pub mutations: Vec<Mutation>,
+    /// median nanoseconds to evaluate the body
+    pub nanoseconds: f64,
+    /// interquartile range of the timed evaluations, in nanoseconds
+    pub spread: f64,
+    /// charges to the searched cost function
+    pub charges: Charges,
+    pub cost_units: f64,
+}
+
+impl Variant {
+    pub fn nanoseconds_per_unit(&self) -> f64 {
+        self.nanoseconds / self.cost_units.max(f64::MIN_POSITIVE)
+    }
+}
+
+/// Median and interquartile range of `repetitions` timed evaluations of `body`,
+/// after one untimed evaluation.
+fn time(setup: Option<&str>, body: &str, repetitions: usize) -> Result<(f64, f64), String> {
+    with_context(setup, body, None, |body_ast, g, c| {
+        eval(body_ast, g, c)?;
+        let mut times = Vec::with_capacity(repetitions);
+        for _ in 0..repetitions {
+            let start = Instant::now();
+            eval(body_ast, g, c)?;
+            times.push(start.elapsed().as_nanos() as f64);
+        }
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let quantile = |q: f64| times[((times.len() - 1) as f64 * q).round() as usize];
+        Ok((quantile(0.5), quantile(0.75) - quantile(0.25)))
+    })
+}
+
+/// Times `body` and observes its charges to `function`. Fails if it doesn't parse
+/// or evaluate, or never charges `function`.
+fn measure(
+    function: ClarityCostFunction,
+    setup: Option<&str>,
+    body: &str,
+    mutations: Vec<Mutation>,
+    pricing: &Pricing,
+    repetitions: usize,
+) -> Result<Variant, String> {
+    let charges = with_context(setup, body, Some(function), |body_ast, g, c| {
+        let before = g.cost_track.get_total();
+        eval(body_ast, g, c)?;
+        Ok(Charges::between(&before, &g.cost_track.get_total()))
+    })?;
+    if charges.count == 0 {
+        return Err(format!("{} is never charged", function));
+    }
+
+    let (nanoseconds, spread) = time(setup, body, repetitions)?;
+
+    Ok(Variant {
+        body: body.to_string(),
+        mutations,
+        nanoseconds,
+        spread,
+        charges,
+        cost_units: pricing.cost_units(&charges),
+    })
+}
+
+/// Settings of one search.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchConfig {
+    pub iterations: usize,
+    pub repetitions: usize,
+    pub seed: u64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        SearchConfig {
+            iterations: DEFAULT_ITERATIONS,
+            repetitions: DEFAULT_REPETITIONS,
+            seed: 0,
+        }
+    }
+}
+
+/// Outcome of a search.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub function: ClarityCostFunction,
+    pub input_size: u64,
+    pub original: Variant,
+    /// most nanoseconds per cost unit first, at most `POOL_SIZE` of them
+    pub worst: Vec<Variant>,
+    /// variants that failed to parse, evaluate or be charged
+    pub rejected: usize,
+}
+
+/// Searches for variants of `output`, generated for `function` at `SEARCH_SCALE`,
+/// that take the most nanoseconds per charged cost unit. Each iteration mutates one
+/// of the worst variants found so far, which starts as the generator's output. The
+/// generator's output and the worst variants are timed again at the end, and
+/// ranked by those times.
+pub fn search(
+    function: ClarityCostFunction,
+    output: &GenOutput,
+    pricing: &Pricing,
+    config: &SearchConfig,
+) -> Result<SearchResult, String> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let setup = output.setup.as_deref();
+    let original = measure(
+        function,
+        setup,
+        &output.body,
+        vec![],
+        pricing,
+        config.repetitions,
+    )?;
+    let by_cost = |a: &Variant, b: &Variant| {
+        b.nanoseconds_per_unit()
+            .partial_cmp(&a.nanoseconds_per_unit())
+            .unwrap()
+    };
+
+    let mut pool = vec![original.clone()];
+    let mut rejected = 0;
+    for _ in 0..config.iterations {
+        let parent = pool.choose(&mut rng).unwrap();
+        let (body, mutation) = match mutate(&parent.body, &mut rng) {
+            Some(mutated) => mutated,
+            None => break,
+        };
+        if pool.iter().any(|variant| variant.body == body) {
+            continue;
+        }
+        let mut mutations = parent.mutations.clone();
+        mutations.push(mutation);
+
+        match measure(
+            function,
+            setup,
+            &body,
+            mutations,
+            pricing,
+            config.repetitions,
+        ) {
+            Ok(variant) => {
+                pool.push(variant);
+                pool.sort_by(by_cost);
+                pool.truncate(POOL_SIZE);
+            }
+            Err(_) => rejected += 1,
+        }
+    }
+
+    let retime = |variant: Variant| -> Result<Variant, String> {
+        let (nanoseconds, spread) = time(setup, &variant.body, config.repetitions)?;
+        Ok(Variant {
+            nanoseconds,
+            spread,
+            ..variant
+        })
+    };
+    let original = retime(original)?;
+    let mut worst = pool
+        .into_iter()
+        .map(retime)
+        .collect::<Result<Vec<_>, _>>()?;
+    worst.sort_by(by_cost);
+
+    Ok(SearchResult {
+        function,
+        input_size: output.input_size,
+        original,
+        worst,
+        rejected,
+    })
+}
+
+/// Writes the worst variants of `result` as a markdown table, relative to the
+/// generator's output.
+pub fn write_search_report<W: Write>(out: &mut W, result: &SearchResult) -> io::Result<()> {
+    let original = result.original.nanoseconds_per_unit();
+    writeln!(
+        out,
+        "{} at input size {}: {} variants rejected",
+        result.function, result.input_size, result.rejected
+    )?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "| Rank | ns | IQR ns | Charges | Mean input | Cost units | ns per unit | vs. generated | Mutations |"
+    )?;
+    writeln!(
+        out,
+        "| ----------- | ----------- | ----------- | ----------- | ----------- | ----------- | ----------- | ----------- | ----------- |"
+    )?;
+    let rows = std::iter::once(("generated".to_string(), &result.original)).chain(
+        result
+            .worst
+            .iter()
+            .enumerate()
+            .map(|(i, variant)| ((i + 1).to_string(), variant)),
+    );
+    for (rank, variant) in rows {
+        let mutations = variant
+            .mutations
+            .iter()
+            .map(Mutation::to_string)
+            .collect::<Vec<_>>()
+            .join("; ");
+        writeln!(
+            out,
+            "| {} | {:.0} | {:.0} | {} | {:.1} | {:.0} | {:.3} | {:.2}x | {} |",
+            rank,
+            variant.nanoseconds,
+            variant.spread,
+            variant.charges.count,
+            variant.charges.mean_input().unwrap_or(0.0),
+            variant.cost_units,
+            variant.nanoseconds_per_unit(),
+            variant.nanoseconds_per_unit() / original,
+            mutations.replace('|', "\\|")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text<'a>(source: &'a str, span: &Span) -> &'a str {
+        &source[span.start..span.end]
+    }
+
+    #[test]
+    fn finds_literals_of_each_kind() {
+        let source = r#"(concat 0x0a0b "ab\"c" u"x") (+ u7 -3 10) ;; u9 0xff
+(sha256 0x)"#;
+        let sites = find_sites(source);
+        let literals: Vec<(&str, LiteralKind)> = sites
+            .literals
+            .iter()
+            .map(|(span, kind)| (text(source, span), *kind))
+            .collect();
+        assert_eq!(
+            literals,
+            [
+                ("0x0a0b", LiteralKind::Buffer),
+                (r#""ab\"c""#, LiteralKind::Ascii),
+                ("u7", LiteralKind::Uint),
+                ("-3", LiteralKind::Int),
+                ("10", LiteralKind::Int),
+                ("0x", LiteralKind::Buffer),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_argument_lists_with_two_or_more_arguments() {
+        let source = "(+ (* u1 u2) (f) u3) {a: 1, b: 2}";
+        let sites = find_sites(source);
+        let lists: Vec<Vec<&str>> = sites
+            .argument_lists
+            .iter()
+            .map(|elements| elements.iter().map(|span| text(source, span)).collect())
+            .collect();
+        // `(f)` has no arguments, and tuples aren't argument lists
+        assert_eq!(
+            lists,
+            [vec!["*", "u1", "u2"], vec!["+", "(* u1 u2)", "(f)", "u3"]]
+        );
+    }
+
+    #[test]
+    fn mutations_keep_buffer_and_string_lengths() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let (mutated, mutation) = mutate("(len 0x0a0b0c)", &mut rng).unwrap();
+            assert_eq!(mutated.len(), "(len 0x0a0b0c)".len());
+            assert!(matches!(mutation, Mutation::ReplaceLiteral { .. }));
+
+            let (mutated, _) = mutate(r#"(len "abc")"#, &mut rng).unwrap();
+            assert_eq!(mutated.len(), r#"(len "abc")"#.len());
+        }
+    }
+
+    #[test]
+    fn swaps_only_arguments_never_the_function_name() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let (mutated, mutation) = mutate("(f a b)", &mut rng).unwrap();
+            assert_eq!(mutated, "(f b a)");
+            assert_eq!(
+                mutation,
+                Mutation::SwapArguments {
+                    first: "a".to_string(),
+                    second: "b".to_string(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn mutate_is_deterministic_for_a_seed() {
+        let source = "(+ u1 u2 (* 3 4))";
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..10)
+                .map(|_| mutate(source, &mut rng).unwrap().0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+    }
+
+    #[test]
+    fn nothing_to_mutate() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(mutate("(f)", &mut rng), None);
+        assert_eq!(mutate(";; u1 0x00\n", &mut rng), None);
+    }
+}
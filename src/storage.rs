@@ -0,0 +1,199 @@
+//! Storage the benchmarks run against.
+//!
+//! By default benchmarks read and write a `MemoryBackingStore`. With the MARF
+//! backend they use a `MarfedKV` in a temporary directory instead, so data
+//! functions pay for trie reads and writes and the SQLite side store like a node.
+
+use blockstack_lib::clarity_vm::database::marf::MarfedKV;
+use blockstack_lib::clarity_vm::database::MemoryBackingStore;
+use blockstack_lib::types::chainstate::StacksBlockId;
+use blockstack_lib::types::proof::ClarityMarfTrieId;
+use blockstack_lib::vm::database::ClarityBackingStore;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Environment variable holding the name of the storage backend, see `StorageBackend`.
+pub const STORAGE_ENV_VAR: &str = "COST_BENCH_STORAGE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    Memory,
+    /// a MARF in a temporary directory, benchmarked in a child of the block holding setup
+    Marf,
+}
+
+impl StorageBackend {
+    pub const ALL: [StorageBackend; 2] = [StorageBackend::Memory, StorageBackend::Marf];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageBackend::Memory => "memory",
+            StorageBackend::Marf => "marf",
+        }
+    }
+
+    pub fn lookup_by_name(name: &str) -> Option<StorageBackend> {
+        StorageBackend::ALL
+            .iter()
+            .find(|backend| backend.name() == name)
+            .copied()
+    }
+
+    /// The backend named by `COST_BENCH_STORAGE`, or `Memory` if it is unset.
+    pub fn from_env() -> StorageBackend {
+        match env::var(STORAGE_ENV_VAR) {
+            Ok(name) => StorageBackend::lookup_by_name(&name).unwrap_or_else(|| {
+                panic!(
+                    "Invalid {}: '{}' is not one of memory, marf",
+                    STORAGE_ENV_VAR, name
+                )
+            }),
+            Err(_) => StorageBackend::Memory,
+        }
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A directory for one MARF, removed when dropped.
+struct TempMarfDir(PathBuf);
+
+impl TempMarfDir {
+    fn new() -> TempMarfDir {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "cost-bench-marf-{}-{}",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&path).expect("failed to create MARF directory");
+        TempMarfDir(path)
+    }
+}
+
+impl Drop for TempMarfDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Calls `f` with a new, empty store of `backend`.
+pub fn with_store<F, R>(backend: StorageBackend, f: F) -> R
+where
+    F: FnOnce(&mut dyn ClarityBackingStore) -> R,
+{
+    with_prepared_store(backend, |_| (), |store, _| f(store))
+}
+
+/// Calls `setup` with a new store of `backend`, then `f` with the store and what
+/// `setup` returned. On a MARF, `setup` writes a parent block that is committed
+/// before `f` runs in a child block, so `f` reads the setup through committed trie
+/// nodes and the side store like a node reading earlier blocks. A memory store
+/// has no blocks, so both run on the same store.
+pub fn with_prepared_store<S, T, F, R>(backend: StorageBackend, setup: S, f: F) -> R
+where
+    S: FnOnce(&mut dyn ClarityBackingStore) -> T,
+    F: FnOnce(&mut dyn ClarityBackingStore, T) -> R,
+{
+    match backend {
+        StorageBackend::Memory => {
+            let mut store = MemoryBackingStore::new();
+            let prepared = setup(&mut store);
+            f(&mut store, prepared)
+        }
+        StorageBackend::Marf => with_marf(setup, f),
+    }
+}
+
+/// Calls `f` with a MARF store whose parent block holds `entries`, as
+/// `with_prepared_store` does.
+pub fn with_committed_marf<F, R>(entries: Vec<(String, String)>, f: F) -> R
+where
+    F: FnOnce(&mut dyn ClarityBackingStore) -> R,
+{
+    with_marf(|store| store.put_all(entries), |store, _| f(store))
+}
+
+/// Commits what `setup` writes in a block on top of the sentinel, and calls `f` in
+/// an open child block. The child block is never committed: it is discarded with
+/// the MARF's directory afterwards.
+fn with_marf<S, T, F, R>(setup: S, f: F) -> R
+where
+    S: FnOnce(&mut dyn ClarityBackingStore) -> T,
+    F: FnOnce(&mut dyn ClarityBackingStore, T) -> R,
+{
+    let dir = TempMarfDir::new();
+    let mut marf = MarfedKV::open(dir.0.to_str().expect("non-UTF-8 temp directory"), None)
+        .expect("failed to open MARF");
+    let parent = StacksBlockId([1; 32]);
+    let mut store = marf.begin(&StacksBlockId::sentinel(), &parent);
+    let prepared = setup(&mut store);
+    store.commit();
+
+    let mut store = marf.begin(&parent, &StacksBlockId([2; 32]));
+    f(&mut store, prepared)
+}
+
+/// A MARF in a temporary directory that commits blocks one on top of another, as a
+/// node does. The MARF is deleted when this is dropped.
+pub struct MarfChain {
+    marf: MarfedKV,
+    tip: StacksBlockId,
+    height: u64,
+    // blocks committed so far, tips or not, numbering the next block's id
+    blocks: u64,
+    // dropped after the MARF, which still has the directory open until then
+    _dir: TempMarfDir,
+}
+
+impl MarfChain {
+    pub fn open() -> MarfChain {
+        let dir = TempMarfDir::new();
+        let marf = MarfedKV::open(dir.0.to_str().expect("non-UTF-8 temp directory"), None)
+            .expect("failed to open MARF");
+        MarfChain {
+            marf,
+            tip: StacksBlockId::sentinel(),
+            height: 0,
+            blocks: 0,
+            _dir: dir,
+        }
+    }
+
+    /// Number of blocks from the sentinel to the tip.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Writes `entries` in a new block on top of the last one, and commits it.
+    pub fn commit_block(&mut self, entries: Vec<(String, String)>) {
+        self.tip = self.commit_child(entries);
+        self.height += 1;
+    }
+
+    /// Writes `entries` in a new block on top of the last one and commits it, but
+    /// keeps the tip, so every call writes a fork at the same height.
+    pub fn commit_sibling(&mut self, entries: Vec<(String, String)>) {
+        self.commit_child(entries);
+    }
+
+    fn commit_child(&mut self, entries: Vec<(String, String)>) -> StacksBlockId {
+        self.blocks += 1;
+        let mut id = [0u8; 32];
+        id[..8].copy_from_slice(&self.blocks.to_be_bytes());
+        let block = StacksBlockId(id);
+
+        let mut store = self.marf.begin(&self.tip, &block);
+        store.put_all(entries);
+        store.commit();
+        block
+    }
+}
@@ -0,0 +1,236 @@
+//! Measures what storage reads and writes cost in wall time, for the read and
+//! write dimensions of the cost functions.
+//!
+//! The fitted cost functions only price runtime: `new_costs.clar` charges every
+//! function zero `read_count`, `read_length`, `write_count` and `write_length`
+//! beyond the special storage functions' fixed shapes. The `storage` bench target
+//! times reads and writes against a MARF store in two sweeps per operation: one
+//! over the byte length of each value at a fixed count, and one over the count
+//! of values at a fixed length. Fitting a line to each gives the time per byte
+//! and the time per operation, which are written to `storage_constants.csv` with
+//! the cost units and share of the block limit they correspond to.
+
+use crate::proposal::Scaling;
+use crate::regression::{fit, Model, Report};
+use blockstack_lib::core::BLOCK_LIMIT_MAINNET;
+use blockstack_lib::vm::database::ClaritySerializable;
+use blockstack_lib::vm::Value;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+/// Byte lengths of the values in the length sweep.
+pub const LENGTH_SWEEP: [u64; 7] = [16, 64, 256, 1024, 4096, 16384, 65536];
+
+/// Number of values read or written per iteration of the length sweep.
+pub const FIXED_COUNT: u64 = 16;
+
+/// Numbers of values read or written per iteration of the count sweep.
+pub const COUNT_SWEEP: [u64; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+/// Byte length of the values in the count sweep.
+pub const FIXED_LENGTH: u64 = 64;
+
+/// Serialized size of a buffer value, beyond its contents: a type prefix and a
+/// 4 byte length.
+const BUFFER_HEADER_LEN: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    Read,
+    Write,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 2] = [Operation::Read, Operation::Write];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Read => "read",
+            Operation::Write => "write",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sweep {
+    /// over the byte length of each value, at `FIXED_COUNT` values
+    Length,
+    /// over the number of values, each `FIXED_LENGTH` bytes
+    Count,
+}
+
+impl Sweep {
+    pub const ALL: [Sweep; 2] = [Sweep::Length, Sweep::Count];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sweep::Length => "length",
+            Sweep::Count => "count",
+        }
+    }
+
+    /// The swept sizes, which are the criterion input sizes.
+    pub fn sizes(&self) -> &'static [u64] {
+        match self {
+            Sweep::Length => &LENGTH_SWEEP,
+            Sweep::Count => &COUNT_SWEEP,
+        }
+    }
+
+    /// The number of values and their byte length at `size`.
+    pub fn count_and_length(&self, size: u64) -> (u64, u64) {
+        match self {
+            Sweep::Length => (FIXED_COUNT, size),
+            Sweep::Count => (size, FIXED_LENGTH),
+        }
+    }
+}
+
+/// Criterion group name of a sweep, e.g. `storage_read_length`.
+pub fn group_name(operation: Operation, sweep: Sweep) -> String {
+    format!("storage_{}_{}", operation, sweep.name())
+}
+
+/// `count` random entries, each a key and a serialized buffer value of `length`
+/// bytes as Clarity measures `read_length` and `write_length`. Values shorter than
+/// a buffer's header are empty buffers.
+pub fn entries(count: u64, length: u64, rng: &mut StdRng) -> Vec<(String, String)> {
+    (0..count)
+        .map(|i| {
+            let contents: Vec<u8> = (0..length.saturating_sub(BUFFER_HEADER_LEN))
+                .map(|_| rng.gen())
+                .collect();
+            let value = Value::buff_from(contents).expect("buffer is within the size limit");
+            (format!("cost-bench::storage::{}", i), value.serialize())
+        })
+        .collect()
+}
+
+/// `entries` for one point of a sweep, seeded by it so every run stores the same data.
+pub fn sweep_entries(sweep: Sweep, size: u64) -> Vec<(String, String)> {
+    let (count, length) = sweep.count_and_length(size);
+    entries(count, length, &mut StdRng::seed_from_u64(size))
+}
+
+/// Entries for one point of a sweep, for the `block`th block of the write sweep. Every
+/// block gets keys of its own and new values from `rng`, so none of its writes are
+/// to keys or values already stored.
+pub fn block_entries(
+    sweep: Sweep,
+    size: u64,
+    block: u64,
+    rng: &mut StdRng,
+) -> Vec<(String, String)> {
+    let (count, length) = sweep.count_and_length(size);
+    entries(count, length, rng)
+        .into_iter()
+        .map(|(key, value)| (format!("{}::{}", key, block), value))
+        .collect()
+}
+
+/// Fitted wall time of one operation's dimensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimensionFit {
+    /// nanoseconds per value read or written, beyond its bytes
+    pub ns_per_operation: f64,
+    /// nanoseconds per byte read or written
+    pub ns_per_byte: f64,
+}
+
+impl DimensionFit {
+    /// Runtime cost units that take as long as one operation, at `scaling`'s rate.
+    pub fn runtime_per_operation(&self, scaling: &Scaling) -> f64 {
+        self.ns_per_operation * scaling.runtime_per_ns()
+    }
+
+    pub fn runtime_per_byte(&self, scaling: &Scaling) -> f64 {
+        self.ns_per_byte * scaling.runtime_per_ns()
+    }
+
+    /// Seconds spent reaching `count_limit` operations of `length_limit` bytes in total.
+    pub fn seconds_at_limits(&self, count_limit: u64, length_limit: u64) -> (f64, f64) {
+        (
+            self.ns_per_operation * count_limit as f64 / 1e9,
+            self.ns_per_byte * length_limit as f64 / 1e9,
+        )
+    }
+}
+
+/// Mainnet block limits on the count and length of `operation`.
+pub fn block_limits(operation: Operation) -> (u64, u64) {
+    match operation {
+        Operation::Read => (
+            BLOCK_LIMIT_MAINNET.read_count,
+            BLOCK_LIMIT_MAINNET.read_length,
+        ),
+        Operation::Write => (
+            BLOCK_LIMIT_MAINNET.write_count,
+            BLOCK_LIMIT_MAINNET.write_length,
+        ),
+    }
+}
+
+/// Fits the storage results in `report`. The length sweep's slope over
+/// `FIXED_COUNT` is the time per byte; the count sweep's slope, less the time of
+/// `FIXED_LENGTH` bytes, is the time per operation. Operations without results for
+/// both sweeps are left out.
+pub fn fit_dimensions(report: &Report) -> BTreeMap<Operation, DimensionFit> {
+    let mut fits = BTreeMap::new();
+    for operation in Operation::ALL.iter() {
+        let length = report.get(&group_name(*operation, Sweep::Length));
+        let count = report.get(&group_name(*operation, Sweep::Count));
+        if let (Some(length), Some(count)) = (length, count) {
+            let ns_per_byte = (fit(Model::Linear, length).a / FIXED_COUNT as f64).max(0.0);
+            let ns_per_operation =
+                (fit(Model::Linear, count).a - ns_per_byte * FIXED_LENGTH as f64).max(0.0);
+            fits.insert(
+                *operation,
+                DimensionFit {
+                    ns_per_operation,
+                    ns_per_byte,
+                },
+            );
+        }
+    }
+    fits
+}
+
+/// Writes fits in the format of `storage_constants.csv`: the time per operation
+/// and per byte, the runtime they correspond to at `scaling`'s rate, and the
+/// seconds it takes to reach the block's count and length limits.
+pub fn write_storage_constants<W: Write>(
+    out: &mut W,
+    fits: &BTreeMap<Operation, DimensionFit>,
+    scaling: &Scaling,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "operation,ns_per_operation,ns_per_byte,runtime_per_operation,runtime_per_byte,seconds_at_count_limit,seconds_at_length_limit"
+    )?;
+    for (operation, fit) in fits {
+        let (count_limit, length_limit) = block_limits(*operation);
+        let (count_seconds, length_seconds) = fit.seconds_at_limits(count_limit, length_limit);
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            operation,
+            fit.ns_per_operation,
+            fit.ns_per_byte,
+            fit.runtime_per_operation(scaling),
+            fit.runtime_per_byte(scaling),
+            count_seconds,
+            length_seconds
+        )?;
+    }
+    Ok(())
+}